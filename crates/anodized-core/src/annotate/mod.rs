@@ -1,11 +1,15 @@
+use quote::ToTokens;
 use syn::{
-    Attribute, Expr, Ident, Meta, Pat, PatIdent,
+    Attribute, Expr, ExprLit, Ident, Lit, LitInt, LitStr, Meta, Pat, PatIdent, Token,
     parse::{Parse, ParseStream, Result},
     parse_quote,
     spanned::Spanned,
 };
 
-use crate::{Capture, PostCondition, PreCondition, Spec, annotate::syntax::CaptureExpr};
+use crate::{
+    Capture, Mode, PostCondition, PreCondition, Sample, Severity, Spec,
+    annotate::syntax::CaptureExpr,
+};
 
 pub mod syntax;
 use syntax::{Captures, Keyword};
@@ -18,6 +22,7 @@ impl Parse for Spec {
         let raw_spec = syntax::SpecArgs::parse(input)?;
 
         let mut prev_keyword: Option<Keyword> = None;
+        let mut mode: Option<Mode> = None;
         let mut requires: Vec<PreCondition> = vec![];
         let mut maintains: Vec<PreCondition> = vec![];
         let mut captures: Vec<Capture> = vec![];
@@ -26,58 +31,80 @@ impl Parse for Spec {
 
         for arg in raw_spec.args {
             match &arg.keyword {
+                Keyword::Mode => {
+                    reject_attrs(&arg.attrs, &arg.keyword)?;
+                    if mode.is_some() {
+                        return Err(syn::Error::new(
+                            arg.keyword_span,
+                            "multiple `mode` parameters are not allowed",
+                        ));
+                    }
+                    mode = Some(parse_mode(arg.value.try_into_expr()?)?);
+                }
                 Keyword::Requires => {
-                    let cfg_attr = find_cfg_attribute(&arg.attrs)?;
-                    let cfg: Option<Meta> = if let Some(attr) = cfg_attr {
-                        Some(attr.parse_args()?)
-                    } else {
-                        None
-                    };
+                    let ConditionAttrs {
+                        cfg,
+                        label,
+                        severity,
+                        sample,
+                        expensive,
+                    } = parse_condition_attrs(&arg.attrs)?;
                     let expr = arg.value.try_into_expr()?;
                     if let Expr::Array(conditions) = expr {
                         for expr in conditions.elems {
                             requires.push(PreCondition {
                                 closure: interpret_expr_as_precondition(expr)?,
                                 cfg: cfg.clone(),
+                                label: label.clone(),
+                                severity,
+                                sample,
+                                expensive,
                             });
                         }
                     } else {
                         requires.push(PreCondition {
                             closure: interpret_expr_as_precondition(expr)?,
                             cfg,
+                            label,
+                            severity,
+                            sample,
+                            expensive,
                         });
                     }
                 }
                 Keyword::Maintains => {
-                    let cfg_attr = find_cfg_attribute(&arg.attrs)?;
-                    let cfg: Option<Meta> = if let Some(attr) = cfg_attr {
-                        Some(attr.parse_args()?)
-                    } else {
-                        None
-                    };
+                    let ConditionAttrs {
+                        cfg,
+                        label,
+                        severity,
+                        sample,
+                        expensive,
+                    } = parse_condition_attrs(&arg.attrs)?;
                     let expr = arg.value.try_into_expr()?;
                     if let Expr::Array(conditions) = expr {
                         for expr in conditions.elems {
                             maintains.push(PreCondition {
                                 closure: interpret_expr_as_precondition(expr)?,
                                 cfg: cfg.clone(),
+                                label: label.clone(),
+                                severity,
+                                sample,
+                                expensive,
                             });
                         }
                     } else {
                         maintains.push(PreCondition {
                             closure: interpret_expr_as_precondition(expr)?,
                             cfg,
+                            label,
+                            severity,
+                            sample,
+                            expensive,
                         });
                     }
                 }
                 Keyword::Captures => {
-                    let cfg_attr = find_cfg_attribute(&arg.attrs)?;
-                    if cfg_attr.is_some() {
-                        return Err(syn::Error::new(
-                            cfg_attr.span(),
-                            "`cfg` attribute is not supported on `captures`",
-                        ));
-                    }
+                    reject_attrs(&arg.attrs, &arg.keyword)?;
                     if !captures.is_empty() {
                         return Err(syn::Error::new(
                             arg.keyword_span,
@@ -98,13 +125,7 @@ impl Parse for Spec {
                     }
                 }
                 Keyword::Binds => {
-                    let cfg_attr = find_cfg_attribute(&arg.attrs)?;
-                    if cfg_attr.is_some() {
-                        return Err(syn::Error::new(
-                            cfg_attr.span(),
-                            "`cfg` attribute is not supported on `binds`",
-                        ));
-                    }
+                    reject_attrs(&arg.attrs, &arg.keyword)?;
                     if binds_pattern.is_some() {
                         return Err(syn::Error::new(
                             arg.keyword_span,
@@ -115,12 +136,13 @@ impl Parse for Spec {
                     binds_pattern = Some(pattern);
                 }
                 Keyword::Ensures => {
-                    let cfg_attr = find_cfg_attribute(&arg.attrs)?;
-                    let cfg: Option<Meta> = if let Some(attr) = cfg_attr {
-                        Some(attr.parse_args()?)
-                    } else {
-                        None
-                    };
+                    let ConditionAttrs {
+                        cfg,
+                        label,
+                        severity,
+                        sample,
+                        expensive,
+                    } = parse_condition_attrs(&arg.attrs)?;
                     let expr = arg.value.try_into_expr()?;
                     let default_pattern = binds_pattern.clone().unwrap_or(parse_quote! { output });
                     if let Expr::Array(conditions) = expr {
@@ -131,12 +153,20 @@ impl Parse for Spec {
                                     default_pattern.clone(),
                                 )?,
                                 cfg: cfg.clone(),
+                                label: label.clone(),
+                                severity,
+                                sample,
+                                expensive,
                             });
                         }
                     } else {
                         ensures.push(PostCondition {
                             closure: interpret_expr_as_postcondition(expr, default_pattern)?,
                             cfg,
+                            label,
+                            severity,
+                            sample,
+                            expensive,
                         });
                     }
                 }
@@ -153,13 +183,14 @@ impl Parse for Spec {
             {
                 return Err(syn::Error::new(
                     arg.keyword_span,
-                    "parameters are out of order: their order must be `requires`, `maintains`, `captures`, `binds`, `ensures`",
+                    "parameters are out of order: their order must be `mode`, `requires`, `maintains`, `captures`, `binds`, `ensures`",
                 ));
             }
             prev_keyword = Some(arg.keyword);
         }
 
         Ok(Spec {
+            mode,
             requires,
             maintains,
             captures,
@@ -294,25 +325,135 @@ fn interpret_expr_as_postcondition(expr: Expr, default_binding: Pat) -> Result<s
     }
 }
 
-fn find_cfg_attribute(attrs: &[Attribute]) -> Result<Option<&Attribute>> {
-    let mut cfg_attr: Option<&Attribute> = None;
+/// Interpret the value of a `mode` parameter, which must name a mode, e.g. `print`.
+fn parse_mode(expr: Expr) -> Result<Mode> {
+    if let Expr::Path(path) = &expr
+        && let Some(ident) = path.path.get_ident()
+        && let Some(mode) = Mode::from_name(&ident.to_string())
+    {
+        return Ok(mode);
+    }
+    Err(syn::Error::new_spanned(
+        expr,
+        "expected a mode: `panic`, `print`, `log`, `trace`, `trap`, or `no_check`",
+    ))
+}
+
+/// Settings given as attributes on a condition parameter.
+#[derive(Default)]
+struct ConditionAttrs {
+    cfg: Option<Meta>,
+    label: Option<LitStr>,
+    severity: Option<Severity>,
+    sample: Option<Sample>,
+    expensive: bool,
+}
+
+fn parse_condition_attrs(attrs: &[Attribute]) -> Result<ConditionAttrs> {
+    let mut condition_attrs = ConditionAttrs::default();
 
     for attr in attrs {
         if attr.path().is_ident("cfg") {
-            if cfg_attr.is_some() {
+            if condition_attrs.cfg.is_some() {
                 return Err(syn::Error::new(
                     attr.span(),
                     "multiple `cfg` attributes are not supported",
                 ));
             }
-            cfg_attr = Some(attr);
+            condition_attrs.cfg = Some(attr.parse_args()?);
+        } else if attr.path().is_ident("label") {
+            if condition_attrs.label.is_some() {
+                return Err(syn::Error::new(
+                    attr.span(),
+                    "multiple `label` attributes are not supported",
+                ));
+            }
+            condition_attrs.label = Some(parse_label(attr)?);
+        } else if attr.path().is_ident("warn") || attr.path().is_ident("deny") {
+            if condition_attrs.severity.is_some() {
+                return Err(syn::Error::new(
+                    attr.span(),
+                    "multiple severity attributes are not supported; use either `warn` or `deny`",
+                ));
+            }
+            attr.meta.require_path_only()?;
+            condition_attrs.severity = Some(if attr.path().is_ident("warn") {
+                Severity::Warn
+            } else {
+                Severity::Deny
+            });
+        } else if attr.path().is_ident("sample") {
+            if condition_attrs.sample.is_some() {
+                return Err(syn::Error::new(
+                    attr.span(),
+                    "multiple `sample` attributes are not supported",
+                ));
+            }
+            condition_attrs.sample = Some(parse_sample(attr)?);
+        } else if attr.path().is_ident("expensive") {
+            if condition_attrs.expensive {
+                return Err(syn::Error::new(
+                    attr.span(),
+                    "multiple `expensive` attributes are not supported",
+                ));
+            }
+            attr.meta.require_path_only()?;
+            condition_attrs.expensive = true;
         } else {
             return Err(syn::Error::new(
                 attr.span(),
-                "unsupported attribute; only `cfg` is allowed",
+                "unsupported attribute; only `cfg`, `label`, `warn`, `deny`, `sample`, and `expensive` are allowed",
             ));
         }
     }
 
-    Ok(cfg_attr)
+    Ok(condition_attrs)
+}
+
+/// Parse `#[label = "..."]` into its string literal.
+fn parse_label(attr: &Attribute) -> Result<LitStr> {
+    match &attr.meta.require_name_value()?.value {
+        Expr::Lit(ExprLit {
+            lit: Lit::Str(label),
+            ..
+        }) => Ok(label.clone()),
+        value => Err(syn::Error::new_spanned(
+            value,
+            r#"expected a string literal: `#[label = "..."]`"#,
+        )),
+    }
+}
+
+/// Parse `#[sample(count in period)]`, e.g. `#[sample(1 in 1000)]`.
+fn parse_sample(attr: &Attribute) -> Result<Sample> {
+    attr.parse_args_with(|input: ParseStream| {
+        let count: LitInt = input.parse()?;
+        input.parse::<Token![in]>()?;
+        let period: LitInt = input.parse()?;
+        let sample = Sample {
+            count: count.base10_parse()?,
+            period: period.base10_parse()?,
+        };
+        if sample.count == 0 || sample.count > sample.period {
+            return Err(syn::Error::new(
+                attr.span(),
+                "the sample must be a share of calls: `#[sample(count in period)]` with `0 < count <= period`",
+            ));
+        }
+        Ok(sample)
+    })
+}
+
+/// Fail on any attribute, for parameters that don't take settings.
+fn reject_attrs(attrs: &[Attribute], keyword: &Keyword) -> Result<()> {
+    match attrs.first() {
+        Some(attr) => Err(syn::Error::new(
+            attr.span(),
+            format!(
+                "`{}` attribute is not supported on `{keyword}`",
+                attr.path().to_token_stream()
+            ),
+        )),
+        None => Ok(()),
+    }
 }
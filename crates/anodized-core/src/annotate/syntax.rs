@@ -233,6 +233,7 @@ impl Parse for CaptureExpr {
 /// Custom keywords for parsing. This allows us to use `requires`, `ensures`, etc.,
 /// as if they were built-in Rust keywords during parsing.
 pub mod kw {
+    syn::custom_keyword!(mode);
     syn::custom_keyword!(requires);
     syn::custom_keyword!(maintains);
     syn::custom_keyword!(captures);
@@ -243,6 +244,7 @@ pub mod kw {
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
 pub enum Keyword {
     Unknown(Ident),
+    Mode,
     Requires,
     Maintains,
     Captures,
@@ -253,7 +255,10 @@ pub enum Keyword {
 impl Keyword {
     fn parse(input: ParseStream) -> Result<(Self, Span)> {
         use Keyword::*;
-        Ok(if input.peek(kw::requires) {
+        Ok(if input.peek(kw::mode) {
+            let token: kw::mode = input.parse()?;
+            (Mode, token.span)
+        } else if input.peek(kw::requires) {
             let keyword: kw::requires = input.parse()?;
             (Requires, keyword.span)
         } else if input.peek(kw::maintains) {
@@ -279,6 +284,7 @@ impl Keyword {
 impl std::fmt::Display for Keyword {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            Keyword::Mode => write!(f, "mode"),
             Keyword::Requires => write!(f, "requires"),
             Keyword::Maintains => write!(f, "maintains"),
             Keyword::Captures => write!(f, "captures"),
@@ -12,15 +12,24 @@ fn simple_spec() {
     };
 
     let expected = Spec {
+        mode: None,
         requires: vec![PreCondition {
             closure: parse_quote! { || is_valid(x) },
             cfg: None,
+            label: None,
+            severity: None,
+            sample: None,
+            expensive: false,
         }],
         maintains: vec![],
         captures: vec![],
         ensures: vec![PostCondition {
             closure: parse_quote! { |output| output > x },
             cfg: None,
+            label: None,
+            severity: None,
+            sample: None,
+            expensive: false,
         }],
         span: Span::call_site(),
     };
@@ -38,18 +47,31 @@ fn all_clauses() {
     };
 
     let expected = Spec {
+        mode: None,
         requires: vec![PreCondition {
             closure: parse_quote! { || x > 0 && x.is_power_of_two() },
             cfg: None,
+            label: None,
+            severity: None,
+            sample: None,
+            expensive: false,
         }],
         maintains: vec![PreCondition {
             closure: parse_quote! { || self.is_valid() },
             cfg: None,
+            label: None,
+            severity: None,
+            sample: None,
+            expensive: false,
         }],
         captures: vec![],
         ensures: vec![PostCondition {
             closure: parse_quote! { |z| z >= x },
             cfg: None,
+            label: None,
+            severity: None,
+            sample: None,
+            expensive: false,
         }],
         span: Span::call_site(),
     };
@@ -110,14 +132,23 @@ fn array_of_conditions() {
     };
 
     let expected = Spec {
+        mode: None,
         requires: vec![
             PreCondition {
                 closure: parse_quote! { || x >= 0 },
                 cfg: None,
+                label: None,
+                severity: None,
+                sample: None,
+                expensive: false,
             },
             PreCondition {
                 closure: parse_quote! { || y.len() < 10 },
                 cfg: None,
+                label: None,
+                severity: None,
+                sample: None,
+                expensive: false,
             },
         ],
         maintains: vec![],
@@ -126,10 +157,18 @@ fn array_of_conditions() {
             PostCondition {
                 closure: parse_quote! { |output| output != x },
                 cfg: None,
+                label: None,
+                severity: None,
+                sample: None,
+                expensive: false,
             },
             PostCondition {
                 closure: parse_quote! { |output| output.is_some() },
                 cfg: None,
+                label: None,
+                severity: None,
+                sample: None,
+                expensive: false,
             },
         ],
         span: Span::call_site(),
@@ -145,12 +184,17 @@ fn ensures_with_explicit_closure() {
     };
 
     let expected = Spec {
+        mode: None,
         requires: vec![],
         maintains: vec![],
         captures: vec![],
         ensures: vec![PostCondition {
             closure: parse_quote! { |result| result.is_ok() || result.unwrap_err().kind() == ErrorKind::NotFound },
             cfg: None,
+            label: None,
+            severity: None,
+            sample: None,
+            expensive: false,
         }],
         span: Span::call_site(),
     };
@@ -168,14 +212,23 @@ fn multiple_clauses_of_same_flavor() {
     };
 
     let expected = Spec {
+        mode: None,
         requires: vec![
             PreCondition {
                 closure: parse_quote! { || x > 0 || x < -10 },
                 cfg: None,
+                label: None,
+                severity: None,
+                sample: None,
+                expensive: false,
             },
             PreCondition {
                 closure: parse_quote! { || y.is_ascii() },
                 cfg: None,
+                label: None,
+                severity: None,
+                sample: None,
+                expensive: false,
             },
         ],
         maintains: vec![],
@@ -184,10 +237,18 @@ fn multiple_clauses_of_same_flavor() {
             PostCondition {
                 closure: parse_quote! { |output| output < x },
                 cfg: None,
+                label: None,
+                severity: None,
+                sample: None,
+                expensive: false,
             },
             PostCondition {
                 closure: parse_quote! { |output| output.len() >= y.len() },
                 cfg: None,
+                label: None,
+                severity: None,
+                sample: None,
+                expensive: false,
             },
         ],
         span: Span::call_site(),
@@ -212,18 +273,31 @@ fn mixed_single_and_array_clauses() {
     };
 
     let expected = Spec {
+        mode: None,
         requires: vec![
             PreCondition {
                 closure: parse_quote! { || x == 0 },
                 cfg: None,
+                label: None,
+                severity: None,
+                sample: None,
+                expensive: false,
             },
             PreCondition {
                 closure: parse_quote! { || y > 1 },
                 cfg: None,
+                label: None,
+                severity: None,
+                sample: None,
+                expensive: false,
             },
             PreCondition {
                 closure: parse_quote! { || z.is_empty() || z.contains("foo") },
                 cfg: None,
+                label: None,
+                severity: None,
+                sample: None,
+                expensive: false,
             },
         ],
         maintains: vec![],
@@ -232,14 +306,26 @@ fn mixed_single_and_array_clauses() {
             PostCondition {
                 closure: parse_quote! { |output| output != y },
                 cfg: None,
+                label: None,
+                severity: None,
+                sample: None,
+                expensive: false,
             },
             PostCondition {
                 closure: parse_quote! { |output| output.starts_with(z) },
                 cfg: None,
+                label: None,
+                severity: None,
+                sample: None,
+                expensive: false,
             },
             PostCondition {
                 closure: parse_quote! { |output| output.len() > x },
                 cfg: None,
+                label: None,
+                severity: None,
+                sample: None,
+                expensive: false,
             },
         ],
         span: Span::call_site(),
@@ -258,15 +344,24 @@ fn cfg_attributes() {
     };
 
     let expected = Spec {
+        mode: None,
         requires: vec![PreCondition {
             closure: parse_quote! { || x > 0 && is_mode() },
             cfg: Some(parse_quote! { test }),
+            label: None,
+            severity: None,
+            sample: None,
+            expensive: false,
         }],
         maintains: vec![],
         captures: vec![],
         ensures: vec![PostCondition {
             closure: parse_quote! { |output| output < x },
             cfg: Some(parse_quote! { not(debug_assertions) }),
+            label: None,
+            severity: None,
+            sample: None,
+            expensive: false,
         }],
         span: Span::call_site(),
     };
@@ -275,7 +370,9 @@ fn cfg_attributes() {
 }
 
 #[test]
-#[should_panic(expected = "unsupported attribute; only `cfg` is allowed")]
+#[should_panic(
+    expected = "unsupported attribute; only `cfg`, `label`, `warn`, `deny`, `sample`, and `expensive` are allowed"
+)]
 fn non_cfg_attribute() {
     let _: Spec = parse_quote! {
         #[allow(dead_code)]
@@ -302,6 +399,319 @@ fn cfg_on_binds() {
     };
 }
 
+#[test]
+fn labels() {
+    let spec: Spec = parse_quote! {
+        #[label = "x must be positive"]
+        requires: x > 0,
+        #[cfg(test)]
+        #[label = "bounds"]
+        ensures: [output > x, output < 100],
+    };
+
+    let expected = Spec {
+        mode: None,
+        requires: vec![PreCondition {
+            closure: parse_quote! { || x > 0 },
+            cfg: None,
+            label: Some(parse_quote! { "x must be positive" }),
+            severity: None,
+            sample: None,
+            expensive: false,
+        }],
+        maintains: vec![],
+        captures: vec![],
+        ensures: vec![
+            PostCondition {
+                closure: parse_quote! { |output| output > x },
+                cfg: Some(parse_quote! { test }),
+                label: Some(parse_quote! { "bounds" }),
+                severity: None,
+                sample: None,
+                expensive: false,
+            },
+            PostCondition {
+                closure: parse_quote! { |output| output < 100 },
+                cfg: Some(parse_quote! { test }),
+                label: Some(parse_quote! { "bounds" }),
+                severity: None,
+                sample: None,
+                expensive: false,
+            },
+        ],
+        span: Span::call_site(),
+    };
+
+    assert_spec_eq(&spec, &expected);
+}
+
+#[test]
+#[should_panic(expected = "expected a string literal")]
+fn label_not_a_string() {
+    let _: Spec = parse_quote! {
+        #[label = 42]
+        requires: x > 0,
+    };
+}
+
+#[test]
+#[should_panic(expected = "multiple `label` attributes are not supported")]
+fn multiple_labels() {
+    let _: Spec = parse_quote! {
+        #[label = "first"]
+        #[label = "second"]
+        requires: x > 0,
+    };
+}
+
+#[test]
+#[should_panic(expected = "`label` attribute is not supported on `binds`")]
+fn label_on_binds() {
+    let _: Spec = parse_quote! {
+        #[label = "result"]
+        binds: y,
+    };
+}
+
+#[test]
+fn mode() {
+    let spec: Spec = parse_quote! {
+        mode: print,
+        requires: x > 0,
+    };
+
+    let expected = Spec {
+        mode: Some(Mode::Print),
+        requires: vec![PreCondition {
+            closure: parse_quote! { || x > 0 },
+            cfg: None,
+            label: None,
+            severity: None,
+            sample: None,
+            expensive: false,
+        }],
+        maintains: vec![],
+        captures: vec![],
+        ensures: vec![],
+        span: Span::call_site(),
+    };
+
+    assert_spec_eq(&spec, &expected);
+}
+
+#[test]
+#[should_panic(expected = "expected a mode")]
+fn unknown_mode() {
+    let _: Spec = parse_quote! {
+        mode: explode,
+        requires: x > 0,
+    };
+}
+
+#[test]
+#[should_panic(expected = "multiple `mode` parameters are not allowed")]
+fn multiple_modes() {
+    let _: Spec = parse_quote! {
+        mode: print,
+        mode: panic,
+    };
+}
+
+#[test]
+#[should_panic(expected = "parameters are out of order")]
+fn mode_out_of_order() {
+    let _: Spec = parse_quote! {
+        requires: x > 0,
+        mode: print,
+    };
+}
+
+#[test]
+fn severities() {
+    let spec: Spec = parse_quote! {
+        #[deny]
+        requires: x > 0,
+        #[warn]
+        #[label = "soft"]
+        ensures: [output > x, output < 100],
+    };
+
+    let expected = Spec {
+        mode: None,
+        requires: vec![PreCondition {
+            closure: parse_quote! { || x > 0 },
+            cfg: None,
+            label: None,
+            severity: Some(Severity::Deny),
+            sample: None,
+            expensive: false,
+        }],
+        maintains: vec![],
+        captures: vec![],
+        ensures: vec![
+            PostCondition {
+                closure: parse_quote! { |output| output > x },
+                cfg: None,
+                label: Some(parse_quote! { "soft" }),
+                severity: Some(Severity::Warn),
+                sample: None,
+                expensive: false,
+            },
+            PostCondition {
+                closure: parse_quote! { |output| output < 100 },
+                cfg: None,
+                label: Some(parse_quote! { "soft" }),
+                severity: Some(Severity::Warn),
+                sample: None,
+                expensive: false,
+            },
+        ],
+        span: Span::call_site(),
+    };
+
+    assert_spec_eq(&spec, &expected);
+}
+
+#[test]
+#[should_panic(expected = "multiple severity attributes are not supported")]
+fn multiple_severities() {
+    let _: Spec = parse_quote! {
+        #[warn]
+        #[deny]
+        requires: x > 0,
+    };
+}
+
+#[test]
+#[should_panic(expected = "unexpected token in attribute")]
+fn severity_with_arguments() {
+    let _: Spec = parse_quote! {
+        #[deny(always)]
+        requires: x > 0,
+    };
+}
+
+#[test]
+fn samples() {
+    let spec: Spec = parse_quote! {
+        #[sample(1 in 1000)]
+        requires: x > 0,
+        #[sample(3 in 4)]
+        ensures: output > x,
+    };
+
+    let expected = Spec {
+        mode: None,
+        requires: vec![PreCondition {
+            closure: parse_quote! { || x > 0 },
+            cfg: None,
+            label: None,
+            severity: None,
+            sample: Some(Sample {
+                count: 1,
+                period: 1000,
+            }),
+            expensive: false,
+        }],
+        maintains: vec![],
+        captures: vec![],
+        ensures: vec![PostCondition {
+            closure: parse_quote! { |output| output > x },
+            cfg: None,
+            label: None,
+            severity: None,
+            sample: Some(Sample {
+                count: 3,
+                period: 4,
+            }),
+            expensive: false,
+        }],
+        span: Span::call_site(),
+    };
+
+    assert_spec_eq(&spec, &expected);
+}
+
+#[test]
+#[should_panic(expected = "the sample must be a share of calls")]
+fn sample_larger_than_period() {
+    let _: Spec = parse_quote! {
+        #[sample(2 in 1)]
+        requires: x > 0,
+    };
+}
+
+#[test]
+#[should_panic(expected = "the sample must be a share of calls")]
+fn empty_sample() {
+    let _: Spec = parse_quote! {
+        #[sample(0 in 10)]
+        requires: x > 0,
+    };
+}
+
+#[test]
+#[should_panic(expected = "expected `in`")]
+fn sample_without_period() {
+    let _: Spec = parse_quote! {
+        #[sample(1)]
+        requires: x > 0,
+    };
+}
+
+#[test]
+fn expensive() {
+    let spec: Spec = parse_quote! {
+        #[expensive]
+        requires: is_sorted(values),
+        ensures: output > 0,
+    };
+
+    let expected = Spec {
+        mode: None,
+        requires: vec![PreCondition {
+            closure: parse_quote! { || is_sorted(values) },
+            cfg: None,
+            label: None,
+            severity: None,
+            sample: None,
+            expensive: true,
+        }],
+        maintains: vec![],
+        captures: vec![],
+        ensures: vec![PostCondition {
+            closure: parse_quote! { |output| output > 0 },
+            cfg: None,
+            label: None,
+            severity: None,
+            sample: None,
+            expensive: false,
+        }],
+        span: Span::call_site(),
+    };
+
+    assert_spec_eq(&spec, &expected);
+}
+
+#[test]
+#[should_panic(expected = "multiple `expensive` attributes are not supported")]
+fn multiple_expensive() {
+    let _: Spec = parse_quote! {
+        #[expensive]
+        #[expensive]
+        requires: is_sorted(values),
+    };
+}
+
+#[test]
+#[should_panic(expected = "unexpected token in attribute")]
+fn expensive_with_arguments() {
+    let _: Spec = parse_quote! {
+        #[expensive(very)]
+        requires: is_sorted(values),
+    };
+}
+
 #[test]
 fn macro_in_condition() {
     let spec: Spec = parse_quote! {
@@ -311,18 +721,31 @@ fn macro_in_condition() {
     };
 
     let expected = Spec {
+        mode: None,
         requires: vec![PreCondition {
             closure: parse_quote! { || matches!(self.state, State::Idle) },
             cfg: None,
+            label: None,
+            severity: None,
+            sample: None,
+            expensive: false,
         }],
         maintains: vec![PreCondition {
             closure: parse_quote! { || matches!(self.state, State::Idle | State::Running | State::Finished) },
             cfg: None,
+            label: None,
+            severity: None,
+            sample: None,
+            expensive: false,
         }],
         captures: vec![],
         ensures: vec![PostCondition {
             closure: parse_quote! { |output| matches!(self.state, State::Running) },
             cfg: None,
+            label: None,
+            severity: None,
+            sample: None,
+            expensive: false,
         }],
         span: Span::call_site(),
     };
@@ -341,6 +764,7 @@ fn binds_pattern() {
     };
 
     let expected = Spec {
+        mode: None,
         requires: vec![],
         maintains: vec![],
         captures: vec![],
@@ -348,10 +772,18 @@ fn binds_pattern() {
             PostCondition {
                 closure: parse_quote! { |(a, b)| a <= b },
                 cfg: None,
+                label: None,
+                severity: None,
+                sample: None,
+                expensive: false,
             },
             PostCondition {
                 closure: parse_quote! { |(a, b)| (a, b) == pair || (b, a) == pair },
                 cfg: None,
+                label: None,
+                severity: None,
+                sample: None,
+                expensive: false,
             },
         ],
         span: Span::call_site(),
@@ -372,23 +804,40 @@ fn multiple_conditions() {
     };
 
     let expected = Spec {
+        mode: None,
         requires: vec![
             PreCondition {
                 closure: parse_quote! { || self.initialized },
                 cfg: None,
+                label: None,
+                severity: None,
+                sample: None,
+                expensive: false,
             },
             PreCondition {
                 closure: parse_quote! { || !self.locked },
                 cfg: None,
+                label: None,
+                severity: None,
+                sample: None,
+                expensive: false,
             },
             PreCondition {
                 closure: parse_quote! { || index < self.items.len() },
                 cfg: None,
+                label: None,
+                severity: None,
+                sample: None,
+                expensive: false,
             },
         ],
         maintains: vec![PreCondition {
             closure: parse_quote! { || self.items.len() <= self.items.capacity() },
             cfg: None,
+            label: None,
+            severity: None,
+            sample: None,
+            expensive: false,
         }],
         captures: vec![],
         ensures: vec![],
@@ -409,6 +858,7 @@ fn rename_return_value() {
     };
 
     let expected = Spec {
+        mode: None,
         requires: vec![],
         maintains: vec![],
         captures: vec![],
@@ -416,10 +866,18 @@ fn rename_return_value() {
             PostCondition {
                 closure: parse_quote! { |result| result > output },
                 cfg: None,
+                label: None,
+                severity: None,
+                sample: None,
+                expensive: false,
             },
             PostCondition {
                 closure: parse_quote! { |val| val % 2 == 0 },
                 cfg: None,
+                label: None,
+                severity: None,
+                sample: None,
+                expensive: false,
             },
         ],
         span: Span::call_site(),
@@ -436,6 +894,7 @@ fn captures_simple_identifier() {
     };
 
     let expected = Spec {
+        mode: None,
         requires: vec![],
         maintains: vec![],
         captures: vec![Capture {
@@ -445,6 +904,10 @@ fn captures_simple_identifier() {
         ensures: vec![PostCondition {
             closure: parse_quote! { |output| output == old_count + 1 },
             cfg: None,
+            label: None,
+            severity: None,
+            sample: None,
+            expensive: false,
         }],
         span: Span::call_site(),
     };
@@ -460,6 +923,7 @@ fn captures_identifier_with_alias() {
     };
 
     let expected = Spec {
+        mode: None,
         requires: vec![],
         maintains: vec![],
         captures: vec![Capture {
@@ -469,6 +933,10 @@ fn captures_identifier_with_alias() {
         ensures: vec![PostCondition {
             closure: parse_quote! { |output| output > prev_value },
             cfg: None,
+            label: None,
+            severity: None,
+            sample: None,
+            expensive: false,
         }],
         span: Span::call_site(),
     };
@@ -492,6 +960,7 @@ fn captures_array() {
     };
 
     let expected = Spec {
+        mode: None,
         requires: vec![],
         maintains: vec![],
         captures: vec![
@@ -512,14 +981,26 @@ fn captures_array() {
             PostCondition {
                 closure: parse_quote! { |output| count == old_count + 1 },
                 cfg: None,
+                label: None,
+                severity: None,
+                sample: None,
+                expensive: false,
             },
             PostCondition {
                 closure: parse_quote! { |output| index == old_index + 1 },
                 cfg: None,
+                label: None,
+                severity: None,
+                sample: None,
+                expensive: false,
             },
             PostCondition {
                 closure: parse_quote! { |output| value > old_value },
                 cfg: None,
+                label: None,
+                severity: None,
+                sample: None,
+                expensive: false,
             },
         ],
         span: Span::call_site(),
@@ -539,13 +1020,22 @@ fn captures_with_all_clauses() {
     };
 
     let expected = Spec {
+        mode: None,
         requires: vec![PreCondition {
             closure: parse_quote! { || x > 0 },
             cfg: None,
+            label: None,
+            severity: None,
+            sample: None,
+            expensive: false,
         }],
         maintains: vec![PreCondition {
             closure: parse_quote! { || self.is_valid() },
             cfg: None,
+            label: None,
+            severity: None,
+            sample: None,
+            expensive: false,
         }],
         captures: vec![Capture {
             expr: parse_quote! { value },
@@ -554,6 +1044,10 @@ fn captures_with_all_clauses() {
         ensures: vec![PostCondition {
             closure: parse_quote! { |result| result > old_val },
             cfg: None,
+            label: None,
+            severity: None,
+            sample: None,
+            expensive: false,
         }],
         span: Span::call_site(),
     };
@@ -578,6 +1072,7 @@ fn captures_array_expression() {
     };
 
     let expected = Spec {
+        mode: None,
         requires: vec![],
         maintains: vec![],
         captures: vec![Capture {
@@ -587,6 +1082,10 @@ fn captures_array_expression() {
         ensures: vec![PostCondition {
             closure: parse_quote! { |output| slice.len() == 3 },
             cfg: None,
+            label: None,
+            severity: None,
+            sample: None,
+            expensive: false,
         }],
         span: Span::call_site(),
     };
@@ -672,6 +1171,7 @@ fn captures_edge_case_cast_expr() {
     };
 
     let expected = Spec {
+        mode: None,
         requires: vec![],
         maintains: vec![],
         captures: vec![Capture {
@@ -696,6 +1196,7 @@ fn captures_edge_case_array_of_cast_exprs() {
     };
 
     let expected = Spec {
+        mode: None,
         requires: vec![],
         maintains: vec![],
         captures: vec![Capture {
@@ -726,6 +1227,7 @@ fn captures_edge_case_list_of_cast_exprs() {
     };
 
     let expected = Spec {
+        mode: None,
         requires: vec![],
         maintains: vec![],
         captures: vec![
@@ -756,6 +1258,7 @@ fn captures_pattern_matches_slices() {
     };
 
     let expected = Spec {
+        mode: None,
         requires: vec![],
         maintains: vec![],
         captures: vec![Capture {
@@ -776,6 +1279,7 @@ fn captures_pattern_matches_tuples() {
     };
 
     let expected = Spec {
+        mode: None,
         requires: vec![],
         maintains: vec![],
         captures: vec![Capture {
@@ -796,6 +1300,7 @@ fn captures_pattern_matches_structs() {
     };
 
     let expected = Spec {
+        mode: None,
         requires: vec![],
         maintains: vec![],
         captures: vec![Capture {
@@ -816,6 +1321,7 @@ fn captures_pattern_matches_nested() {
     };
 
     let expected = Spec {
+        mode: None,
         requires: vec![],
         maintains: vec![],
         captures: vec![Capture {
@@ -836,6 +1342,7 @@ fn captures_pattern_with_binding_modifier() {
     };
 
     let expected = Spec {
+        mode: None,
         requires: vec![],
         maintains: vec![],
         captures: vec![Capture {
@@ -0,0 +1,111 @@
+//! Contracts for the [Creusot](https://github.com/creusot-rs/creusot) verifier.
+//!
+//! Creusot states contracts in Pearlite, its logic language, with `#[requires]` and
+//! `#[ensures]` attributes. A postcondition refers to the return value as `result`. Since a
+//! Pearlite postcondition reads a mutable reference `x` as its value at entry, `*x`, and
+//! as its value at exit, `^x`, the translation of a postcondition:
+//! - replaces the return value with `result`, and the names in a tuple pattern of `binds:`
+//!   with its fields, e.g. `result.0`,
+//! - replaces `*x`, `x.field`, and `x.method()` of a `&mut` argument `x` with `(^x)`,
+//! - replaces each capture with the captured expression, which stands for its value at entry.
+//!
+//! Conditions are translated whatever their `#[warn]`, `#[deny]`, `#[sample]`, or
+//! `#[expensive]` attributes; a `#[cfg]` still applies.
+
+#[cfg(test)]
+mod tests;
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Attribute, Expr, Ident, Meta, Signature, parse_quote, spanned::Spanned};
+
+use crate::{
+    Spec,
+    translate::{PostRewrite, condition_error, mut_ref_args, unsupported_constructs},
+};
+
+/// Builds the Creusot contract attributes of a function with the given spec.
+///
+/// Each attribute is only active under `cfg(creusot)`, and under the `#[cfg]` of its
+/// condition. A `requires` condition becomes `requires`, an `ensures` condition becomes
+/// `ensures`, and a `maintains` condition becomes both, since Creusot's `#[invariant]` is
+/// for types and loops rather than functions.
+///
+/// Returns an error for each condition that Pearlite can't express, e.g. one with a
+/// closure or a macro call, or a postcondition that destructures the return value with a
+/// pattern other than a tuple of names.
+pub fn build_contract(sig: &Signature, spec: &Spec) -> syn::Result<Vec<Attribute>> {
+    let mut errors = Vec::new();
+    let mut_refs = mut_ref_args(sig);
+    let rewrite = PostRewrite {
+        result: parse_quote! { result },
+        captures: &spec.captures,
+        capture: Expr::clone,
+        mut_refs: &mut_refs,
+        final_value,
+    };
+
+    let mut attrs = Vec::new();
+    for condition in spec.requires.iter().chain(&spec.maintains) {
+        let body = &condition.closure.body;
+        check(body, &mut errors);
+        attrs.push(contract_attr(
+            condition.cfg.as_ref(),
+            quote! { creusot_contracts::requires(#body) },
+        ));
+    }
+    for condition in &spec.maintains {
+        let body = rewrite.condition(&condition.closure.body);
+        attrs.push(contract_attr(
+            condition.cfg.as_ref(),
+            quote! { creusot_contracts::ensures(#body) },
+        ));
+    }
+    for postcondition in &spec.ensures {
+        check(&postcondition.closure.body, &mut errors);
+        let Some(body) = rewrite.postcondition(postcondition) else {
+            errors.push(condition_error(
+                postcondition.closure.span(),
+                "Creusot translation cannot destructure the return value",
+                &postcondition.closure,
+            ));
+            continue;
+        };
+        attrs.push(contract_attr(
+            postcondition.cfg.as_ref(),
+            quote! { creusot_contracts::ensures(#body) },
+        ));
+    }
+
+    match errors.into_iter().reduce(|mut combined, error| {
+        combined.combine(error);
+        combined
+    }) {
+        Some(error) => Err(error),
+        None => Ok(attrs),
+    }
+}
+
+/// Adds an error for each construct in the condition that Pearlite can't express.
+fn check(condition: &Expr, errors: &mut Vec<syn::Error>) {
+    for (span, construct) in unsupported_constructs(condition) {
+        errors.push(condition_error(
+            span,
+            &format!("Creusot's Pearlite cannot express {construct}"),
+            condition,
+        ));
+    }
+}
+
+/// The value of a mutable reference at exit, in Pearlite.
+fn final_value(ident: &Ident) -> Expr {
+    Expr::Verbatim(quote! { (^#ident) })
+}
+
+/// Makes an attribute that is only active under `cfg(creusot)` and the condition's `#[cfg]`.
+fn contract_attr(cfg: Option<&Meta>, contract: TokenStream) -> Attribute {
+    match cfg {
+        Some(cfg) => parse_quote! { #[cfg_attr(all(creusot, #cfg), #contract)] },
+        None => parse_quote! { #[cfg_attr(creusot, #contract)] },
+    }
+}
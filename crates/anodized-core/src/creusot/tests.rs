@@ -0,0 +1,114 @@
+use super::*;
+
+use syn::ItemFn;
+
+use crate::test_util::assert_tokens_eq;
+
+fn assert_attrs_eq(observed: &[Attribute], expected: &[Attribute]) {
+    assert_eq!(observed.len(), expected.len(), "number of attributes");
+    for (observed, expected) in observed.iter().zip(expected) {
+        assert_tokens_eq(observed, expected);
+    }
+}
+
+#[test]
+fn contract() {
+    let spec: Spec = parse_quote! {
+        requires: divisor != 0,
+        captures: dividend as original,
+        #[cfg(debug_assertions)]
+        ensures: *output <= original,
+    };
+    let func: ItemFn = parse_quote! {
+        fn divide(dividend: u32, divisor: u32) -> u32 {}
+    };
+
+    let expected: [Attribute; 2] = [
+        parse_quote! { #[cfg_attr(creusot, creusot_contracts::requires(divisor != 0))] },
+        parse_quote! {
+            #[cfg_attr(all(creusot, debug_assertions), creusot_contracts::ensures(result <= (dividend)))]
+        },
+    ];
+
+    assert_attrs_eq(&build_contract(&func.sig, &spec).unwrap(), &expected);
+}
+
+#[test]
+fn mutable_references() {
+    let spec: Spec = parse_quote! {
+        maintains: self.is_valid(),
+        captures: [self.len() as len, *total as old_total],
+        binds: count,
+        ensures: [
+            self.len() == len + 1,
+            *total == old_total + *count,
+            self.items[len] == item,
+        ],
+    };
+    let func: ItemFn = parse_quote! {
+        fn push(&mut self, item: u8, total: &mut u64) -> u64 {}
+    };
+
+    let expected: [Attribute; 5] = [
+        parse_quote! { #[cfg_attr(creusot, creusot_contracts::requires(self.is_valid()))] },
+        parse_quote! { #[cfg_attr(creusot, creusot_contracts::ensures((^self).is_valid()))] },
+        parse_quote! {
+            #[cfg_attr(creusot, creusot_contracts::ensures((^self).len() == (self.len()) + 1))]
+        },
+        parse_quote! {
+            #[cfg_attr(creusot, creusot_contracts::ensures((^total) == (*total) + result))]
+        },
+        parse_quote! {
+            #[cfg_attr(creusot, creusot_contracts::ensures((^self).items[(self.len())] == item))]
+        },
+    ];
+
+    assert_attrs_eq(&build_contract(&func.sig, &spec).unwrap(), &expected);
+}
+
+#[test]
+fn unsupported_conditions() {
+    let spec: Spec = parse_quote! {
+        requires: items.iter().all(|item| *item > 0),
+        binds: (low, Some(high)),
+        ensures: [
+            matches!(low, 0..=9),
+            low <= high,
+        ],
+    };
+    let func: ItemFn = parse_quote! {
+        fn bounds(items: &[u8]) -> (u8, Option<u8>) {}
+    };
+
+    let errors = build_contract(&func.sig, &spec)
+        .unwrap_err()
+        .into_iter()
+        .map(|error| error.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        errors,
+        [
+            "Creusot's Pearlite cannot express closures: `items . iter () . all (| item | * item > 0)`",
+            "Creusot's Pearlite cannot express macro calls: `matches ! (low , 0 ..= 9)`",
+            "Creusot translation cannot destructure the return value: `| (low , Some (high)) | matches ! (low , 0 ..= 9)`",
+            "Creusot translation cannot destructure the return value: `| (low , Some (high)) | low <= high`",
+        ]
+    );
+}
+
+#[test]
+fn tuple_binds() {
+    let spec: Spec = parse_quote! {
+        binds: (low, high),
+        ensures: *low <= *high,
+    };
+    let func: ItemFn = parse_quote! {
+        fn bounds(items: &[u8]) -> (u8, u8) {}
+    };
+
+    let expected: [Attribute; 1] = [parse_quote! {
+        #[cfg_attr(creusot, creusot_contracts::ensures(result.0 <= result.1))]
+    }];
+
+    assert_attrs_eq(&build_contract(&func.sig, &spec).unwrap(), &expected);
+}
@@ -0,0 +1,186 @@
+//! A machine-readable form of specs, for tools that don't parse Rust.
+//!
+//! A spec is exported as a JSON object:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "function": "divide",
+//!   "mode": null,
+//!   "requires": [{ "condition": "divisor != 0", "span": null, "cfg": null, "label": null,
+//!                  "severity": null, "sample": null, "expensive": false }],
+//!   "maintains": [],
+//!   "captures": [{ "expression": "dividend", "pattern": "original" }],
+//!   "ensures": [{ "pattern": "output", "condition": "* output <= original", "span": null,
+//!                 "cfg": null, "label": null, "severity": null, "sample": null,
+//!                 "expensive": false }]
+//! }
+//! ```
+//!
+//! Expressions, patterns, and `cfg` settings are given as Rust tokens. A `sample` is an
+//! object with `count` and `period`. The `span` of a condition is `null` in the spec of a
+//! single function, and an object with the 1-based `line` and 0-based `column` where the
+//! condition starts in an export of a whole crate, see [`export_crate`]. The `version`
+//! changes whenever the format changes in a way that existing readers wouldn't understand.
+
+mod source;
+#[cfg(test)]
+mod tests;
+
+pub(crate) use source::read_specs;
+pub use source::{ExportError, export_crate};
+
+use std::fmt::Write as _;
+
+use proc_macro2::LineColumn;
+use quote::ToTokens;
+use syn::{LitStr, Meta, spanned::Spanned};
+
+use crate::{Capture, PostCondition, PreCondition, Sample, Severity, Spec};
+
+/// The version of the format, given in the `version` field.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// The name of the hidden const that holds the exported spec in an instrumented function.
+///
+/// Tools that work on expanded code, HIR, or MIR find the spec of a function as the
+/// `&str` value of the const with this name among the items declared in its body.
+pub const SPEC_CONST_NAME: &str = "__ANODIZED_SPEC";
+
+/// Exports the spec of the function with the given name as a JSON object.
+pub fn spec_to_json(fn_name: &str, spec: &Spec) -> String {
+    spec_object(fn_name, spec, false)
+}
+
+/// Exports a spec, with the spans of its conditions if `with_spans` is set.
+fn spec_object(fn_name: &str, spec: &Spec, with_spans: bool) -> String {
+    let Spec {
+        mode,
+        requires,
+        maintains,
+        captures,
+        ensures,
+        span: _,
+    } = spec;
+    let mode = mode.map_or("null".to_string(), |mode| json_string(mode.name()));
+    format!(
+        r#"{{"version":{FORMAT_VERSION},"function":{},"mode":{mode},"requires":[{}],"maintains":[{}],"captures":[{}],"ensures":[{}]}}"#,
+        json_string(fn_name),
+        join(requires.iter().map(|c| precondition_to_json(c, with_spans))),
+        join(
+            maintains
+                .iter()
+                .map(|c| precondition_to_json(c, with_spans))
+        ),
+        join(captures.iter().map(capture_to_json)),
+        join(ensures.iter().map(|c| postcondition_to_json(c, with_spans))),
+    )
+}
+
+fn precondition_to_json(condition: &PreCondition, with_spans: bool) -> String {
+    let PreCondition {
+        closure,
+        cfg,
+        label,
+        severity,
+        sample,
+        expensive,
+    } = condition;
+    format!(
+        r#"{{"condition":{},"span":{},{}}}"#,
+        tokens_to_json(&closure.body),
+        span_to_json(&closure.body, with_spans),
+        settings_to_json(cfg, label, severity, sample, *expensive),
+    )
+}
+
+fn postcondition_to_json(condition: &PostCondition, with_spans: bool) -> String {
+    let PostCondition {
+        closure,
+        cfg,
+        label,
+        severity,
+        sample,
+        expensive,
+    } = condition;
+    let pattern = closure
+        .inputs
+        .first()
+        .map_or("null".to_string(), tokens_to_json);
+    format!(
+        r#"{{"pattern":{pattern},"condition":{},"span":{},{}}}"#,
+        tokens_to_json(&closure.body),
+        span_to_json(&closure.body, with_spans),
+        settings_to_json(cfg, label, severity, sample, *expensive),
+    )
+}
+
+fn settings_to_json(
+    cfg: &Option<Meta>,
+    label: &Option<LitStr>,
+    severity: &Option<Severity>,
+    sample: &Option<Sample>,
+    expensive: bool,
+) -> String {
+    let cfg = cfg.as_ref().map_or("null".to_string(), tokens_to_json);
+    let label = label
+        .as_ref()
+        .map_or("null".to_string(), |label| json_string(&label.value()));
+    let severity = severity.map_or("null".to_string(), |severity| {
+        json_string(match severity {
+            Severity::Warn => "warn",
+            Severity::Deny => "deny",
+        })
+    });
+    let sample = sample.map_or("null".to_string(), |Sample { count, period }| {
+        format!(r#"{{"count":{count},"period":{period}}}"#)
+    });
+    format!(
+        r#""cfg":{cfg},"label":{label},"severity":{severity},"sample":{sample},"expensive":{expensive}"#
+    )
+}
+
+fn span_to_json(tokens: &impl Spanned, with_spans: bool) -> String {
+    if !with_spans {
+        return "null".to_string();
+    }
+    let LineColumn { line, column } = tokens.span().start();
+    format!(r#"{{"line":{line},"column":{column}}}"#)
+}
+
+fn capture_to_json(capture: &Capture) -> String {
+    let Capture { expr, pat } = capture;
+    format!(
+        r#"{{"expression":{},"pattern":{}}}"#,
+        tokens_to_json(expr),
+        tokens_to_json(pat),
+    )
+}
+
+fn join(items: impl Iterator<Item = String>) -> String {
+    items.collect::<Vec<_>>().join(",")
+}
+
+fn tokens_to_json<T: ToTokens>(tokens: &T) -> String {
+    json_string(&tokens.to_token_stream().to_string())
+}
+
+fn json_string(value: &str) -> String {
+    let mut json = String::with_capacity(value.len() + 2);
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c < ' ' => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
@@ -0,0 +1,297 @@
+//! Reading of all specs in the source of a crate, and their export.
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use proc_macro2::LineColumn;
+use quote::ToTokens;
+use syn::{
+    Attribute, ImplItem, Item, ItemMod, Meta, Signature, TraitItem, Visibility, spanned::Spanned,
+};
+
+use super::{FORMAT_VERSION, join, json_string, spec_object, tokens_to_json};
+use crate::{Spec, is_spec_attr};
+
+/// An error while reading the specs of a crate.
+#[derive(Debug)]
+pub enum ExportError {
+    /// A source file couldn't be read.
+    Io {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+    /// A source file, or a spec in it, couldn't be parsed.
+    Parse { path: PathBuf, error: syn::Error },
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportError::Io { path, error } => {
+                write!(f, "cannot read {}: {error}", path.display())
+            }
+            ExportError::Parse { path, error } => {
+                let LineColumn { line, column } = error.span().start();
+                write!(f, "{}:{line}:{}: {error}", path.display(), column + 1)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ExportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ExportError::Io { error, .. } => Some(error),
+            ExportError::Parse { error, .. } => Some(error),
+        }
+    }
+}
+
+/// Exports the specs of all functions in a crate as a JSON document.
+///
+/// The crate is read starting from its root file, e.g. `src/lib.rs`, following `mod`
+/// declarations to the files of its modules. The document lists the specs in source order:
+///
+/// ```json
+/// {
+///   "version": 1,
+///   "specs": [{
+///     "path": "crate::math::divide",
+///     "file": "src/math.rs",
+///     "line": 4,
+///     "signature": "fn divide (dividend : u32 , divisor : u32) -> u32",
+///     "spec": { "version": 1, "function": "divide", ... }
+///   }]
+/// }
+/// ```
+///
+/// The `path` of a method is the path of its trait or `impl` type, followed by the name of
+/// the method. The `file` is the path of the source file as found from `root`: the
+/// directory of `root` joined with the path of the file in it, e.g. `src/math.rs` for the
+/// root `src/lib.rs`. So it's relative to the current directory if `root` is, and absolute
+/// if `root` is. The `line` is that of the `#[spec]` attribute. The `spec` is in the format
+/// described in the [module documentation](self), with the spans of its conditions.
+pub fn export_crate(root: &Path) -> Result<String, ExportError> {
+    let mut specs = Vec::new();
+    read_specs(root, &mut |found| {
+        let name = found.sig.ident.to_string();
+        specs.push(format!(
+            r#"{{"path":{},"file":{},"line":{},"signature":{},"spec":{}}}"#,
+            json_string(&format!("{}::{name}", found.owner)),
+            json_string(&found.file.display().to_string()),
+            found.attr.span().start().line,
+            tokens_to_json(found.sig),
+            spec_object(&name, &found.spec, true),
+        ));
+    })?;
+    Ok(format!(
+        r#"{{"version":{FORMAT_VERSION},"specs":[{}]}}"#,
+        join(specs.into_iter())
+    ))
+}
+
+/// A function with a `#[spec]`, found in the source of a crate.
+pub(crate) struct SpecFn<'a> {
+    /// The source file of the function.
+    pub file: &'a Path,
+    /// The path of the module, trait, or `impl` type the function is in, e.g.
+    /// `crate::math`.
+    pub owner: &'a str,
+    /// Whether the function is in a trait or an `impl`.
+    pub method: bool,
+    /// Whether the function is `pub` in a module that is reachable from outside the crate,
+    /// for functions that aren't methods.
+    pub public: bool,
+    /// The `#[spec]` attribute.
+    pub attr: &'a Attribute,
+    /// The signature of the function.
+    pub sig: &'a Signature,
+    /// The spec, parsed from the attribute.
+    pub spec: Spec,
+}
+
+/// Reads the specs of all functions in a crate, in source order.
+///
+/// The crate is read starting from its root file, following `mod` declarations to the files
+/// of its modules.
+pub(crate) fn read_specs(
+    root: &Path,
+    visit: &mut dyn FnMut(SpecFn<'_>),
+) -> Result<(), ExportError> {
+    let dir = root.parent().unwrap_or(Path::new("")).to_path_buf();
+    read_module_file(root, dir, "crate".to_string(), true, visit)
+}
+
+/// Reads the specs of a source file, given the directory of its submodules' files.
+fn read_module_file(
+    path: &Path,
+    dir: PathBuf,
+    module_path: String,
+    public: bool,
+    visit: &mut dyn FnMut(SpecFn<'_>),
+) -> Result<(), ExportError> {
+    let source = std::fs::read_to_string(path).map_err(|error| ExportError::Io {
+        path: path.to_path_buf(),
+        error,
+    })?;
+    let file = syn::parse_file(&source).map_err(|error| ExportError::Parse {
+        path: path.to_path_buf(),
+        error,
+    })?;
+    let mut module = Module {
+        file: path,
+        dir,
+        path: module_path,
+        public,
+        visit,
+    };
+    module.read_items(&file.items)
+}
+
+/// A module whose specs are being read.
+struct Module<'a> {
+    /// The source file the module is in.
+    file: &'a Path,
+    /// The directory of the files of its `mod name;` submodules.
+    dir: PathBuf,
+    /// The path of the module, e.g. `crate::math`.
+    path: String,
+    /// Whether the module is reachable from outside the crate.
+    public: bool,
+    visit: &'a mut dyn FnMut(SpecFn<'_>),
+}
+
+impl Module<'_> {
+    fn read_items(&mut self, items: &[Item]) -> Result<(), ExportError> {
+        for item in items {
+            match item {
+                Item::Fn(func) => {
+                    let public = self.public && matches!(func.vis, Visibility::Public(_));
+                    self.read_fn(&self.path.clone(), false, public, &func.attrs, &func.sig)?;
+                }
+                Item::Trait(the_trait) => {
+                    let owner = format!("{}::{}", self.path, the_trait.ident);
+                    for item in &the_trait.items {
+                        if let TraitItem::Fn(func) = item {
+                            self.read_fn(&owner, true, false, &func.attrs, &func.sig)?;
+                        }
+                    }
+                }
+                Item::Impl(the_impl) => {
+                    let self_ty = the_impl.self_ty.to_token_stream().to_string();
+                    let owner = match &the_impl.trait_ {
+                        None => format!("{}::{self_ty}", self.path),
+                        Some((_, trait_path, _)) => format!(
+                            "<{}::{self_ty} as {}>",
+                            self.path,
+                            trait_path.to_token_stream()
+                        ),
+                    };
+                    for item in &the_impl.items {
+                        if let ImplItem::Fn(func) = item {
+                            self.read_fn(&owner, true, false, &func.attrs, &func.sig)?;
+                        }
+                    }
+                }
+                Item::Mod(the_mod) => self.read_mod(the_mod)?,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn read_mod(&mut self, the_mod: &ItemMod) -> Result<(), ExportError> {
+        let name = the_mod.ident.to_string();
+        let path = format!("{}::{name}", self.path);
+        let public = self.public && matches!(the_mod.vis, Visibility::Public(_));
+        let path_attr = path_attr(&the_mod.attrs).map_err(|error| self.parse_error(error))?;
+
+        if let Some((_, items)) = &the_mod.content {
+            let dir = match &path_attr {
+                Some(dir) => self.dir.join(dir),
+                None => self.dir.join(&name),
+            };
+            let mut module = Module {
+                file: self.file,
+                dir,
+                path,
+                public,
+                visit: &mut *self.visit,
+            };
+            return module.read_items(items);
+        }
+
+        let (file, dir) = match path_attr {
+            Some(file) => {
+                let file = self.file.parent().unwrap_or(Path::new("")).join(file);
+                // Like `mod.rs`, the file's submodules are next to it.
+                let dir = file.parent().unwrap_or(Path::new("")).to_path_buf();
+                (file, dir)
+            }
+            None => {
+                let file = self.dir.join(format!("{name}.rs"));
+                if file.exists() {
+                    (file, self.dir.join(&name))
+                } else {
+                    let dir = self.dir.join(&name);
+                    (dir.join("mod.rs"), dir)
+                }
+            }
+        };
+        read_module_file(&file, dir, path, public, self.visit)
+    }
+
+    fn read_fn(
+        &mut self,
+        owner: &str,
+        method: bool,
+        public: bool,
+        attrs: &[Attribute],
+        sig: &Signature,
+    ) -> Result<(), ExportError> {
+        let Some(attr) = attrs.iter().find(|attr| is_spec_attr(attr)) else {
+            return Ok(());
+        };
+        let spec: Spec = match &attr.meta {
+            Meta::Path(_) => syn::parse2(Default::default()),
+            _ => attr.parse_args(),
+        }
+        .map_err(|error| self.parse_error(error))?;
+        (self.visit)(SpecFn {
+            file: self.file,
+            owner,
+            method,
+            public,
+            attr,
+            sig,
+            spec,
+        });
+        Ok(())
+    }
+
+    fn parse_error(&self, error: syn::Error) -> ExportError {
+        ExportError::Parse {
+            path: self.file.to_path_buf(),
+            error,
+        }
+    }
+}
+
+/// Returns the value of a `#[path = "..."]` attribute, if any.
+fn path_attr(attrs: &[Attribute]) -> syn::Result<Option<String>> {
+    for attr in attrs {
+        if attr.path().is_ident("path") {
+            let value = &attr.meta.require_name_value()?.value;
+            return match value {
+                syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Str(path),
+                    ..
+                }) => Ok(Some(path.value())),
+                _ => Err(syn::Error::new_spanned(value, "expected a string literal")),
+            };
+        }
+    }
+    Ok(None)
+}
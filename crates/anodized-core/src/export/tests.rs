@@ -0,0 +1,91 @@
+use super::*;
+
+use syn::parse_quote;
+
+#[test]
+fn all_parameters() {
+    let spec: Spec = parse_quote! {
+        mode: print,
+        #[cfg(test)]
+        #[label = "a \"positive\" divisor"]
+        requires: divisor > 0,
+        #[warn]
+        #[sample(1 in 10)]
+        maintains: self.is_valid(),
+        captures: dividend as original,
+        #[deny]
+        #[expensive]
+        ensures: |quotient| quotient <= original,
+    };
+
+    let expected = concat!(
+        r#"{"version":1,"function":"divide","mode":"print","#,
+        r#""requires":[{"condition":"divisor > 0","span":null,"cfg":"test","label":"a \"positive\" divisor","severity":null,"sample":null,"expensive":false}],"#,
+        r#""maintains":[{"condition":"self . is_valid ()","span":null,"cfg":null,"label":null,"severity":"warn","sample":{"count":1,"period":10},"expensive":false}],"#,
+        r#""captures":[{"expression":"dividend","pattern":"original"}],"#,
+        r#""ensures":[{"pattern":"quotient","condition":"quotient <= original","span":null,"cfg":null,"label":null,"severity":"deny","sample":null,"expensive":true}]}"#,
+    );
+
+    assert_eq!(spec_to_json("divide", &spec), expected);
+}
+
+#[test]
+fn empty_spec() {
+    let spec: Spec = parse_quote! {};
+
+    assert_eq!(
+        spec_to_json("f", &spec),
+        r#"{"version":1,"function":"f","mode":null,"requires":[],"maintains":[],"captures":[],"ensures":[]}"#
+    );
+}
+
+#[test]
+fn spans() {
+    let spec: Spec = syn::parse_str("requires: x > 0,\nensures: *output < x,").unwrap();
+
+    assert_eq!(
+        spec_object("f", &spec, true),
+        concat!(
+            r#"{"version":1,"function":"f","mode":null,"#,
+            r#""requires":[{"condition":"x > 0","span":{"line":1,"column":10},"cfg":null,"label":null,"severity":null,"sample":null,"expensive":false}],"#,
+            r#""maintains":[],"captures":[],"#,
+            r#""ensures":[{"pattern":"output","condition":"* output < x","span":{"line":2,"column":9},"cfg":null,"label":null,"severity":null,"sample":null,"expensive":false}]}"#,
+        )
+    );
+}
+
+#[test]
+fn missing_module_file() {
+    let error = export_crate(std::path::Path::new("no/such/lib.rs")).unwrap_err();
+
+    assert!(matches!(error, ExportError::Io { .. }));
+    assert!(
+        error
+            .to_string()
+            .starts_with("cannot read no/such/lib.rs: ")
+    );
+}
+
+#[test]
+fn files_outside_current_directory() {
+    let dir = std::env::temp_dir().join(format!("anodized-export-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    std::fs::write(dir.join("src/lib.rs"), "mod math;\n").unwrap();
+    std::fs::write(
+        dir.join("src/math.rs"),
+        "#[spec(requires: divisor != 0)]\nfn divide(dividend: u32, divisor: u32) -> u32 {\n    dividend / divisor\n}\n",
+    )
+    .unwrap();
+
+    let document = export_crate(&dir.join("src/lib.rs"));
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    // The file is the directory of the root joined with the file's path in it.
+    let file = json_string(&dir.join("src/math.rs").display().to_string());
+    assert!(
+        document
+            .unwrap()
+            .contains(&format!(r#""file":{file},"line":1,"#)),
+        "the file isn't {file}"
+    );
+}
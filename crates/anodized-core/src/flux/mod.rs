@@ -0,0 +1,219 @@
+//! Refinement types for the [Flux](https://github.com/flux-rs/flux) verifier.
+//!
+//! Flux refines the types in a function's signature with predicates in linear integer
+//! arithmetic, so a precondition over integer arguments, e.g. `idx < len`, becomes a
+//! refinement of an argument's type, e.g. `len: usize{idx < len}`. Each precondition is
+//! attached to the last argument it mentions, so that every argument it mentions is in
+//! scope.
+//!
+//! Preconditions outside this fragment are left out of the signature, with a note; so are
+//! postconditions, for now. Conditions are translated whatever their `#[warn]`, `#[deny]`,
+//! `#[sample]`, or `#[expensive]` attributes.
+
+#[cfg(test)]
+mod tests;
+
+use std::fmt;
+
+use proc_macro2::Span;
+use quote::{ToTokens, quote};
+use syn::{
+    Attribute, BinOp, Expr, FnArg, Ident, Lit, Pat, Signature, Type, UnOp, parse_quote,
+    spanned::Spanned,
+};
+
+use crate::Spec;
+
+/// A condition that was left out of a Flux signature.
+#[derive(Debug)]
+pub struct Note {
+    /// The span of the condition.
+    pub span: Span,
+    /// Why the condition was left out.
+    pub message: String,
+}
+
+impl fmt::Display for Note {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// Builds the Flux signature of a function with the given spec, if any of its
+/// preconditions can be stated as a refinement.
+///
+/// The signature is only active under `cfg(flux)`. A `requires` or `maintains` condition
+/// is translated if it is built from integer literals, arguments of primitive integer
+/// types, `+`, `-`, multiplication by a literal, comparisons, `&&`, `||`, and `!`. Returns
+/// a note for each precondition that isn't, and for each one with a `#[cfg]`, which a
+/// refinement can't have.
+pub fn build_sig(sig: &Signature, spec: &Spec) -> (Option<Attribute>, Vec<Note>) {
+    let mut notes = Vec::new();
+    if sig.asyncness.is_some() {
+        notes.push(Note {
+            span: sig.asyncness.span(),
+            message: "Flux signatures are not generated for `async` functions".to_string(),
+        });
+        return (None, notes);
+    }
+
+    let integer_args = integer_args(sig);
+    let mut refinements: Vec<Vec<&Expr>> = vec![Vec::new(); sig.inputs.len()];
+    for condition in spec.requires.iter().chain(&spec.maintains) {
+        let body = condition.closure.body.as_ref();
+        if condition.cfg.is_some() {
+            notes.push(note(
+                body,
+                "Flux signature leaves out a condition with a `#[cfg]`",
+            ));
+            continue;
+        }
+        let mut mentioned = Vec::new();
+        if !is_linear(body, &integer_args, &mut mentioned) {
+            notes.push(note(
+                body,
+                "Flux signature leaves out a condition that isn't linear arithmetic over \
+                 integer arguments",
+            ));
+            continue;
+        }
+        match mentioned.into_iter().max() {
+            Some(position) => refinements[position].push(body),
+            None => notes.push(note(
+                body,
+                "Flux signature leaves out a condition that mentions no arguments",
+            )),
+        }
+    }
+    if refinements.iter().all(Vec::is_empty) {
+        return (None, notes);
+    }
+
+    let inputs = sig
+        .inputs
+        .iter()
+        .zip(&refinements)
+        .map(|(input, conditions)| {
+            let (name, ty) = match input {
+                FnArg::Receiver(receiver) => {
+                    let ty = &receiver.ty;
+                    (Some(quote! { self }), quote! { #ty })
+                }
+                FnArg::Typed(input) => {
+                    let name = match input.pat.as_ref() {
+                        Pat::Ident(pat) => Some(pat.ident.to_token_stream()),
+                        _ => None,
+                    };
+                    let ty = &input.ty;
+                    (name, quote! { #ty })
+                }
+            };
+            let refinement = (!conditions.is_empty()).then(|| quote! { { #(#conditions)&&* } });
+            match name {
+                Some(name) => quote! { #name: #ty #refinement },
+                None => ty,
+            }
+        });
+    let output = &sig.output;
+    let attr = parse_quote! { #[cfg_attr(flux, flux::sig(fn(#(#inputs),*) #output))] };
+    (Some(attr), notes)
+}
+
+fn note(condition: &Expr, message: &str) -> Note {
+    Note {
+        span: condition.span(),
+        message: format!("{message}: `{}`", condition.to_token_stream()),
+    }
+}
+
+/// The names of the arguments of primitive integer types, by position.
+fn integer_args(sig: &Signature) -> Vec<(Ident, usize)> {
+    sig.inputs
+        .iter()
+        .enumerate()
+        .filter_map(|(position, input)| match input {
+            FnArg::Typed(input) => match (input.pat.as_ref(), input.ty.as_ref()) {
+                (Pat::Ident(pat), ty) if pat.by_ref.is_none() && is_integer_type(ty) => {
+                    Some((pat.ident.clone(), position))
+                }
+                _ => None,
+            },
+            FnArg::Receiver(_) => None,
+        })
+        .collect()
+}
+
+fn is_integer_type(ty: &Type) -> bool {
+    const INTEGERS: [&str; 12] = [
+        "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128", "usize",
+    ];
+    match ty {
+        Type::Path(path) if path.qself.is_none() => path
+            .path
+            .get_ident()
+            .is_some_and(|ident| INTEGERS.iter().any(|integer| ident == integer)),
+        Type::Paren(paren) => is_integer_type(&paren.elem),
+        Type::Group(group) => is_integer_type(&group.elem),
+        _ => false,
+    }
+}
+
+/// Whether an expression is in linear integer arithmetic over the integer arguments,
+/// collecting the positions of the arguments it mentions.
+fn is_linear(expr: &Expr, integer_args: &[(Ident, usize)], mentioned: &mut Vec<usize>) -> bool {
+    match expr {
+        Expr::Lit(lit) => matches!(lit.lit, Lit::Int(_) | Lit::Bool(_)),
+        Expr::Path(path) if path.qself.is_none() => {
+            let Some(ident) = path.path.get_ident() else {
+                return false;
+            };
+            match integer_args.iter().find(|(name, _)| name == ident) {
+                Some((_, position)) => {
+                    mentioned.push(*position);
+                    true
+                }
+                None => false,
+            }
+        }
+        Expr::Paren(paren) => is_linear(&paren.expr, integer_args, mentioned),
+        Expr::Group(group) => is_linear(&group.expr, integer_args, mentioned),
+        Expr::Unary(unary) => {
+            matches!(unary.op, UnOp::Not(_) | UnOp::Neg(_))
+                && is_linear(&unary.expr, integer_args, mentioned)
+        }
+        Expr::Binary(binary) => {
+            let operands_linear = |mentioned: &mut Vec<usize>| {
+                is_linear(&binary.left, integer_args, mentioned)
+                    && is_linear(&binary.right, integer_args, mentioned)
+            };
+            match binary.op {
+                BinOp::Add(_)
+                | BinOp::Sub(_)
+                | BinOp::And(_)
+                | BinOp::Or(_)
+                | BinOp::Eq(_)
+                | BinOp::Ne(_)
+                | BinOp::Lt(_)
+                | BinOp::Le(_)
+                | BinOp::Gt(_)
+                | BinOp::Ge(_) => operands_linear(mentioned),
+                // A product is linear if one of its factors is a constant.
+                BinOp::Mul(_) => {
+                    (is_int_literal(&binary.left) || is_int_literal(&binary.right))
+                        && operands_linear(mentioned)
+                }
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+fn is_int_literal(expr: &Expr) -> bool {
+    match expr {
+        Expr::Lit(lit) => matches!(lit.lit, Lit::Int(_)),
+        Expr::Paren(paren) => is_int_literal(&paren.expr),
+        Expr::Group(group) => is_int_literal(&group.expr),
+        _ => false,
+    }
+}
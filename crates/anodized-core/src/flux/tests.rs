@@ -0,0 +1,101 @@
+use super::*;
+
+use syn::ItemFn;
+
+use crate::test_util::assert_tokens_eq;
+
+fn messages(notes: &[Note]) -> Vec<String> {
+    notes.iter().map(Note::to_string).collect()
+}
+
+#[test]
+fn linear_preconditions() {
+    let spec: Spec = parse_quote! {
+        requires: [
+            idx < len,
+            0 < len && len <= 2 * CAPACITY_MIN + 1,
+            start + 4 <= idx,
+        ],
+        maintains: !(len == 0),
+        ensures: *output < len,
+    };
+    let func: ItemFn = parse_quote! {
+        fn get(items: &[u8], start: usize, idx: usize, len: usize) -> u8 {}
+    };
+
+    let (attr, notes) = build_sig(&func.sig, &spec);
+
+    let expected: Attribute = parse_quote! {
+        #[cfg_attr(flux, flux::sig(fn(
+            items: &[u8],
+            start: usize,
+            idx: usize { start + 4 <= idx },
+            len: usize { idx < len && !(len == 0) }
+        ) -> u8))]
+    };
+    assert_tokens_eq(&attr.unwrap(), &expected);
+    assert_eq!(
+        messages(&notes),
+        [
+            "Flux signature leaves out a condition that isn't linear arithmetic over integer \
+             arguments: `0 < len && len <= 2 * CAPACITY_MIN + 1`"
+        ]
+    );
+}
+
+#[test]
+fn method() {
+    let spec: Spec = parse_quote! {
+        requires: index <= self.len(),
+        #[cfg(debug_assertions)]
+        requires: index > 0,
+        requires: 3 * index - offset >= -1,
+    };
+    let func: ItemFn = parse_quote! {
+        fn insert(&mut self, (a, b): (u8, u8), index: usize, offset: i64) {}
+    };
+
+    let (attr, notes) = build_sig(&func.sig, &spec);
+
+    let expected: Attribute = parse_quote! {
+        #[cfg_attr(flux, flux::sig(fn(
+            self: &mut Self,
+            (u8, u8),
+            index: usize,
+            offset: i64 { 3 * index - offset >= -1 }
+        )))]
+    };
+    assert_tokens_eq(&attr.unwrap(), &expected);
+    assert_eq!(
+        messages(&notes),
+        [
+            "Flux signature leaves out a condition that isn't linear arithmetic over integer \
+             arguments: `index <= self . len ()`",
+            "Flux signature leaves out a condition with a `#[cfg]`: `index > 0`",
+        ]
+    );
+}
+
+#[test]
+fn nothing_to_refine() {
+    let spec: Spec = parse_quote! {
+        requires: [a * b < 10, true, !items.is_empty()],
+    };
+    let func: ItemFn = parse_quote! {
+        fn f(a: u32, b: u32, items: Vec<u32>) {}
+    };
+
+    let (attr, notes) = build_sig(&func.sig, &spec);
+
+    assert!(attr.is_none());
+    assert_eq!(
+        messages(&notes),
+        [
+            "Flux signature leaves out a condition that isn't linear arithmetic over integer \
+             arguments: `a * b < 10`",
+            "Flux signature leaves out a condition that mentions no arguments: `true`",
+            "Flux signature leaves out a condition that isn't linear arithmetic over integer \
+             arguments: `! items . is_empty ()`",
+        ]
+    );
+}
@@ -0,0 +1,255 @@
+//! Fuzz targets for [cargo-fuzz](https://github.com/rust-fuzz/cargo-fuzz) and libFuzzer.
+//!
+//! A fuzz target turns a spec into a crash oracle: it decodes the arguments from the
+//! fuzzer's input with [arbitrary](https://github.com/rust-fuzz/arbitrary), skips inputs
+//! that violate a precondition or invariant, calls the function, and panics if an
+//! invariant or postcondition doesn't hold, which the fuzzer reports as a crash.
+//!
+//! The target checks the conditions itself, so they are checked whatever their `#[warn]`,
+//! `#[deny]`, `#[sample]`, or `#[expensive]` attributes, and whatever the runtime behavior
+//! and switches that the function's own checks follow; a `#[cfg]` still applies.
+
+#[cfg(test)]
+mod tests;
+
+use std::path::{Path, PathBuf};
+
+use proc_macro2::{Span, TokenStream, TokenTree};
+use quote::{ToTokens, quote};
+use syn::{FnArg, Ident, Pat, Path as SynPath, Signature, Type, parse_quote, spanned::Spanned};
+
+use crate::{
+    Spec,
+    export::{ExportError, read_specs},
+    instrument::{CheckKind, fns::annotate_postcondition_closure_argument},
+    translate::{guard, mentions_self, message, owned_type, return_type},
+};
+
+/// A public function with a spec, and its fuzz target or why it has none.
+pub struct FuzzFn {
+    /// The path of the function, starting with the crate's name, e.g. `shapes::math::divide`.
+    pub path: String,
+    /// The path of the source file, the directory of the crate's root file joined with the
+    /// path of the file in it.
+    pub file: PathBuf,
+    /// The line of the `#[spec]` attribute.
+    pub line: usize,
+    /// The fuzz target, or why the function can't be fuzzed.
+    pub target: Result<FuzzTarget, String>,
+}
+
+/// The source of a fuzz target.
+pub struct FuzzTarget {
+    /// The name of the target, made of the function's path after the crate's name, e.g.
+    /// `math_divide`.
+    pub name: String,
+    /// The source file of the target.
+    pub file: syn::File,
+}
+
+/// Builds fuzz targets for the public functions with a spec in a crate, in source order.
+///
+/// The crate is read starting from its root file, e.g. `src/lib.rs`, and `crate_name` is
+/// the name under which the fuzz targets refer to it. Methods, and functions that aren't
+/// reachable from outside the crate, are left out; see [`build_target`] for the functions
+/// that can't be fuzzed.
+pub fn fuzz_crate(root: &Path, crate_name: &str) -> Result<Vec<FuzzFn>, ExportError> {
+    let mut fns = Vec::new();
+    read_specs(root, &mut |found| {
+        if found.method || !found.public {
+            return;
+        }
+        let module = found.owner.replacen("crate", crate_name, 1);
+        let path = format!("{module}::{}", found.sig.ident);
+        let name = found
+            .owner
+            .split("::")
+            .skip(1)
+            .chain([found.sig.ident.to_string().as_str()])
+            .collect::<Vec<_>>()
+            .join("_");
+        let module: SynPath = syn::parse_str(&module).expect("a module path is a path");
+        fns.push(FuzzFn {
+            path,
+            file: found.file.to_path_buf(),
+            line: found.attr.span().start().line,
+            target: build_target(&module, found.sig, &found.spec)
+                .map(|file| FuzzTarget { name, file }),
+        });
+    })?;
+    Ok(fns)
+}
+
+/// Builds a fuzz target for a function with the given spec, in the given module of its
+/// crate, e.g. `shapes::math`.
+///
+/// The target refers to the types in the function's signature, and the items in its
+/// conditions, through a glob import of the module. Each argument is decoded with
+/// `arbitrary::Arbitrary`, so the argument types must implement it; an argument `&T` or
+/// `&mut T` borrows a decoded `T`, where `&str` borrows a `String` and `&[T]` a `Vec<T>`.
+/// The target is a binary for the fuzz package written by `anodized-fuzz`: it runs under
+/// `cfg(fuzzing)` with `libfuzzer-sys`, and otherwise through the package's `driver`
+/// module, which runs it on given or random inputs.
+///
+/// Returns why the function can't be fuzzed if it mentions `Self` or a path relative to
+/// its module, has generic parameters, is `async`, `unsafe`, or variadic, or has an
+/// argument that is a pattern other than a name, an `impl Trait`, or a reference with a
+/// lifetime.
+pub fn build_target(module: &SynPath, sig: &Signature, spec: &Spec) -> Result<syn::File, String> {
+    let unsupported = if sig.asyncness.is_some() {
+        Some("it's `async`")
+    } else if sig.unsafety.is_some() {
+        Some("it's `unsafe`")
+    } else if sig.variadic.is_some() {
+        Some("it's variadic")
+    } else if !sig.generics.params.is_empty() {
+        Some("it's generic")
+    } else if sig.receiver().is_some() {
+        Some("it's a method")
+    } else if mentions_self(sig.to_token_stream()) {
+        Some("it mentions `Self`")
+    } else if mentions_relative_path(sig.to_token_stream()) {
+        Some("its signature has a path relative to its module")
+    } else {
+        None
+    };
+    if let Some(unsupported) = unsupported {
+        return Err(unsupported.to_string());
+    }
+
+    let mut names = Vec::with_capacity(sig.inputs.len());
+    let mut patterns = Vec::with_capacity(sig.inputs.len());
+    let mut types = Vec::with_capacity(sig.inputs.len());
+    let mut borrows = Vec::new();
+    for input in &sig.inputs {
+        let FnArg::Typed(input) = input else {
+            unreachable!("methods are rejected above");
+        };
+        let Pat::Ident(pat) = input.pat.as_ref() else {
+            return Err("it has an argument that is a pattern".to_string());
+        };
+        let (name, ty) = (&pat.ident, input.ty.as_ref());
+        if let Type::Reference(reference) = ty {
+            if reference.lifetime.is_some() {
+                return Err(format!("`{name}` is a reference with a lifetime"));
+            }
+            let mutability = &reference.mutability;
+            borrows.push(quote! { let #name: #ty = &#mutability #name; });
+            patterns.push(quote! { #mutability #name });
+        } else {
+            let mutability = &pat.mutability;
+            patterns.push(quote! { #mutability #name });
+        }
+        let value_type =
+            owned_type(ty).ok_or_else(|| format!("the type of `{name}` can't be decoded"))?;
+        names.push(name);
+        types.push(value_type);
+    }
+
+    let assumptions = spec
+        .requires
+        .iter()
+        .chain(&spec.maintains)
+        .map(|condition| {
+            let body = &condition.closure.body;
+            guard(condition.cfg.as_ref(), quote! { if !(#body) { return; } })
+        });
+
+    let fn_name = &sig.ident;
+    let call = quote! { #module::#fn_name(#(#names),*) };
+
+    let return_type = return_type(sig);
+    let output = Ident::new("__anodized_output", Span::mixed_site());
+    let call = if spec.captures.is_empty() {
+        quote! { let #output: #return_type = #call; }
+    } else {
+        let aliases = spec.captures.iter().map(|capture| &capture.pat);
+        let capture_exprs = spec.captures.iter().map(|capture| &capture.expr);
+        let capture_types = spec.captures.iter().map(|_| quote! { _ });
+        quote! {
+            let (#(#aliases,)* #output): (#(#capture_types,)* #return_type) =
+                (#(#capture_exprs,)* #call);
+        }
+    };
+
+    let fail = |kind, label, repr, check: TokenStream| {
+        let message = message(kind, label, repr);
+        quote! {
+            if !(#check) {
+                ::core::panic!("{}", #message);
+            }
+        }
+    };
+    let checks = spec
+        .maintains
+        .iter()
+        .map(|condition| {
+            let body = &condition.closure.body;
+            guard(
+                condition.cfg.as_ref(),
+                fail(
+                    CheckKind::PostInvariant,
+                    condition.label.as_ref(),
+                    body.to_token_stream(),
+                    body.to_token_stream(),
+                ),
+            )
+        })
+        .chain(spec.ensures.iter().map(|postcondition| {
+            let closure = annotate_postcondition_closure_argument(
+                postcondition.closure.clone(),
+                return_type.clone(),
+            );
+            guard(
+                postcondition.cfg.as_ref(),
+                fail(
+                    CheckKind::Postcondition,
+                    postcondition.label.as_ref(),
+                    postcondition.closure.to_token_stream(),
+                    quote! { (#closure)(&#output) },
+                ),
+            )
+        }));
+    let doc = format!(
+        " Fuzzes `{}::{fn_name}` against its spec. Generated by `anodized-fuzz`.",
+        module.to_token_stream().to_string().replace(' ', "")
+    );
+    Ok(parse_quote! {
+        #![doc = #doc]
+        #![cfg_attr(fuzzing, no_main)]
+        #![allow(
+            clippy::let_unit_value,
+            clippy::nonminimal_bool,
+            clippy::redundant_closure_call
+        )]
+
+        #[path = "../driver.rs"]
+        mod driver;
+
+        #[allow(unused_imports)]
+        use #module::*;
+
+        fn run(data: &[u8]) {
+            let Ok((#(#patterns,)*)) = <(#(#types,)*) as ::arbitrary::Arbitrary>::arbitrary_take_rest(
+                ::arbitrary::Unstructured::new(data),
+            ) else {
+                return;
+            };
+            #(#borrows)*
+            #(#assumptions)*
+            #call
+            #(#checks)*
+        }
+
+        driver::fuzz_main!(run);
+    })
+}
+
+/// Whether tokens contain a path that starts with `crate`, `super`, or `self`.
+fn mentions_relative_path(tokens: TokenStream) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(ident) => ident == "crate" || ident == "super" || ident == "self",
+        TokenTree::Group(group) => mentions_relative_path(group.stream()),
+        _ => false,
+    })
+}
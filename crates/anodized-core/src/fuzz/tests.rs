@@ -0,0 +1,133 @@
+use super::*;
+
+use crate::test_util::assert_tokens_eq;
+
+fn reason(sig: Signature) -> String {
+    build_target(
+        &parse_quote! { shapes },
+        &sig,
+        &parse_quote! { requires: true },
+    )
+    .unwrap_err()
+}
+
+#[test]
+fn target() {
+    let spec: Spec = parse_quote! {
+        requires: divisor != 0,
+        captures: dividend as original,
+        ensures: *output <= original,
+    };
+    let sig: Signature = parse_quote! { fn divide(dividend: u32, divisor: u32) -> u32 };
+
+    let expected: syn::File = parse_quote! {
+        #![doc = " Fuzzes `shapes::math::divide` against its spec. Generated by `anodized-fuzz`."]
+        #![cfg_attr(fuzzing, no_main)]
+        #![allow(
+            clippy::let_unit_value,
+            clippy::nonminimal_bool,
+            clippy::redundant_closure_call
+        )]
+
+        #[path = "../driver.rs"]
+        mod driver;
+
+        #[allow(unused_imports)]
+        use shapes::math::*;
+
+        fn run(data: &[u8]) {
+            let Ok((dividend, divisor,)) = <(u32, u32,) as ::arbitrary::Arbitrary>::arbitrary_take_rest(
+                ::arbitrary::Unstructured::new(data),
+            ) else {
+                return;
+            };
+            if !(divisor != 0) {
+                return;
+            }
+            let (original, __anodized_output): (_, u32) =
+                (dividend, shapes::math::divide(dividend, divisor));
+            if !((|output: &u32| *output <= original)(&__anodized_output)) {
+                ::core::panic!("{}", "Postcondition failed: | output | * output <= original");
+            }
+        }
+
+        driver::fuzz_main!(run);
+    };
+
+    let target = build_target(&parse_quote! { shapes::math }, &sig, &spec).unwrap();
+    assert_tokens_eq(&target, &expected);
+}
+
+#[test]
+fn target_with_references() {
+    let spec: Spec = parse_quote! {
+        #[cfg(debug_assertions)]
+        requires: *count > 0,
+        maintains: *count < 100,
+    };
+    let sig: Signature = parse_quote! { fn step(count: &mut u8, name: &str, items: &[u8]) };
+
+    let target = build_target(&parse_quote! { shapes }, &sig, &spec).unwrap();
+
+    let body = target.items[2].to_token_stream().to_string();
+    for expected in [
+        quote! {
+            let Ok((mut count, name, items,)) = <(
+                u8,
+                ::std::string::String,
+                ::std::vec::Vec<u8>,
+            ) as ::arbitrary::Arbitrary>::arbitrary_take_rest
+        },
+        quote! {
+            let count: &mut u8 = &mut count;
+            let name: &str = &name;
+            let items: &[u8] = &items;
+            if cfg!(debug_assertions) {
+                if !(*count > 0) {
+                    return;
+                }
+            }
+            if !(*count < 100) {
+                return;
+            }
+            let __anodized_output: () = shapes::step(count, name, items);
+            if !(*count < 100) {
+                ::core::panic!("{}", "Post-invariant failed: * count < 100");
+            }
+        },
+    ] {
+        assert!(
+            body.contains(&expected.to_string()),
+            "missing `{expected}` in `{body}`"
+        );
+    }
+}
+
+#[test]
+fn unsupported() {
+    assert_eq!(
+        reason(parse_quote! { fn get(&self) -> u8 }),
+        "it's a method"
+    );
+    assert_eq!(reason(parse_quote! { unsafe fn f(x: u8) }), "it's `unsafe`");
+    assert_eq!(
+        reason(parse_quote! { fn first<T>(items: Vec<T>) }),
+        "it's generic"
+    );
+    assert_eq!(
+        reason(parse_quote! { fn f(point: crate::Point) }),
+        "its signature has a path relative to its module"
+    );
+    assert_eq!(
+        reason(parse_quote! { fn f((a, b): (u8, u8)) }),
+        "it has an argument that is a pattern"
+    );
+    assert_eq!(
+        reason(parse_quote! { fn f(name: &'static str) }),
+        "`name` is a reference with a lifetime"
+    );
+    assert_eq!(
+        reason(parse_quote! { fn f(items: impl Iterator<Item = u8>) }),
+        "the type of `items` can't be decoded"
+    );
+}
@@ -1,156 +1,251 @@
 #[cfg(test)]
 mod tests;
 
-use crate::{Spec, instrument::Backend};
+use crate::{
+    Spec,
+    export::{SPEC_CONST_NAME, spec_to_json},
+    instrument::{Backend, Check, CheckKind, Function},
+};
 
 use proc_macro2::Span;
 use quote::{ToTokens, quote};
 use syn::{Block, Ident, ItemFn, Pat, PatIdent, parse::Result, parse_quote};
 
-impl Backend {
-    pub fn instrument_fn(&self, spec: Spec, mut func: ItemFn) -> syn::Result<ItemFn> {
-        let is_async = func.sig.asyncness.is_some();
+/// Instrument a fn with the runtime checks of its spec, built by the given backend.
+pub fn instrument_fn(backend: &dyn Backend, spec: Spec, mut func: ItemFn) -> syn::Result<ItemFn> {
+    let is_async = func.sig.asyncness.is_some();
 
-        // Extract the return type from the function signature
-        let return_type = match &func.sig.output {
-            syn::ReturnType::Default => syn::parse_quote!(()),
-            syn::ReturnType::Type(_, ty) => ty.as_ref().clone(),
-        };
+    // Extract the return type from the function signature
+    let return_type = match &func.sig.output {
+        syn::ReturnType::Default => syn::parse_quote!(()),
+        syn::ReturnType::Type(_, ty) => ty.as_ref().clone(),
+    };
 
-        // Generate the new, instrumented function body.
-        let new_body = self.instrument_fn_body(&spec, &func.block, is_async, &return_type)?;
+    // Blame the caller for violations of preconditions, by reporting panics at the call site.
+    // Async fns don't support this, and `main` and non-Rust ABIs don't allow it.
+    let has_preconditions = !spec.requires.is_empty() || !spec.maintains.is_empty();
+    let allows_track_caller = !is_async
+        && func.sig.ident != "main"
+        && func
+            .sig
+            .abi
+            .as_ref()
+            .is_none_or(|abi| abi.name.as_ref().is_some_and(|name| name.value() == "Rust"));
+    let has_track_caller = func
+        .attrs
+        .iter()
+        .any(|attr| attr.path().is_ident("track_caller"));
+    if has_preconditions && allows_track_caller && !has_track_caller {
+        func.attrs.push(parse_quote! { #[track_caller] });
+    }
+    let track_caller = has_track_caller || (has_preconditions && allows_track_caller);
 
-        // Replace the old function body with the new one.
-        *func.block = new_body;
+    // A spec that selects its own mode overrides the crate's runtime behavior.
+    let mode_backend = spec.mode.map(|mode| backend.for_mode(mode));
+    let backend = mode_backend.as_deref().unwrap_or(backend);
 
-        Ok(func)
-    }
+    // Generate the new, instrumented function body.
+    let new_body = instrument_fn_body(
+        backend,
+        &spec,
+        &func.sig.ident,
+        &func.block,
+        is_async,
+        track_caller,
+        &return_type,
+    )?;
 
-    fn instrument_fn_body(
-        &self,
-        spec: &Spec,
-        original_body: &Block,
-        is_async: bool,
-        return_type: &syn::Type,
-    ) -> Result<Block> {
-        let build_check = self.build_check;
-
-        // The identifier for the return value binding.
-        let output_ident = Pat::Ident(PatIdent {
-            attrs: vec![],
-            by_ref: None,
-            mutability: None,
-            ident: Ident::new("__anodized_output", Span::mixed_site()),
-            subpat: None,
-        });
+    // Replace the old function body with the new one.
+    *func.block = new_body;
+
+    // Keep the spec for tools that work on expanded code, HIR, or MIR.
+    let spec_json = spec_to_json(&func.sig.ident.to_string(), &spec);
+    let spec_const = Ident::new(SPEC_CONST_NAME, Span::call_site());
+    func.block.stmts.insert(
+        0,
+        parse_quote! {
+            #[doc(hidden)]
+            #[allow(dead_code)]
+            const #spec_const: &str = #spec_json;
+        },
+    );
+
+    Ok(func)
+}
 
-        // --- Generate Precondition Checks ---
-        let precondition_checks = spec
-            .requires
-            .iter()
-            .map(|condition| {
-                let closure = condition.closure.to_token_stream();
-                let expr = quote! { (#closure)() };
-                let repr = condition.closure.body.to_token_stream();
-                build_check(
-                    condition.cfg.as_ref(),
-                    &expr,
-                    "Precondition failed: {}",
-                    &repr,
-                )
+fn instrument_fn_body(
+    backend: &dyn Backend,
+    spec: &Spec,
+    fn_name: &Ident,
+    original_body: &Block,
+    is_async: bool,
+    track_caller: bool,
+    return_type: &syn::Type,
+) -> Result<Block> {
+    // The identifier for the return value binding.
+    let output = Ident::new("__anodized_output", Span::mixed_site());
+    let output_ident = Pat::Ident(PatIdent {
+        attrs: vec![],
+        by_ref: None,
+        mutability: None,
+        ident: output.clone(),
+        subpat: None,
+    });
+
+    let function = Function {
+        name: fn_name,
+        spec,
+        is_async,
+        return_type,
+        output: &output,
+    };
+
+    // --- Generate Entry Statements ---
+    let entry = backend.build_entry(&function);
+
+    // --- Generate Precondition Checks ---
+    let precondition_checks = spec
+        .requires
+        .iter()
+        .map(|condition| {
+            let closure = condition.closure.to_token_stream();
+            let expr = quote! { (#closure)() };
+            let repr = condition.closure.body.to_token_stream();
+            backend.build_check(&Check {
+                kind: CheckKind::Precondition,
+                fn_name,
+                cfg: condition.cfg.as_ref(),
+                expr: &expr,
+                repr: &repr,
+                label: condition.label.as_ref(),
+                sample: condition.sample,
+                expensive: condition.expensive,
+                severity: condition.severity,
+                blames_caller: track_caller,
             })
-            .chain(spec.maintains.iter().map(|condition| {
-                let closure = condition.closure.to_token_stream();
-                let expr = quote! { (#closure)() };
-                let repr = condition.closure.body.to_token_stream();
-                build_check(
-                    condition.cfg.as_ref(),
-                    &expr,
-                    "Pre-invariant failed: {}",
-                    &repr,
-                )
-            }));
-
-        // --- Generate Combined Body and Capture Statement ---
-        // Capture values and execute body in a single tuple assignment
-        // This ensures captured values aren't accessible to the body itself
-
-        // Chain capture aliases with output binding
-        let aliases = spec
-            .captures
-            .iter()
-            .map(|cb| &cb.pat)
-            .chain(std::iter::once(&output_ident));
-
-        // Chain capture expressions with body expression
-        let capture_exprs = spec.captures.iter().map(|cb| {
-            let expr = &cb.expr;
-            // Evaluate expression in a closure to prevent early return.
-            quote! { (|| #expr)() }
-        });
+        })
+        .chain(spec.maintains.iter().map(|condition| {
+            let closure = condition.closure.to_token_stream();
+            let expr = quote! { (#closure)() };
+            let repr = condition.closure.body.to_token_stream();
+            backend.build_check(&Check {
+                kind: CheckKind::PreInvariant,
+                fn_name,
+                cfg: condition.cfg.as_ref(),
+                expr: &expr,
+                repr: &repr,
+                label: condition.label.as_ref(),
+                sample: condition.sample,
+                expensive: condition.expensive,
+                severity: condition.severity,
+                blames_caller: track_caller,
+            })
+        }));
 
-        // Chain underscore types with return type for tuple type annotation
-        let types = spec
-            .captures
-            .iter()
-            .map(|_| quote! { _ })
-            .chain(std::iter::once(quote! { #return_type }));
-
-        let body_expr = if is_async {
-            quote! { (async || #original_body)().await }
-        } else {
-            quote! { (|| #original_body)() }
-        };
-
-        let exprs = capture_exprs.chain(std::iter::once(body_expr));
-
-        // Build tuple assignment with type annotation on the tuple
-        let body_and_captures = quote! {
-            let (#(#aliases),*): (#(#types),*) = (#(#exprs),*);
-        };
-
-        // --- Generate Postcondition Checks ---
-        let postcondition_checks = spec
-            .maintains
-            .iter()
-            .map(|condition| {
-                let closure = condition.closure.to_token_stream();
-                let expr = quote! { (#closure)() };
-                let repr = condition.closure.body.to_token_stream();
-                build_check(
-                    condition.cfg.as_ref(),
-                    &expr,
-                    "Post-invariant failed: {}",
-                    &repr,
-                )
+    // --- Generate Combined Body and Capture Statement ---
+    // Capture values and execute body in a single tuple assignment
+    // This ensures captured values aren't accessible to the body itself
+
+    // Chain capture aliases with output binding
+    let aliases = spec
+        .captures
+        .iter()
+        .map(|cb| &cb.pat)
+        .chain(std::iter::once(&output_ident));
+
+    // Chain capture expressions with body expression
+    let capture_exprs = spec
+        .captures
+        .iter()
+        .map(|cb| backend.build_capture(&function, cb));
+
+    // Chain underscore types with return type for tuple type annotation
+    let types = spec
+        .captures
+        .iter()
+        .map(|_| quote! { _ })
+        .chain(std::iter::once(quote! { #return_type }));
+
+    let body_expr = if is_async {
+        let body = backend.build_body(&function, quote! { (async || #original_body)() });
+        quote! { #body.await }
+    } else {
+        backend.build_body(&function, quote! { (|| #original_body)() })
+    };
+
+    let exprs = capture_exprs.chain(std::iter::once(body_expr));
+
+    // Build tuple assignment with type annotation on the tuple
+    let body_and_captures = quote! {
+        let (#(#aliases),*): (#(#types),*) = (#(#exprs),*);
+    };
+
+    // --- Generate Postcondition Checks ---
+    let postcondition_checks = spec
+        .maintains
+        .iter()
+        .map(|condition| {
+            let closure = condition.closure.to_token_stream();
+            let expr = quote! { (#closure)() };
+            let repr = condition.closure.body.to_token_stream();
+            backend.build_check(&Check {
+                kind: CheckKind::PostInvariant,
+                fn_name,
+                cfg: condition.cfg.as_ref(),
+                expr: &expr,
+                repr: &repr,
+                label: condition.label.as_ref(),
+                sample: condition.sample,
+                expensive: condition.expensive,
+                severity: condition.severity,
+                blames_caller: false,
             })
-            .chain(spec.ensures.iter().map(|postcondition| {
-                let closure = annotate_postcondition_closure_argument(
-                    postcondition.closure.clone(),
-                    return_type.clone(),
-                );
-
-                let expr = quote! { (#closure)(&#output_ident) };
-                build_check(
-                    postcondition.cfg.as_ref(),
-                    &expr,
-                    "Postcondition failed: {}",
-                    &postcondition.closure.to_token_stream(),
-                )
-            }));
-
-        Ok(parse_quote! {
-            {
-                #(#precondition_checks)*
-                #body_and_captures
-                #(#postcondition_checks)*
-                #output_ident
-            }
         })
-    }
+        .chain(spec.ensures.iter().map(|postcondition| {
+            let closure = annotate_postcondition_closure_argument(
+                postcondition.closure.clone(),
+                return_type.clone(),
+            );
+
+            let expr = quote! { (#closure)(&#output_ident) };
+            backend.build_check(&Check {
+                kind: CheckKind::Postcondition,
+                fn_name,
+                cfg: postcondition.cfg.as_ref(),
+                expr: &expr,
+                repr: &postcondition.closure.to_token_stream(),
+                label: postcondition.label.as_ref(),
+                sample: postcondition.sample,
+                expensive: postcondition.expensive,
+                severity: postcondition.severity,
+                blames_caller: false,
+            })
+        }))
+        .collect::<Vec<_>>();
+
+    // With `#[track_caller]`, the closure makes postcondition violations blame the callee.
+    let postcondition_checks = if track_caller && !postcondition_checks.is_empty() {
+        quote! { (|| { #(#postcondition_checks)* })(); }
+    } else {
+        quote! { #(#postcondition_checks)* }
+    };
+
+    // --- Generate Exit Statements ---
+    let exit = backend.build_exit(&function);
+
+    Ok(parse_quote! {
+        {
+            #entry
+            #(#precondition_checks)*
+            #body_and_captures
+            #postcondition_checks
+            #exit
+            #output_ident
+        }
+    })
 }
 
-fn annotate_postcondition_closure_argument(
+pub(crate) fn annotate_postcondition_closure_argument(
     mut closure: syn::ExprClosure,
     return_type: syn::Type,
 ) -> syn::ExprClosure {
@@ -1,6 +1,11 @@
-use crate::test_util::assert_tokens_eq;
+use crate::{
+    Capture, Mode,
+    instrument::{BuiltinBackend, LogLevel, Settings, TrapFallback, guard_check},
+    test_util::assert_tokens_eq,
+};
 
 use super::*;
+use proc_macro2::TokenStream;
 use syn::{Block, Type, parse_quote};
 
 fn make_fn_body() -> Block {
@@ -15,6 +20,16 @@ fn make_return_type() -> Type {
     parse_quote! { SomeType }
 }
 
+fn make_fn_name() -> Ident {
+    parse_quote! { some_fn }
+}
+
+/// Remove the spec marker that starts an instrumented fn, see `spec_marker`.
+fn strip_spec_marker(mut func: ItemFn) -> ItemFn {
+    func.block.stmts.remove(0);
+    func
+}
+
 #[test]
 fn simple_requires() {
     let spec: Spec = parse_quote! {
@@ -22,7 +37,9 @@ fn simple_requires() {
     };
     let body = make_fn_body();
     let ret_type = make_return_type();
+    let fn_name = make_fn_name();
     let is_async = false;
+    let track_caller = false;
 
     let expected: Block = parse_quote! {
         {
@@ -32,9 +49,16 @@ fn simple_requires() {
         }
     };
 
-    let observed = Backend::CHECK_AND_PANIC
-        .instrument_fn_body(&spec, &body, is_async, &ret_type)
-        .unwrap();
+    let observed = instrument_fn_body(
+        &BuiltinBackend::CHECK_AND_PANIC,
+        &spec,
+        &fn_name,
+        &body,
+        is_async,
+        track_caller,
+        &ret_type,
+    )
+    .unwrap();
     assert_tokens_eq(&observed, &expected);
 }
 
@@ -45,7 +69,9 @@ fn requires_disable_runtime_checks() {
     };
     let body = make_fn_body();
     let ret_type = make_return_type();
+    let fn_name = make_fn_name();
     let is_async = false;
+    let track_caller = false;
 
     let expected: Block = parse_quote! {
         {
@@ -57,9 +83,16 @@ fn requires_disable_runtime_checks() {
         }
     };
 
-    let observed = Backend::NO_CHECK
-        .instrument_fn_body(&spec, &body, is_async, &ret_type)
-        .unwrap();
+    let observed = instrument_fn_body(
+        &BuiltinBackend::NO_CHECK,
+        &spec,
+        &fn_name,
+        &body,
+        is_async,
+        track_caller,
+        &ret_type,
+    )
+    .unwrap();
     assert_tokens_eq(&observed, &expected);
 }
 
@@ -70,7 +103,9 @@ fn requires_no_panic_runtime() {
     };
     let body = make_fn_body();
     let ret_type = make_return_type();
+    let fn_name = make_fn_name();
     let is_async = false;
+    let track_caller = false;
 
     let expected: Block = parse_quote! {
         {
@@ -82,12 +117,675 @@ fn requires_no_panic_runtime() {
         }
     };
 
-    let observed = Backend::CHECK_AND_PRINT
-        .instrument_fn_body(&spec, &body, is_async, &ret_type)
-        .unwrap();
+    let observed = instrument_fn_body(
+        &BuiltinBackend::CHECK_AND_PRINT,
+        &spec,
+        &fn_name,
+        &body,
+        is_async,
+        track_caller,
+        &ret_type,
+    )
+    .unwrap();
+    assert_tokens_eq(&observed, &expected);
+}
+
+#[test]
+fn requires_log_runtime() {
+    let spec: Spec = parse_quote! {
+        requires: CONDITION_1,
+    };
+    let body = make_fn_body();
+    let ret_type = make_return_type();
+    let fn_name = make_fn_name();
+    let is_async = false;
+    let track_caller = false;
+
+    let expected: Block = parse_quote! {
+        {
+            if !((| | CONDITION_1)()) {
+                ::log::log!(::log::Level::Warn, "Precondition failed: {}", "CONDITION_1");
+            }
+            let (__anodized_output): (#ret_type) = ((|| #body)());
+            __anodized_output
+        }
+    };
+
+    let observed = instrument_fn_body(
+        &BuiltinBackend::check_and_log(LogLevel::Warn),
+        &spec,
+        &fn_name,
+        &body,
+        is_async,
+        track_caller,
+        &ret_type,
+    )
+    .unwrap();
+    assert_tokens_eq(&observed, &expected);
+}
+
+#[test]
+fn requires_trace_runtime() {
+    let spec: Spec = parse_quote! {
+        #[label = "LABEL_1"]
+        requires: CONDITION_1,
+    };
+    let body = make_fn_body();
+    let ret_type = make_return_type();
+    let fn_name = make_fn_name();
+    let is_async = false;
+    let track_caller = false;
+
+    let expected: Block = parse_quote! {
+        {
+            if !((| | CONDITION_1)()) {
+                ::tracing::error!(
+                    function = "some_fn",
+                    kind = "precondition",
+                    condition = "CONDITION_1",
+                    label = "LABEL_1",
+                    "Precondition failed: LABEL_1: {}",
+                    "CONDITION_1"
+                );
+            }
+            let (__anodized_output): (#ret_type) = ((|| #body)());
+            __anodized_output
+        }
+    };
+
+    let observed = instrument_fn_body(
+        &BuiltinBackend::CHECK_AND_TRACE,
+        &spec,
+        &fn_name,
+        &body,
+        is_async,
+        track_caller,
+        &ret_type,
+    )
+    .unwrap();
+    assert_tokens_eq(&observed, &expected);
+}
+
+#[test]
+fn ensures_trace_runtime_with_spans() {
+    let spec: Spec = parse_quote! {
+        ensures: CONDITION_1,
+    };
+    let body = make_fn_body();
+    let ret_type = make_return_type();
+    let fn_name = make_fn_name();
+    let is_async = false;
+    let track_caller = false;
+
+    let expected: Block = parse_quote! {
+        {
+            let __anodized_span = ::tracing::info_span!("spec", function = "some_fn");
+            let __anodized_span_guard = __anodized_span.enter();
+            let (__anodized_output): (#ret_type) = ((|| #body)());
+            if !((|output: &#ret_type| CONDITION_1)(&__anodized_output)) {
+                ::tracing::error!(
+                    parent: &__anodized_span,
+                    function = "some_fn",
+                    kind = "postcondition",
+                    condition = "| output | CONDITION_1",
+                    "Postcondition failed: {}",
+                    "| output | CONDITION_1"
+                );
+            }
+            __anodized_output
+        }
+    };
+
+    let observed = instrument_fn_body(
+        &BuiltinBackend::check_and_trace(true),
+        &spec,
+        &fn_name,
+        &body,
+        is_async,
+        track_caller,
+        &ret_type,
+    )
+    .unwrap();
+    assert_tokens_eq(&observed, &expected);
+}
+
+#[test]
+fn async_trace_runtime_with_spans() {
+    let spec: Spec = parse_quote! {
+        ensures: CONDITION_1,
+    };
+    let body = make_fn_body();
+    let ret_type = make_return_type();
+    let fn_name = make_fn_name();
+    let is_async = true;
+    let track_caller = false;
+
+    let expected: Block = parse_quote! {
+        {
+            let __anodized_span = ::tracing::info_span!("spec", function = "some_fn");
+            let (__anodized_output): (#ret_type) = (::tracing::Instrument::instrument(
+                (async || #body)(),
+                __anodized_span.clone()
+            )
+            .await);
+            if !((|output: &#ret_type| CONDITION_1)(&__anodized_output)) {
+                ::tracing::error!(
+                    parent: &__anodized_span,
+                    function = "some_fn",
+                    kind = "postcondition",
+                    condition = "| output | CONDITION_1",
+                    "Postcondition failed: {}",
+                    "| output | CONDITION_1"
+                );
+            }
+            __anodized_output
+        }
+    };
+
+    let observed = instrument_fn_body(
+        &BuiltinBackend::check_and_trace(true),
+        &spec,
+        &fn_name,
+        &body,
+        is_async,
+        track_caller,
+        &ret_type,
+    )
+    .unwrap();
+    assert_tokens_eq(&observed, &expected);
+}
+
+#[test]
+fn requires_trap_runtime_blames_caller() {
+    let spec: Spec = parse_quote! {
+        requires: CONDITION_1,
+    };
+    let body = make_fn_body();
+    let ret_type = make_return_type();
+    let fn_name = make_fn_name();
+    let is_async = false;
+    let track_caller = true;
+
+    let expected: Block = parse_quote! {
+        {
+            if !((| | CONDITION_1)()) {
+                ::anodized::__private::trap_or_print(format_args!(
+                    "Precondition failed: {}, called at {}",
+                    "CONDITION_1",
+                    ::core::panic::Location::caller()
+                ));
+            }
+            let (__anodized_output): (#ret_type) = ((|| #body)());
+            __anodized_output
+        }
+    };
+
+    let observed = instrument_fn_body(
+        &BuiltinBackend::check_and_trap(TrapFallback::Print),
+        &spec,
+        &fn_name,
+        &body,
+        is_async,
+        track_caller,
+        &ret_type,
+    )
+    .unwrap();
+    assert_tokens_eq(&observed, &expected);
+}
+
+#[test]
+fn requires_trap_runtime() {
+    let spec: Spec = parse_quote! {
+        requires: CONDITION_1,
+    };
+    let body = make_fn_body();
+    let ret_type = make_return_type();
+    let fn_name = make_fn_name();
+    let is_async = false;
+    let track_caller = false;
+
+    let expected: Block = parse_quote! {
+        {
+            if !((| | CONDITION_1)()) {
+                ::anodized::__private::trap_or_print(format_args!("Precondition failed: {}", "CONDITION_1"));
+            }
+            let (__anodized_output): (#ret_type) = ((|| #body)());
+            __anodized_output
+        }
+    };
+
+    let observed = instrument_fn_body(
+        &BuiltinBackend::check_and_trap(TrapFallback::Print),
+        &spec,
+        &fn_name,
+        &body,
+        is_async,
+        track_caller,
+        &ret_type,
+    )
+    .unwrap();
+    assert_tokens_eq(&observed, &expected);
+}
+
+#[test]
+fn labeled_requires() {
+    let spec: Spec = parse_quote! {
+        #[label = "must hold {always}"]
+        requires: CONDITION_1,
+    };
+    let body = make_fn_body();
+    let ret_type = make_return_type();
+    let fn_name = make_fn_name();
+    let is_async = false;
+    let track_caller = false;
+
+    let expected: Block = parse_quote! {
+        {
+            assert!((| | CONDITION_1)(), "Precondition failed: must hold {{always}}: {}", "CONDITION_1");
+            let (__anodized_output): (#ret_type) = ((|| #body)());
+            __anodized_output
+        }
+    };
+
+    let observed = instrument_fn_body(
+        &BuiltinBackend::CHECK_AND_PANIC,
+        &spec,
+        &fn_name,
+        &body,
+        is_async,
+        track_caller,
+        &ret_type,
+    )
+    .unwrap();
+    assert_tokens_eq(&observed, &expected);
+}
+
+#[test]
+fn condition_severities() {
+    let spec: Spec = parse_quote! {
+        #[warn]
+        requires: CONDITION_1,
+        #[deny]
+        ensures: CONDITION_2,
+    };
+    let body = make_fn_body();
+    let ret_type = make_return_type();
+    let fn_name = make_fn_name();
+    let is_async = false;
+    let track_caller = false;
+
+    let expected: Block = parse_quote! {
+        {
+            if !((| | CONDITION_1)()) {
+                eprintln!("Precondition failed: {}", "CONDITION_1");
+            }
+            let (__anodized_output): (#ret_type) = ((|| #body)());
+            assert!((|output: &#ret_type| CONDITION_2)(&__anodized_output), "Postcondition failed: {}", "| output | CONDITION_2");
+            __anodized_output
+        }
+    };
+
+    let observed = instrument_fn_body(
+        &BuiltinBackend::CHECK_AND_PANIC,
+        &spec,
+        &fn_name,
+        &body,
+        is_async,
+        track_caller,
+        &ret_type,
+    )
+    .unwrap();
+    assert_tokens_eq(&observed, &expected);
+
+    // `#[deny]` panics even when checks are disabled, while `#[warn]` doesn't report.
+    let expected: Block = parse_quote! {
+        {
+            if false {
+                assert!((| | CONDITION_1)(), "Precondition failed: {}", "CONDITION_1");
+            }
+            let (__anodized_output): (#ret_type) = ((|| #body)());
+            assert!((|output: &#ret_type| CONDITION_2)(&__anodized_output), "Postcondition failed: {}", "| output | CONDITION_2");
+            __anodized_output
+        }
+    };
+
+    let observed = instrument_fn_body(
+        &BuiltinBackend::NO_CHECK,
+        &spec,
+        &fn_name,
+        &body,
+        is_async,
+        track_caller,
+        &ret_type,
+    )
+    .unwrap();
+    assert_tokens_eq(&observed, &expected);
+}
+
+#[test]
+fn mode_overrides_backend() {
+    let spec: Spec = parse_quote! {
+        mode: log,
+        requires: CONDITION_1,
+    };
+    let func: ItemFn = parse_quote! {
+        fn some_fn() -> SomeType {
+            this_is_the_body()
+        }
+    };
+    let body = make_fn_body();
+    let ret_type = make_return_type();
+
+    // The mode keeps the settings of the crate's backend.
+    let expected: Block = parse_quote! {
+        {
+            if !((| | CONDITION_1)()) {
+                ::log::log!(
+                    ::log::Level::Warn,
+                    "Precondition failed: {}, called at {}",
+                    "CONDITION_1",
+                    ::core::panic::Location::caller()
+                );
+            }
+            let (__anodized_output): (#ret_type) = ((|| #body)());
+            __anodized_output
+        }
+    };
+
+    let backend = BuiltinBackend::new(
+        Mode::Panic,
+        Settings {
+            log_level: LogLevel::Warn,
+            ..Settings::DEFAULT
+        },
+    );
+    let observed = strip_spec_marker(instrument_fn(&backend, spec, func).unwrap());
+    assert_tokens_eq(&observed.block, &expected);
+}
+
+#[test]
+fn sampled_requires() {
+    let spec: Spec = parse_quote! {
+        #[cfg(not(test))]
+        #[sample(1 in 1000)]
+        requires: CONDITION_1,
+    };
+    let body = make_fn_body();
+    let ret_type = make_return_type();
+    let fn_name = make_fn_name();
+    let is_async = false;
+    let track_caller = false;
+
+    let expected: Block = parse_quote! {
+        {
+            if cfg!(not(test)) {
+                if ::anodized::__private::sample!(1u32, 1000u32) {
+                    assert!((| | CONDITION_1)(), "Precondition failed: {}", "CONDITION_1");
+                }
+            }
+            let (__anodized_output): (#ret_type) = ((|| #body)());
+            __anodized_output
+        }
+    };
+
+    let observed = instrument_fn_body(
+        &BuiltinBackend::CHECK_AND_PANIC,
+        &spec,
+        &fn_name,
+        &body,
+        is_async,
+        track_caller,
+        &ret_type,
+    )
+    .unwrap();
+    assert_tokens_eq(&observed, &expected);
+}
+
+#[test]
+fn skip_expensive() {
+    let spec: Spec = parse_quote! {
+        requires: CONDITION_1,
+        #[expensive]
+        requires: CONDITION_2,
+    };
+    let body = make_fn_body();
+    let ret_type = make_return_type();
+    let fn_name = make_fn_name();
+    let is_async = false;
+    let track_caller = false;
+
+    let expected: Block = parse_quote! {
+        {
+            assert!((| | CONDITION_1)(), "Precondition failed: {}", "CONDITION_1");
+            if false {
+                assert!((| | CONDITION_2)(), "Precondition failed: {}", "CONDITION_2");
+            }
+            let (__anodized_output): (#ret_type) = ((|| #body)());
+            __anodized_output
+        }
+    };
+
+    let backend = BuiltinBackend::new(
+        Mode::Panic,
+        Settings {
+            skip_expensive: true,
+            ..Settings::DEFAULT
+        },
+    );
+    let observed = instrument_fn_body(
+        &backend,
+        &spec,
+        &fn_name,
+        &body,
+        is_async,
+        track_caller,
+        &ret_type,
+    )
+    .unwrap();
     assert_tokens_eq(&observed, &expected);
 }
 
+#[test]
+fn print_fn() {
+    let spec: Spec = parse_quote! {
+        requires: CONDITION_1,
+        #[warn]
+        ensures: CONDITION_2,
+    };
+    let body = make_fn_body();
+    let ret_type = make_return_type();
+    let fn_name = make_fn_name();
+    let is_async = false;
+    let track_caller = false;
+
+    let expected: Block = parse_quote! {
+        {
+            if !((| | CONDITION_1)()) {
+                crate::report(format_args!("Precondition failed: {}", "CONDITION_1"));
+            }
+            let (__anodized_output): (#ret_type) = ((|| #body)());
+            if !((|output: &#ret_type| CONDITION_2)(&__anodized_output)) {
+                crate::report(format_args!("Postcondition failed: {}", "| output | CONDITION_2"));
+            }
+            __anodized_output
+        }
+    };
+
+    let backend = BuiltinBackend::new(
+        Mode::Print,
+        Settings {
+            print_fn: Some(parse_quote! { crate::report }),
+            ..Settings::DEFAULT
+        },
+    );
+    let observed = instrument_fn_body(
+        &backend,
+        &spec,
+        &fn_name,
+        &body,
+        is_async,
+        track_caller,
+        &ret_type,
+    )
+    .unwrap();
+    assert_tokens_eq(&observed, &expected);
+}
+
+#[test]
+fn runtime_control() {
+    let spec: Spec = parse_quote! {
+        #[cfg(test)]
+        requires: CONDITION_1,
+        #[deny]
+        ensures: CONDITION_2,
+    };
+    let body = make_fn_body();
+    let ret_type = make_return_type();
+    let fn_name = make_fn_name();
+    let is_async = false;
+    let track_caller = false;
+
+    // `#[deny]` conditions can't be turned off at runtime.
+    let expected: Block = parse_quote! {
+        {
+            if cfg!(test) {
+                if ::anodized::__private::checks_enabled() {
+                    assert!((| | CONDITION_1)(), "Precondition failed: {}", "CONDITION_1");
+                }
+            }
+            let (__anodized_output): (#ret_type) = ((|| #body)());
+            assert!((|output: &#ret_type| CONDITION_2)(&__anodized_output), "Postcondition failed: {}", "| output | CONDITION_2");
+            __anodized_output
+        }
+    };
+
+    let backend = BuiltinBackend::new(
+        Mode::Panic,
+        Settings {
+            runtime_control: true,
+            ..Settings::DEFAULT
+        },
+    );
+    let observed = instrument_fn_body(
+        &backend,
+        &spec,
+        &fn_name,
+        &body,
+        is_async,
+        track_caller,
+        &ret_type,
+    )
+    .unwrap();
+    assert_tokens_eq(&observed, &expected);
+}
+
+#[test]
+fn stats() {
+    let spec: Spec = parse_quote! {
+        #[label = "first"]
+        requires: CONDITION_1,
+    };
+    let body = make_fn_body();
+    let ret_type = make_return_type();
+    let fn_name = make_fn_name();
+    let is_async = false;
+    let track_caller = false;
+
+    let expected: Block = parse_quote! {
+        {
+            if !({
+                static COUNTER: ::anodized::__private::Counter =
+                    ::anodized::__private::Counter::new(::anodized::stats::Condition {
+                        module: ::core::module_path!(),
+                        function: "some_fn",
+                        kind: "precondition",
+                        condition: "CONDITION_1",
+                        label: ::core::option::Option::Some("first"),
+                        file: ::core::file!(),
+                        line: ::core::line!(),
+                    });
+                COUNTER.record_timed(|| (| | CONDITION_1)())
+            }) {
+                eprintln!("Precondition failed: first: {}", "CONDITION_1");
+            }
+            let (__anodized_output): (#ret_type) = ((|| #body)());
+            __anodized_output
+        }
+    };
+
+    let backend = BuiltinBackend::new(
+        Mode::Print,
+        Settings {
+            stats: true,
+            stats_timing: true,
+            ..Settings::DEFAULT
+        },
+    );
+    let observed = instrument_fn_body(
+        &backend,
+        &spec,
+        &fn_name,
+        &body,
+        is_async,
+        track_caller,
+        &ret_type,
+    )
+    .unwrap();
+    assert_tokens_eq(&observed, &expected);
+}
+
+#[test]
+fn track_caller_blames_caller_for_preconditions() {
+    let spec: Spec = parse_quote! {
+        requires: CONDITION_1,
+        ensures: CONDITION_2,
+    };
+    let func: ItemFn = parse_quote! {
+        fn some_fn() -> SomeType {
+            this_is_the_body()
+        }
+    };
+    let body = make_fn_body();
+    let ret_type = make_return_type();
+
+    // The closure keeps postcondition violations at the callee.
+    let expected: ItemFn = parse_quote! {
+        #[track_caller]
+        fn some_fn() -> SomeType {
+            assert!((| | CONDITION_1)(), "Precondition failed: {}", "CONDITION_1");
+            let (__anodized_output): (#ret_type) = ((|| #body)());
+            (|| {
+                assert!((|output: &#ret_type| CONDITION_2)(&__anodized_output), "Postcondition failed: {}", "| output | CONDITION_2");
+            })();
+            __anodized_output
+        }
+    };
+
+    let observed =
+        strip_spec_marker(instrument_fn(&BuiltinBackend::CHECK_AND_PANIC, spec, func).unwrap());
+    assert_tokens_eq(&observed, &expected);
+}
+
+#[test]
+fn track_caller_not_applicable() {
+    let funcs: [ItemFn; 4] = [
+        // Without preconditions, there's nothing to blame the caller for.
+        parse_quote! { fn some_fn() {} },
+        parse_quote! { async fn some_fn() {} },
+        parse_quote! { extern "C" fn some_fn() {} },
+        parse_quote! { fn main() {} },
+    ];
+    for (i, func) in funcs.into_iter().enumerate() {
+        let spec: Spec = if i == 0 {
+            parse_quote! { ensures: CONDITION_1 }
+        } else {
+            parse_quote! { requires: CONDITION_1 }
+        };
+        let observed = instrument_fn(&BuiltinBackend::CHECK_AND_PANIC, spec, func).unwrap();
+        assert!(observed.attrs.is_empty(), "{}", observed.sig.ident);
+    }
+}
+
 #[test]
 fn simple_maintains() {
     let spec: Spec = parse_quote! {
@@ -95,7 +793,9 @@ fn simple_maintains() {
     };
     let body = make_fn_body();
     let ret_type = make_return_type();
+    let fn_name = make_fn_name();
     let is_async = false;
+    let track_caller = false;
 
     let expected: Block = parse_quote! {
         {
@@ -106,9 +806,16 @@ fn simple_maintains() {
         }
     };
 
-    let observed = Backend::CHECK_AND_PANIC
-        .instrument_fn_body(&spec, &body, is_async, &ret_type)
-        .unwrap();
+    let observed = instrument_fn_body(
+        &BuiltinBackend::CHECK_AND_PANIC,
+        &spec,
+        &fn_name,
+        &body,
+        is_async,
+        track_caller,
+        &ret_type,
+    )
+    .unwrap();
     assert_tokens_eq(&observed, &expected);
 }
 
@@ -119,7 +826,9 @@ fn simple_ensures() {
     };
     let body = make_fn_body();
     let ret_type = make_return_type();
+    let fn_name = make_fn_name();
     let is_async = false;
+    let track_caller = false;
 
     let expected: Block = parse_quote! {
         {
@@ -129,9 +838,16 @@ fn simple_ensures() {
         }
     };
 
-    let observed = Backend::CHECK_AND_PANIC
-        .instrument_fn_body(&spec, &body, is_async, &ret_type)
-        .unwrap();
+    let observed = instrument_fn_body(
+        &BuiltinBackend::CHECK_AND_PANIC,
+        &spec,
+        &fn_name,
+        &body,
+        is_async,
+        track_caller,
+        &ret_type,
+    )
+    .unwrap();
     assert_tokens_eq(&observed, &expected);
 }
 
@@ -143,7 +859,9 @@ fn simple_requires_and_maintains() {
     };
     let body = make_fn_body();
     let ret_type = make_return_type();
+    let fn_name = make_fn_name();
     let is_async = false;
+    let track_caller = false;
 
     let expected: Block = parse_quote! {
         {
@@ -155,9 +873,16 @@ fn simple_requires_and_maintains() {
         }
     };
 
-    let observed = Backend::CHECK_AND_PANIC
-        .instrument_fn_body(&spec, &body, is_async, &ret_type)
-        .unwrap();
+    let observed = instrument_fn_body(
+        &BuiltinBackend::CHECK_AND_PANIC,
+        &spec,
+        &fn_name,
+        &body,
+        is_async,
+        track_caller,
+        &ret_type,
+    )
+    .unwrap();
     assert_tokens_eq(&observed, &expected);
 }
 
@@ -169,7 +894,9 @@ fn simple_requires_and_ensures() {
     };
     let body = make_fn_body();
     let ret_type = make_return_type();
+    let fn_name = make_fn_name();
     let is_async = false;
+    let track_caller = false;
 
     let expected: Block = parse_quote! {
         {
@@ -180,9 +907,16 @@ fn simple_requires_and_ensures() {
         }
     };
 
-    let observed = Backend::CHECK_AND_PANIC
-        .instrument_fn_body(&spec, &body, is_async, &ret_type)
-        .unwrap();
+    let observed = instrument_fn_body(
+        &BuiltinBackend::CHECK_AND_PANIC,
+        &spec,
+        &fn_name,
+        &body,
+        is_async,
+        track_caller,
+        &ret_type,
+    )
+    .unwrap();
     assert_tokens_eq(&observed, &expected);
 }
 
@@ -194,7 +928,9 @@ fn simple_maintains_and_ensures() {
     };
     let body = make_fn_body();
     let ret_type = make_return_type();
+    let fn_name = make_fn_name();
     let is_async = false;
+    let track_caller = false;
 
     let expected: Block = parse_quote! {
         {
@@ -206,9 +942,16 @@ fn simple_maintains_and_ensures() {
         }
     };
 
-    let observed = Backend::CHECK_AND_PANIC
-        .instrument_fn_body(&spec, &body, is_async, &ret_type)
-        .unwrap();
+    let observed = instrument_fn_body(
+        &BuiltinBackend::CHECK_AND_PANIC,
+        &spec,
+        &fn_name,
+        &body,
+        is_async,
+        track_caller,
+        &ret_type,
+    )
+    .unwrap();
     assert_tokens_eq(&observed, &expected);
 }
 
@@ -221,7 +964,9 @@ fn simple_requires_maintains_and_ensures() {
     };
     let body = make_fn_body();
     let ret_type = make_return_type();
+    let fn_name = make_fn_name();
     let is_async = false;
+    let track_caller = false;
 
     let expected: Block = parse_quote! {
         {
@@ -234,9 +979,16 @@ fn simple_requires_maintains_and_ensures() {
         }
     };
 
-    let observed = Backend::CHECK_AND_PANIC
-        .instrument_fn_body(&spec, &body, is_async, &ret_type)
-        .unwrap();
+    let observed = instrument_fn_body(
+        &BuiltinBackend::CHECK_AND_PANIC,
+        &spec,
+        &fn_name,
+        &body,
+        is_async,
+        track_caller,
+        &ret_type,
+    )
+    .unwrap();
     assert_tokens_eq(&observed, &expected);
 }
 
@@ -249,7 +1001,9 @@ fn simple_async_requires_maintains_and_ensures() {
     };
     let body = make_fn_body();
     let ret_type = make_return_type();
+    let fn_name = make_fn_name();
     let is_async = true;
+    let track_caller = false;
 
     let expected: Block = parse_quote! {
         {
@@ -262,9 +1016,16 @@ fn simple_async_requires_maintains_and_ensures() {
         }
     };
 
-    let observed = Backend::CHECK_AND_PANIC
-        .instrument_fn_body(&spec, &body, is_async, &ret_type)
-        .unwrap();
+    let observed = instrument_fn_body(
+        &BuiltinBackend::CHECK_AND_PANIC,
+        &spec,
+        &fn_name,
+        &body,
+        is_async,
+        track_caller,
+        &ret_type,
+    )
+    .unwrap();
     assert_tokens_eq(&observed, &expected);
 }
 
@@ -277,7 +1038,9 @@ fn multiple_conditions_in_clauses() {
     };
     let body = make_fn_body();
     let ret_type = make_return_type();
+    let fn_name = make_fn_name();
     let is_async = false;
+    let track_caller = false;
 
     let expected: Block = parse_quote! {
         {
@@ -294,9 +1057,16 @@ fn multiple_conditions_in_clauses() {
         }
     };
 
-    let observed = Backend::CHECK_AND_PANIC
-        .instrument_fn_body(&spec, &body, is_async, &ret_type)
-        .unwrap();
+    let observed = instrument_fn_body(
+        &BuiltinBackend::CHECK_AND_PANIC,
+        &spec,
+        &fn_name,
+        &body,
+        is_async,
+        track_caller,
+        &ret_type,
+    )
+    .unwrap();
     assert_tokens_eq(&observed, &expected);
 }
 
@@ -308,7 +1078,9 @@ fn binds_parameter() {
     };
     let body = make_fn_body();
     let ret_type = make_return_type();
+    let fn_name = make_fn_name();
     let is_async = false;
+    let track_caller = false;
 
     let expected: Block = parse_quote! {
         {
@@ -318,9 +1090,16 @@ fn binds_parameter() {
         }
     };
 
-    let observed = Backend::CHECK_AND_PANIC
-        .instrument_fn_body(&spec, &body, is_async, &ret_type)
-        .unwrap();
+    let observed = instrument_fn_body(
+        &BuiltinBackend::CHECK_AND_PANIC,
+        &spec,
+        &fn_name,
+        &body,
+        is_async,
+        track_caller,
+        &ret_type,
+    )
+    .unwrap();
     assert_tokens_eq(&observed, &expected);
 }
 
@@ -336,7 +1115,9 @@ fn ensures_with_mixed_conditions() {
     };
     let body = make_fn_body();
     let ret_type = make_return_type();
+    let fn_name = make_fn_name();
     let is_async = false;
+    let track_caller = false;
 
     let expected: Block = parse_quote! {
         {
@@ -349,9 +1130,16 @@ fn ensures_with_mixed_conditions() {
         }
     };
 
-    let observed = Backend::CHECK_AND_PANIC
-        .instrument_fn_body(&spec, &body, is_async, &ret_type)
-        .unwrap();
+    let observed = instrument_fn_body(
+        &BuiltinBackend::CHECK_AND_PANIC,
+        &spec,
+        &fn_name,
+        &body,
+        is_async,
+        track_caller,
+        &ret_type,
+    )
+    .unwrap();
     assert_tokens_eq(&observed, &expected);
 }
 
@@ -367,7 +1155,9 @@ fn cfg_attributes() {
     };
     let body = make_fn_body();
     let ret_type = make_return_type();
+    let fn_name = make_fn_name();
     let is_async = false;
+    let track_caller = false;
 
     let expected: Block = parse_quote! {
         {
@@ -388,9 +1178,16 @@ fn cfg_attributes() {
         }
     };
 
-    let observed = Backend::CHECK_AND_PANIC
-        .instrument_fn_body(&spec, &body, is_async, &ret_type)
-        .unwrap();
+    let observed = instrument_fn_body(
+        &BuiltinBackend::CHECK_AND_PANIC,
+        &spec,
+        &fn_name,
+        &body,
+        is_async,
+        track_caller,
+        &ret_type,
+    )
+    .unwrap();
     assert_tokens_eq(&observed, &expected);
 }
 
@@ -405,7 +1202,9 @@ fn cfg_on_single_and_list_conditions() {
     };
     let body = make_fn_body();
     let ret_type = make_return_type();
+    let fn_name = make_fn_name();
     let is_async = false;
+    let track_caller = false;
 
     let expected: Block = parse_quote! {
         {
@@ -427,9 +1226,16 @@ fn cfg_on_single_and_list_conditions() {
         }
     };
 
-    let observed = Backend::CHECK_AND_PANIC
-        .instrument_fn_body(&spec, &body, is_async, &ret_type)
-        .unwrap();
+    let observed = instrument_fn_body(
+        &BuiltinBackend::CHECK_AND_PANIC,
+        &spec,
+        &fn_name,
+        &body,
+        is_async,
+        track_caller,
+        &ret_type,
+    )
+    .unwrap();
     assert_tokens_eq(&observed, &expected);
 }
 
@@ -448,7 +1254,9 @@ fn complex_mixed_conditions() {
     };
     let body = make_fn_body();
     let ret_type = make_return_type();
+    let fn_name = make_fn_name();
     let is_async = false;
+    let track_caller = false;
 
     let expected: Block = parse_quote! {
         {
@@ -481,9 +1289,16 @@ fn complex_mixed_conditions() {
         }
     };
 
-    let observed = Backend::CHECK_AND_PANIC
-        .instrument_fn_body(&spec, &body, is_async, &ret_type)
-        .unwrap();
+    let observed = instrument_fn_body(
+        &BuiltinBackend::CHECK_AND_PANIC,
+        &spec,
+        &fn_name,
+        &body,
+        is_async,
+        track_caller,
+        &ret_type,
+    )
+    .unwrap();
     assert_tokens_eq(&observed, &expected);
 }
 
@@ -502,7 +1317,9 @@ fn captures() {
     };
     let body = make_fn_body();
     let ret_type = make_return_type();
+    let fn_name = make_fn_name();
     let is_async = false;
+    let track_caller = false;
 
     let expected: Block = parse_quote! {
         {
@@ -514,8 +1331,138 @@ fn captures() {
         }
     };
 
-    let observed = Backend::CHECK_AND_PANIC
-        .instrument_fn_body(&spec, &body, is_async, &ret_type)
-        .unwrap();
+    let observed = instrument_fn_body(
+        &BuiltinBackend::CHECK_AND_PANIC,
+        &spec,
+        &fn_name,
+        &body,
+        is_async,
+        track_caller,
+        &ret_type,
+    )
+    .unwrap();
+    assert_tokens_eq(&observed, &expected);
+}
+
+/// A backend outside of this crate, which counts calls and violations as metrics.
+struct Metrics;
+
+impl Backend for Metrics {
+    fn build_check(&self, check: &Check) -> TokenStream {
+        let expr = check.expr;
+        let kind = check.kind.name();
+        let tokens = quote! {
+            if !(#expr) {
+                metrics::violation(#kind);
+            }
+        };
+        guard_check(check, tokens)
+    }
+
+    fn build_entry(&self, func: &Function) -> TokenStream {
+        let name = func.name.to_string();
+        quote! { let __metrics_timer = metrics::start(#name); }
+    }
+
+    fn build_exit(&self, func: &Function) -> TokenStream {
+        let output = func.output;
+        quote! { metrics::stop(__metrics_timer, &#output); }
+    }
+
+    fn build_capture(&self, _func: &Function, capture: &Capture) -> TokenStream {
+        let expr = &capture.expr;
+        quote! { metrics::captured(#expr) }
+    }
+}
+
+#[test]
+fn custom_backend() {
+    let spec: Spec = parse_quote! {
+        #[cfg(test)]
+        requires: CONDITION_1,
+        captures: EXPR_1 as alias1,
+        ensures: CONDITION_2,
+    };
+    let body = make_fn_body();
+    let ret_type = make_return_type();
+    let fn_name = make_fn_name();
+    let is_async = false;
+    let track_caller = false;
+
+    let expected: Block = parse_quote! {
+        {
+            let __metrics_timer = metrics::start("some_fn");
+            if cfg!(test) {
+                if !((| | CONDITION_1)()) {
+                    metrics::violation("precondition");
+                }
+            }
+            let (alias1, __anodized_output): (_, #ret_type) = (metrics::captured(EXPR_1), (|| #body)());
+            if !((|output: &#ret_type| CONDITION_2)(&__anodized_output)) {
+                metrics::violation("postcondition");
+            }
+            metrics::stop(__metrics_timer, &__anodized_output);
+            __anodized_output
+        }
+    };
+
+    let observed = instrument_fn_body(
+        &Metrics,
+        &spec,
+        &fn_name,
+        &body,
+        is_async,
+        track_caller,
+        &ret_type,
+    )
+    .unwrap();
     assert_tokens_eq(&observed, &expected);
 }
+
+#[test]
+fn custom_backend_with_mode() {
+    let spec: Spec = parse_quote! {
+        mode: print,
+        requires: CONDITION_1,
+    };
+    let func: ItemFn = parse_quote! {
+        fn some_fn() {}
+    };
+
+    let expected: ItemFn = parse_quote! {
+        #[track_caller]
+        fn some_fn() {
+            if !((| | CONDITION_1)()) {
+                eprintln!(
+                    "Precondition failed: {}, called at {}",
+                    "CONDITION_1",
+                    ::core::panic::Location::caller()
+                );
+            }
+            let (__anodized_output): (()) = ((|| {})());
+            __anodized_output
+        }
+    };
+
+    let observed = strip_spec_marker(instrument_fn(&Metrics, spec, func).unwrap());
+    assert_tokens_eq(&observed, &expected);
+}
+
+#[test]
+fn spec_marker() {
+    let spec: Spec = parse_quote! {
+        requires: x > 0,
+    };
+    let func: ItemFn = parse_quote! {
+        fn some_fn(x: i32) {}
+    };
+
+    let expected: syn::Stmt = parse_quote! {
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        const __ANODIZED_SPEC: &str = "{\"version\":1,\"function\":\"some_fn\",\"mode\":null,\"requires\":[{\"condition\":\"x > 0\",\"span\":null,\"cfg\":null,\"label\":null,\"severity\":null,\"sample\":null,\"expensive\":false}],\"maintains\":[],\"captures\":[],\"ensures\":[]}";
+    };
+
+    let observed = instrument_fn(&BuiltinBackend::NO_CHECK, spec, func).unwrap();
+    assert_tokens_eq(&observed.block.stmts[0], &expected);
+}
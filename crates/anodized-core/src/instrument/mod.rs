@@ -1,28 +1,499 @@
-use proc_macro2::TokenStream;
+use std::borrow::Cow;
+
+use proc_macro2::{Span, TokenStream};
 use quote::{ToTokens, quote};
-use syn::{Attribute, Meta};
+use syn::{Attribute, Ident, Item, LitStr, Meta, TraitItemFn, Type};
+
+use crate::{Capture, Mode, Sample, Severity, Spec};
 
 pub mod fns;
 pub mod traits;
 
-pub struct Backend {
-    pub build_check: fn(Option<&Meta>, &TokenStream, &str, &TokenStream) -> TokenStream,
+/// Builds the runtime code of instrumented functions.
+///
+/// Implement this trait to make a backend of your own, e.g. one that reports violations
+/// as metrics, and use it from an attribute macro of your own through [`expand`].
+pub trait Backend {
+    /// Builds the runtime check for a single condition.
+    ///
+    /// The check should honor the condition's `cfg` and `sample`, e.g. with [`guard_check`].
+    fn build_check(&self, check: &Check) -> TokenStream;
+
+    /// Builds statements that run on entry to the instrumented function, before any check.
+    fn build_entry(&self, func: &Function) -> TokenStream {
+        let _ = func;
+        TokenStream::new()
+    }
+
+    /// Wraps the expression that runs the function body, e.g. to run it in a context.
+    ///
+    /// The expression evaluates to the return value, or for an `async` function, to a future
+    /// of it, which the wrapped expression must be too.
+    fn build_body(&self, func: &Function, body: TokenStream) -> TokenStream {
+        let _ = func;
+        body
+    }
+
+    /// Builds statements that run on exit from the instrumented function, after all checks.
+    ///
+    /// The return value is bound to [`Function::output`] at this point.
+    fn build_exit(&self, func: &Function) -> TokenStream {
+        let _ = func;
+        TokenStream::new()
+    }
+
+    /// Builds the expression that evaluates a captured value, before the function body runs.
+    fn build_capture(&self, func: &Function, capture: &Capture) -> TokenStream {
+        let _ = func;
+        let expr = &capture.expr;
+        // Evaluate expression in a closure to prevent early return.
+        quote! { (|| #expr)() }
+    }
+
+    /// The backend for a spec that selects its own mode, see [`Spec::mode`].
+    ///
+    /// Defaults to the built-in backend for the mode, with the default settings.
+    fn for_mode(&self, mode: Mode) -> Box<dyn Backend> {
+        Box::new(BuiltinBackend::new(mode, Settings::DEFAULT))
+    }
 }
 
-impl Backend {
-    pub const CHECK_AND_PANIC: Backend = Backend {
-        build_check: build_assert,
-    };
+/// Builds the check for a condition with the given settings.
+type BuildCheck = fn(&Check, &Settings) -> TokenStream;
 
-    pub const CHECK_AND_PRINT: Backend = Backend {
-        build_check: build_eprint,
-    };
+/// The backends of the runtime behaviors that come with Anodized.
+pub struct BuiltinBackend {
+    /// Builds the runtime check for a single condition.
+    check_fn: BuildCheck,
+    /// Builds the runtime check for a `#[warn]` condition, which reports a violation and
+    /// continues.
+    report_fn: BuildCheck,
+    /// Builds statements that run on entry to the instrumented function, before any check.
+    entry_fn: fn(&Function) -> TokenStream,
+    /// Wraps the expression that runs the function body.
+    body_fn: fn(&Function, TokenStream) -> TokenStream,
+    /// The settings used to build the backend for a spec that selects its own mode.
+    pub settings: Settings,
+}
+
+impl BuiltinBackend {
+    pub const CHECK_AND_PANIC: BuiltinBackend = BuiltinBackend::new(Mode::Panic, Settings::DEFAULT);
+
+    pub const CHECK_AND_PRINT: BuiltinBackend = BuiltinBackend::new(Mode::Print, Settings::DEFAULT);
+
+    pub const NO_CHECK: BuiltinBackend = BuiltinBackend::new(Mode::NoCheck, Settings::DEFAULT);
+
+    pub const CHECK_AND_LOG: BuiltinBackend = BuiltinBackend::new(Mode::Log, Settings::DEFAULT);
+
+    pub const CHECK_AND_TRACE: BuiltinBackend = BuiltinBackend::new(Mode::Trace, Settings::DEFAULT);
+
+    pub const CHECK_AND_TRAP: BuiltinBackend = BuiltinBackend::new(Mode::Trap, Settings::DEFAULT);
+
+    /// Makes the backend for a runtime behavior.
+    ///
+    /// Only the settings that apply to `mode` take effect, but all of them are kept for
+    /// specs that select another mode.
+    pub const fn new(mode: Mode, settings: Settings) -> BuiltinBackend {
+        match mode {
+            Mode::Panic => BuiltinBackend {
+                check_fn: build_assert,
+                report_fn: build_eprint,
+                entry_fn: build_nothing,
+                body_fn: build_plain_body,
+                settings,
+            },
+            Mode::Print => BuiltinBackend {
+                check_fn: build_eprint,
+                report_fn: build_eprint,
+                entry_fn: build_nothing,
+                body_fn: build_plain_body,
+                settings,
+            },
+            Mode::Log => {
+                let build_log = match settings.log_level {
+                    LogLevel::Error => build_log_error,
+                    LogLevel::Warn => build_log_warn,
+                    LogLevel::Info => build_log_info,
+                    LogLevel::Debug => build_log_debug,
+                    LogLevel::Trace => build_log_trace,
+                };
+                BuiltinBackend {
+                    check_fn: build_log,
+                    report_fn: build_log,
+                    entry_fn: build_nothing,
+                    body_fn: build_plain_body,
+                    settings,
+                }
+            }
+            Mode::Trace if settings.trace_spans => BuiltinBackend {
+                check_fn: build_trace_event_in_span,
+                report_fn: build_trace_event_in_span,
+                entry_fn: build_trace_span,
+                body_fn: build_body_in_trace_span,
+                settings,
+            },
+            Mode::Trace => BuiltinBackend {
+                check_fn: build_trace_event,
+                report_fn: build_trace_event,
+                entry_fn: build_nothing,
+                body_fn: build_plain_body,
+                settings,
+            },
+            Mode::Trap => BuiltinBackend {
+                check_fn: match settings.trap_fallback {
+                    TrapFallback::Panic => build_trap_or_panic,
+                    TrapFallback::Print => build_trap_or_print,
+                },
+                report_fn: build_eprint,
+                entry_fn: build_nothing,
+                body_fn: build_plain_body,
+                settings,
+            },
+            Mode::NoCheck => BuiltinBackend {
+                check_fn: build_inert,
+                report_fn: build_inert,
+                entry_fn: build_nothing,
+                body_fn: build_plain_body,
+                settings,
+            },
+        }
+    }
+
+    /// Reports violations through the `log` facade at the given level.
+    ///
+    /// The instrumented crate must depend on `log` itself.
+    pub const fn check_and_log(level: LogLevel) -> BuiltinBackend {
+        let settings = Settings {
+            log_level: level,
+            ..Settings::DEFAULT
+        };
+        BuiltinBackend::new(Mode::Log, settings)
+    }
+
+    /// Reports violations as `tracing` events, optionally inside a span opened per call.
+    ///
+    /// The instrumented crate must depend on `tracing` itself.
+    pub const fn check_and_trace(spans: bool) -> BuiltinBackend {
+        let settings = Settings {
+            trace_spans: spans,
+            ..Settings::DEFAULT
+        };
+        BuiltinBackend::new(Mode::Trace, settings)
+    }
+
+    /// Reports violations and breaks into an attached debugger.
+    ///
+    /// Without a debugger, a violation is handled according to `fallback`.
+    /// The instrumented crate must depend on `anodized`, which provides the runtime support.
+    pub const fn check_and_trap(fallback: TrapFallback) -> BuiltinBackend {
+        let settings = Settings {
+            trap_fallback: fallback,
+            ..Settings::DEFAULT
+        };
+        BuiltinBackend::new(Mode::Trap, settings)
+    }
+}
+
+impl Backend for BuiltinBackend {
+    fn build_check(&self, check: &Check) -> TokenStream {
+        if check.expensive && self.settings.skip_expensive {
+            return build_inert(check, &self.settings);
+        }
+        let (build_check, settings) = match check.severity {
+            None => (self.check_fn, Cow::Borrowed(&self.settings)),
+            Some(Severity::Warn) => (self.report_fn, Cow::Borrowed(&self.settings)),
+            // `#[deny]` conditions can't be turned off at runtime.
+            Some(Severity::Deny) => (
+                build_assert as BuildCheck,
+                Cow::Owned(Settings {
+                    runtime_control: false,
+                    ..self.settings.clone()
+                }),
+            ),
+        };
+        if settings.stats {
+            let expr = build_stats_expr(check, settings.stats_timing);
+            let check = Check {
+                expr: &expr,
+                ..*check
+            };
+            build_check(&check, &settings)
+        } else {
+            build_check(check, &settings)
+        }
+    }
+
+    fn build_entry(&self, func: &Function) -> TokenStream {
+        (self.entry_fn)(func)
+    }
+
+    fn build_body(&self, func: &Function, body: TokenStream) -> TokenStream {
+        (self.body_fn)(func, body)
+    }
+
+    fn for_mode(&self, mode: Mode) -> Box<dyn Backend> {
+        Box::new(BuiltinBackend::new(mode, self.settings.clone()))
+    }
+}
+
+/// Settings of specific runtime behaviors.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Settings {
+    /// The level of the log records emitted in [`Mode::Log`].
+    pub log_level: LogLevel,
+    /// What [`Mode::Trap`] does without a debugger.
+    pub trap_fallback: TrapFallback,
+    /// Whether [`Mode::Trace`] opens a span per call of an instrumented function.
+    pub trace_spans: bool,
+    /// Whether checks can be turned off while the program runs, through `anodized::runtime`.
+    ///
+    /// The instrumented crate must depend on `anodized`, which provides the runtime support.
+    pub runtime_control: bool,
+    /// Whether to count the evaluations and failures of each condition, in `anodized::stats`.
+    ///
+    /// The instrumented crate must depend on `anodized`, which provides the runtime support.
+    pub stats: bool,
+    /// Whether to also measure the time spent evaluating each condition, if `stats` is set.
+    pub stats_timing: bool,
+    /// Whether to drop the runtime checks of conditions marked `#[expensive]`.
+    pub skip_expensive: bool,
+    /// The path of a function that reports violations instead of `eprintln!`, e.g. in `no_std`
+    /// crates.
+    ///
+    /// It's called with the report as `core::fmt::Arguments`, from the instrumented function.
+    pub print_fn: Option<syn::Path>,
+}
 
-    pub const NO_CHECK: Backend = Backend {
-        build_check: build_inert,
+impl Settings {
+    pub const DEFAULT: Settings = Settings {
+        log_level: LogLevel::Error,
+        trap_fallback: TrapFallback::Panic,
+        trace_spans: false,
+        runtime_control: false,
+        stats: false,
+        stats_timing: false,
+        skip_expensive: false,
+        print_fn: None,
     };
 }
 
+/// What [`BuiltinBackend::check_and_trap`] does on a violation when no debugger is attached.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrapFallback {
+    /// Panic, like [`BuiltinBackend::CHECK_AND_PANIC`].
+    Panic,
+    /// Print the violation and continue, like [`BuiltinBackend::CHECK_AND_PRINT`].
+    Print,
+}
+
+impl TrapFallback {
+    /// Parses a fallback name, i.e. `panic` or `print`, ignoring ASCII case.
+    pub const fn from_name(name: &str) -> Option<TrapFallback> {
+        if name.eq_ignore_ascii_case("panic") {
+            Some(TrapFallback::Panic)
+        } else if name.eq_ignore_ascii_case("print") {
+            Some(TrapFallback::Print)
+        } else {
+            None
+        }
+    }
+}
+
+/// The level of the log records emitted by [`BuiltinBackend::check_and_log`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    /// Parses a level name as spelled by the `log` crate, ignoring ASCII case.
+    pub const fn from_name(name: &str) -> Option<LogLevel> {
+        if name.eq_ignore_ascii_case("error") {
+            Some(LogLevel::Error)
+        } else if name.eq_ignore_ascii_case("warn") {
+            Some(LogLevel::Warn)
+        } else if name.eq_ignore_ascii_case("info") {
+            Some(LogLevel::Info)
+        } else if name.eq_ignore_ascii_case("debug") {
+            Some(LogLevel::Debug)
+        } else if name.eq_ignore_ascii_case("trace") {
+            Some(LogLevel::Trace)
+        } else {
+            None
+        }
+    }
+}
+
+/// The part of a spec that a runtime check comes from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CheckKind {
+    /// A `requires` condition, checked on entry.
+    Precondition,
+    /// A `maintains` condition, checked on entry.
+    PreInvariant,
+    /// A `maintains` condition, checked on exit.
+    PostInvariant,
+    /// An `ensures` condition, checked on exit.
+    Postcondition,
+}
+
+impl CheckKind {
+    /// The name used for this kind in violation reports, e.g. `Precondition`.
+    pub fn title(self) -> &'static str {
+        match self {
+            CheckKind::Precondition => "Precondition",
+            CheckKind::PreInvariant => "Pre-invariant",
+            CheckKind::PostInvariant => "Post-invariant",
+            CheckKind::Postcondition => "Postcondition",
+        }
+    }
+
+    /// The name used for this kind in structured data, e.g. `precondition`.
+    pub fn name(self) -> &'static str {
+        match self {
+            CheckKind::Precondition => "precondition",
+            CheckKind::PreInvariant => "pre-invariant",
+            CheckKind::PostInvariant => "post-invariant",
+            CheckKind::Postcondition => "postcondition",
+        }
+    }
+}
+
+/// Everything a [`Backend`] needs to know to build the runtime check for a condition.
+#[derive(Clone, Copy)]
+pub struct Check<'a> {
+    /// The part of the spec that the condition comes from.
+    pub kind: CheckKind,
+    /// The name of the instrumented function.
+    pub fn_name: &'a Ident,
+    /// Build configuration filter, see [`crate::PreCondition::cfg`].
+    pub cfg: Option<&'a Meta>,
+    /// The `bool`-valued expression that evaluates the condition.
+    pub expr: &'a TokenStream,
+    /// The condition as it appears in the spec, used in violation reports.
+    pub repr: &'a TokenStream,
+    /// The condition's label, if it has one.
+    pub label: Option<&'a LitStr>,
+    /// Whether the condition is always checked, or only reported, see [`crate::Severity`].
+    pub severity: Option<Severity>,
+    /// The share of calls that run the check, if not all of them.
+    pub sample: Option<Sample>,
+    /// Whether the condition is costly to check, see [`crate::PreCondition::expensive`].
+    pub expensive: bool,
+    /// Whether a violation is the caller's fault, and the function has `#[track_caller]`, so
+    /// that `core::panic::Location::caller()` is the location of the call.
+    pub blames_caller: bool,
+}
+
+impl Check<'_> {
+    /// A format string describing a violation, with a single `{}` for the condition.
+    pub fn message(&self) -> String {
+        match self.label {
+            Some(label) => format!(
+                "{} failed: {}: {{}}",
+                self.kind.title(),
+                label.value().replace('{', "{{").replace('}', "}}")
+            ),
+            None => format!("{} failed: {{}}", self.kind.title()),
+        }
+    }
+
+    /// The arguments of `format_args!` that report a violation, which name the location of
+    /// the call if the check blames the caller. A panic reports that location by itself.
+    pub fn report_args(&self) -> TokenStream {
+        let repr_str = self.repr.to_string();
+        if self.blames_caller {
+            let message = format!("{}, called at {{}}", self.message());
+            quote! { #message, #repr_str, ::core::panic::Location::caller() }
+        } else {
+            let message = self.message();
+            quote! { #message, #repr_str }
+        }
+    }
+}
+
+/// Everything a [`Backend`] knows about an instrumented function.
+#[derive(Clone, Copy)]
+pub struct Function<'a> {
+    /// The name of the function.
+    pub name: &'a Ident,
+    /// The spec of the function.
+    pub spec: &'a Spec,
+    /// Whether the function is `async`.
+    pub is_async: bool,
+    /// The return type of the function.
+    pub return_type: &'a Type,
+    /// The variable that holds the return value, after the function body has run.
+    pub output: &'a Ident,
+}
+
+/// Expand `#[spec]` on an item using the given backend.
+///
+/// A crate with a backend of its own can provide its own attribute macro by forwarding to
+/// this function, e.g. `expand(&MyBackend, args.into(), item.into()).into()`.
+pub fn expand(backend: &dyn Backend, args: TokenStream, item: TokenStream) -> TokenStream {
+    match try_expand(backend, args, item) {
+        Ok(item) => item,
+        Err(e) => e.to_compile_error(),
+    }
+}
+
+fn try_expand(
+    backend: &dyn Backend,
+    args: TokenStream,
+    item: TokenStream,
+) -> syn::Result<TokenStream> {
+    // Parse the item to which the attribute is attached.
+    let item: Item = syn::parse2(item)?;
+
+    match item {
+        Item::Fn(func) => {
+            let spec = syn::parse2(args)?;
+            fns::instrument_fn(backend, spec, func).map(|tokens| tokens.into_token_stream())
+        }
+        Item::Trait(the_trait) => {
+            let spec = syn::parse2(args)?;
+            traits::instrument_trait(backend, spec, the_trait)
+                .map(|tokens| tokens.into_token_stream())
+        }
+        Item::Impl(the_impl) if the_impl.trait_.is_some() => {
+            let spec = syn::parse2(args)?;
+            traits::instrument_trait_impl(spec, the_impl).map(|tokens| tokens.into_token_stream())
+        }
+        Item::Impl(ref the_impl) if the_impl.trait_.is_none() => {
+            Err(make_item_error(&item, "inherent impl"))
+        }
+        Item::Const(_) => Err(make_item_error(&item, "const")),
+        Item::Enum(_) => Err(make_item_error(&item, "enum")),
+        Item::ExternCrate(_) => Err(make_item_error(&item, "extern crate")),
+        Item::ForeignMod(_) => Err(make_item_error(&item, "extern block")),
+        Item::Macro(_) => Err(make_item_error(&item, "macro")),
+        Item::Mod(_) => Err(make_item_error(&item, "mod")),
+        Item::Static(_) => Err(make_item_error(&item, "static")),
+        Item::Struct(_) => Err(make_item_error(&item, "struct")),
+        Item::TraitAlias(_) => Err(make_item_error(&item, "trait alias")),
+        Item::Type(_) => Err(make_item_error(&item, "type")),
+        Item::Union(_) => Err(make_item_error(&item, "union")),
+        Item::Use(_) => Err(make_item_error(&item, "use")),
+        Item::Verbatim(ref tokens) => {
+            // Try to parse as a trait fn
+            if let Ok(trait_fn) = syn::parse2::<TraitItemFn>(tokens.clone()) {
+                Err(syn::Error::new_spanned(
+                    &trait_fn,
+                    r#"The enclosing trait must have a `#[spec]` annotation."#,
+                ))
+            } else {
+                Err(make_item_error(&item, "<unexpected>"))
+            }
+        }
+        _ => Err(make_item_error(&item, "<unknown>")),
+    }
+}
+
 /// Make an error message to say that some item is unsupported.
 pub fn make_item_error<T: ToTokens>(tokens: &T, item_descr: &str) -> syn::Error {
     let msg = format!(
@@ -58,39 +529,161 @@ fn find_spec_attr(attrs: Vec<Attribute>) -> syn::Result<(Option<Attribute>, Vec<
     Ok((spec_attr, other_attrs))
 }
 
-fn build_assert(
-    cfg: Option<&Meta>,
-    expr: &TokenStream,
-    message: &str,
-    repr: &TokenStream,
-) -> TokenStream {
+fn build_assert(check: &Check, settings: &Settings) -> TokenStream {
+    let Check { expr, repr, .. } = check;
+    let message = check.message();
     let repr_str = repr.to_string();
-    let check = quote! { assert!(#expr, #message, #repr_str); };
-    guard_check(cfg, check)
+    let tokens = quote! { assert!(#expr, #message, #repr_str); };
+    guard(check, settings.runtime_control, tokens)
+}
+
+fn build_eprint(check: &Check, settings: &Settings) -> TokenStream {
+    let expr = check.expr;
+    let report = check.report_args();
+    let tokens = match &settings.print_fn {
+        Some(print_fn) => {
+            quote! {
+                if !(#expr) {
+                    #print_fn(format_args!(#report));
+                }
+            }
+        }
+        None => quote! {
+            if !(#expr) {
+                eprintln!(#report);
+            }
+        },
+    };
+    guard(check, settings.runtime_control, tokens)
+}
+
+fn build_log(level: LogLevel, check: &Check, settings: &Settings) -> TokenStream {
+    let expr = check.expr;
+    let report = check.report_args();
+    let level = match level {
+        LogLevel::Error => quote! { Error },
+        LogLevel::Warn => quote! { Warn },
+        LogLevel::Info => quote! { Info },
+        LogLevel::Debug => quote! { Debug },
+        LogLevel::Trace => quote! { Trace },
+    };
+    // The record's target and module path both point at the instrumented fn's module.
+    let tokens = quote! {
+        if !(#expr) {
+            ::log::log!(::log::Level::#level, #report);
+        }
+    };
+    guard(check, settings.runtime_control, tokens)
+}
+
+fn build_log_error(check: &Check, settings: &Settings) -> TokenStream {
+    build_log(LogLevel::Error, check, settings)
+}
+
+fn build_log_warn(check: &Check, settings: &Settings) -> TokenStream {
+    build_log(LogLevel::Warn, check, settings)
+}
+
+fn build_log_info(check: &Check, settings: &Settings) -> TokenStream {
+    build_log(LogLevel::Info, check, settings)
+}
+
+fn build_log_debug(check: &Check, settings: &Settings) -> TokenStream {
+    build_log(LogLevel::Debug, check, settings)
+}
+
+fn build_log_trace(check: &Check, settings: &Settings) -> TokenStream {
+    build_log(LogLevel::Trace, check, settings)
+}
+
+/// The span opened per call by [`BuiltinBackend::check_and_trace`].
+fn trace_span_ident() -> Ident {
+    Ident::new("__anodized_span", Span::mixed_site())
+}
+
+/// Opens the span of a call, and enters it for the rest of a sync function. An `async`
+/// function enters it whenever its body is polled instead, see [`build_body_in_trace_span`].
+fn build_trace_span(func: &Function) -> TokenStream {
+    let span_ident = trace_span_ident();
+    let fn_name = func.name.to_string();
+    let span = quote! {
+        let #span_ident = ::tracing::info_span!("spec", function = #fn_name);
+    };
+    if func.is_async {
+        span
+    } else {
+        let guard_ident = Ident::new("__anodized_span_guard", Span::mixed_site());
+        quote! {
+            #span
+            let #guard_ident = #span_ident.enter();
+        }
+    }
+}
+
+/// Runs the body of an `async` function in the span of its call.
+fn build_body_in_trace_span(func: &Function, body: TokenStream) -> TokenStream {
+    if func.is_async {
+        let span_ident = trace_span_ident();
+        quote! { ::tracing::Instrument::instrument(#body, #span_ident.clone()) }
+    } else {
+        body
+    }
+}
+
+fn build_trace_event(check: &Check, settings: &Settings) -> TokenStream {
+    build_trace(check, settings, None)
+}
+
+fn build_trace_event_in_span(check: &Check, settings: &Settings) -> TokenStream {
+    build_trace(check, settings, Some(trace_span_ident()))
 }
 
-fn build_eprint(
-    cfg: Option<&Meta>,
-    expr: &TokenStream,
-    message: &str,
-    repr: &TokenStream,
-) -> TokenStream {
+fn build_trace(check: &Check, settings: &Settings, span_ident: Option<Ident>) -> TokenStream {
+    let Check { expr, repr, .. } = check;
+    let report = check.report_args();
     let repr_str = repr.to_string();
-    let check = quote! {
+    let fn_name = check.fn_name.to_string();
+    let kind = check.kind.name();
+    let parent = span_ident.map(|span_ident| quote! { parent: &#span_ident, });
+    let label = check.label.map(|label| quote! { label = #label, });
+    let tokens = quote! {
         if !(#expr) {
-            eprintln!(#message, #repr_str);
+            ::tracing::error!(
+                #parent
+                function = #fn_name,
+                kind = #kind,
+                condition = #repr_str,
+                #label
+                #report
+            );
         }
     };
-    guard_check(cfg, check)
+    guard(check, settings.runtime_control, tokens)
 }
 
-fn build_inert(
+fn build_trap(check: &Check, settings: &Settings, trap_fn: TokenStream) -> TokenStream {
+    let expr = check.expr;
+    let report = check.report_args();
+    let tokens = quote! {
+        if !(#expr) {
+            ::anodized::__private::#trap_fn(format_args!(#report));
+        }
+    };
+    guard(check, settings.runtime_control, tokens)
+}
+
+fn build_trap_or_panic(check: &Check, settings: &Settings) -> TokenStream {
+    build_trap(check, settings, quote! { trap_or_panic })
+}
+
+fn build_trap_or_print(check: &Check, settings: &Settings) -> TokenStream {
+    build_trap(check, settings, quote! { trap_or_print })
+}
+
+fn build_inert(check: &Check, _settings: &Settings) -> TokenStream {
     // The check will not be present at runtime regardless of the `#[cfg]` setting.
-    _cfg: Option<&Meta>,
-    expr: &TokenStream,
-    message: &str,
-    repr: &TokenStream,
-) -> TokenStream {
+    let Check { expr, repr, .. } = check;
+    let message = check.message();
     let repr_str = repr.to_string();
     quote! {
         if false {
@@ -99,10 +692,77 @@ fn build_inert(
     }
 }
 
-fn guard_check(cfg: Option<&Meta>, check: TokenStream) -> TokenStream {
-    if let Some(cfg) = cfg {
-        quote! { if cfg!(#cfg) { #check } }
+fn build_nothing(_func: &Function) -> TokenStream {
+    TokenStream::new()
+}
+
+fn build_plain_body(_func: &Function, body: TokenStream) -> TokenStream {
+    body
+}
+
+/// Wrap the expression of a check to record its evaluations in `anodized::stats`.
+fn build_stats_expr(check: &Check, timing: bool) -> TokenStream {
+    let Check { expr, repr, .. } = check;
+    let function = check.fn_name.to_string();
+    let kind = check.kind.name();
+    let condition = repr.to_string();
+    let label = match check.label {
+        Some(label) => quote! { ::core::option::Option::Some(#label) },
+        None => quote! { ::core::option::Option::None },
+    };
+    let record = if timing {
+        quote! { record_timed }
+    } else {
+        quote! { record }
+    };
+    quote! {
+        {
+            static COUNTER: ::anodized::__private::Counter =
+                ::anodized::__private::Counter::new(::anodized::stats::Condition {
+                    module: ::core::module_path!(),
+                    function: #function,
+                    kind: #kind,
+                    condition: #condition,
+                    label: #label,
+                    file: ::core::file!(),
+                    line: ::core::line!(),
+                });
+            COUNTER.#record(|| #expr)
+        }
+    }
+}
+
+/// Make the runtime check conditional on the condition's `cfg` and `sample` settings.
+///
+/// Sampling relies on the runtime support of the `anodized` crate, which the instrumented
+/// crate must then depend on.
+pub fn guard_check(check: &Check, tokens: TokenStream) -> TokenStream {
+    guard(check, false, tokens)
+}
+
+/// Make the runtime check conditional on the condition's `cfg` and `sample` settings,
+/// and, if `runtime_control` is set, on the runtime control switches.
+fn guard(check: &Check, runtime_control: bool, tokens: TokenStream) -> TokenStream {
+    let tokens = match check.sample {
+        Some(Sample { count, period }) => quote! {
+            if ::anodized::__private::sample!(#count, #period) {
+                #tokens
+            }
+        },
+        None => tokens,
+    };
+    let tokens = if runtime_control {
+        quote! {
+            if ::anodized::__private::checks_enabled() {
+                #tokens
+            }
+        }
+    } else {
+        tokens
+    };
+    if let Some(cfg) = check.cfg {
+        quote! { if cfg!(#cfg) { #tokens } }
     } else {
-        check
+        tokens
     }
 }
@@ -0,0 +1,229 @@
+//! Proof harnesses and function contracts for the [Kani](https://github.com/model-checking/kani)
+//! model checker.
+//!
+//! A harness turns a spec into a bounded proof: it creates symbolic values for the
+//! arguments, assumes the preconditions and invariants, calls the function, and asserts
+//! the invariants and postconditions. A contract states the spec in Kani's own attributes,
+//! so that Kani can verify callers against it instead of the function's body.
+//!
+//! Conditions are translated whatever their `#[warn]`, `#[deny]`, `#[sample]`, or
+//! `#[expensive]` attributes; a `#[cfg]` still applies.
+
+#[cfg(test)]
+mod tests;
+
+use proc_macro2::{Span, TokenStream};
+use quote::{ToTokens, format_ident, quote};
+use syn::{Attribute, FnArg, Ident, ItemFn, Meta, Pat, Signature, Type, parse_quote};
+
+use crate::{
+    Spec,
+    instrument::{CheckKind, fns::annotate_postcondition_closure_argument},
+    translate::{PostRewrite, guard, mentions_self, message, mut_ref_args, return_type},
+};
+
+/// Builds a Kani proof harness for a function with the given spec.
+///
+/// The harness is named after the function, e.g. `__anodized_kani_divide` for `divide`,
+/// and is only compiled under `cfg(kani)`. Each argument is created with `kani::any()`,
+/// so the argument types must implement `kani::Arbitrary`; an argument `&T` or `&mut T`
+/// borrows a symbolic `T`.
+///
+/// Returns `None` if the function can't be called from a harness generically: if it has a
+/// receiver, mentions `Self`, has generic parameters, is `async` or variadic, or has an
+/// argument that is a pattern other than a name, an `impl Trait`, or a reference with a
+/// lifetime or to an unsized type.
+///
+/// The harness calls the function by its name, so it belongs next to a function in a module.
+/// It can't be an item of an `impl` block, where the function would need a path like
+/// `Self::make`.
+pub fn build_harness(func: &ItemFn, spec: &Spec) -> Option<ItemFn> {
+    let sig = &func.sig;
+    if sig.asyncness.is_some()
+        || sig.variadic.is_some()
+        || !sig.generics.params.is_empty()
+        || mentions_self(sig.to_token_stream())
+    {
+        return None;
+    }
+
+    let mut arguments = Vec::with_capacity(sig.inputs.len());
+    let mut symbolic_values = Vec::with_capacity(sig.inputs.len());
+    for input in &sig.inputs {
+        let FnArg::Typed(input) = input else {
+            return None;
+        };
+        let Pat::Ident(pat) = input.pat.as_ref() else {
+            return None;
+        };
+        if pat.by_ref.is_some() || pat.subpat.is_some() || !is_arbitrary_type(&input.ty) {
+            return None;
+        }
+        let (name, ty) = (&pat.ident, &input.ty);
+        let value = match ty.as_ref() {
+            Type::Reference(reference) if reference.mutability.is_some() => {
+                quote! { &mut ::kani::any() }
+            }
+            Type::Reference(_) => quote! { &::kani::any() },
+            _ => quote! { ::kani::any() },
+        };
+        let mutability = &pat.mutability;
+        symbolic_values.push(quote! { let #mutability #name: #ty = #value; });
+        arguments.push(name);
+    }
+
+    let return_type = return_type(sig);
+    let output = Ident::new("__anodized_output", Span::mixed_site());
+
+    let assumptions = spec
+        .requires
+        .iter()
+        .chain(&spec.maintains)
+        .map(|condition| {
+            let body = &condition.closure.body;
+            guard(condition.cfg.as_ref(), quote! { ::kani::assume(#body); })
+        });
+
+    let fn_name = &sig.ident;
+    let call = if sig.unsafety.is_some() {
+        quote! { unsafe { #fn_name(#(#arguments),*) } }
+    } else {
+        quote! { #fn_name(#(#arguments),*) }
+    };
+    let aliases = spec.captures.iter().map(|capture| &capture.pat);
+    let capture_exprs = spec.captures.iter().map(|capture| &capture.expr);
+    let types = spec.captures.iter().map(|_| quote! { _ });
+
+    let assertions = spec
+        .maintains
+        .iter()
+        .map(|condition| {
+            let body = &condition.closure.body;
+            let message = message(
+                CheckKind::PostInvariant,
+                condition.label.as_ref(),
+                body.to_token_stream(),
+            );
+            guard(
+                condition.cfg.as_ref(),
+                quote! { ::kani::assert(#body, #message); },
+            )
+        })
+        .chain(spec.ensures.iter().map(|postcondition| {
+            let closure = annotate_postcondition_closure_argument(
+                postcondition.closure.clone(),
+                return_type.clone(),
+            );
+            let message = message(
+                CheckKind::Postcondition,
+                postcondition.label.as_ref(),
+                postcondition.closure.to_token_stream(),
+            );
+            guard(
+                postcondition.cfg.as_ref(),
+                quote! { ::kani::assert((#closure)(&#output), #message); },
+            )
+        }));
+
+    let harness_name = format_ident!("__anodized_kani_{}", fn_name);
+    Some(parse_quote! {
+        #[cfg(kani)]
+        #[kani::proof]
+        fn #harness_name() {
+            #(#symbolic_values)*
+            #(#assumptions)*
+            let (#(#aliases,)* #output): (#(#types,)* #return_type) = (#(#capture_exprs,)* #call);
+            #(#assertions)*
+        }
+    })
+}
+
+/// Builds the Kani contract attributes of a function with the given spec.
+///
+/// Each attribute is only active under `cfg(kani)`, and under the `#[cfg]` of its condition:
+/// - a `requires` condition becomes `kani::requires`,
+/// - a `maintains` condition becomes both `kani::requires` and `kani::ensures`,
+/// - an `ensures` condition becomes `kani::ensures`, where each capture is replaced by
+///   Kani's `old(...)` of the captured expression,
+/// - each `&mut` argument, including `&mut self`, is listed in `kani::modifies`.
+///
+/// Returns no attributes for an `async` function, which Kani contracts don't support.
+pub fn build_contract(sig: &Signature, spec: &Spec) -> Vec<Attribute> {
+    if sig.asyncness.is_some() {
+        return Vec::new();
+    }
+
+    let return_type = return_type(sig);
+    let rewrite = PostRewrite {
+        result: parse_quote! { result },
+        captures: &spec.captures,
+        capture: |expr| parse_quote! { old(#expr) },
+        // Kani reads a mutable reference in a postcondition as its value at exit.
+        mut_refs: &[],
+        final_value: |ident| parse_quote! { #ident },
+    };
+
+    let requires = spec
+        .requires
+        .iter()
+        .chain(&spec.maintains)
+        .map(|condition| {
+            let body = &condition.closure.body;
+            contract_attr(condition.cfg.as_ref(), quote! { kani::requires(#body) })
+        });
+
+    let invariants = spec.maintains.iter().map(|condition| {
+        let body = &condition.closure.body;
+        contract_attr(
+            condition.cfg.as_ref(),
+            quote! { kani::ensures(|_: &#return_type| #body) },
+        )
+    });
+
+    let ensures = spec.ensures.iter().map(|postcondition| {
+        let mut closure = annotate_postcondition_closure_argument(
+            postcondition.closure.clone(),
+            return_type.clone(),
+        );
+        // The closure keeps its argument, so only the captures are rewritten.
+        *closure.body = rewrite.condition(&closure.body);
+        contract_attr(
+            postcondition.cfg.as_ref(),
+            quote! { kani::ensures(#closure) },
+        )
+    });
+
+    let modifies = mut_ref_args(sig)
+        .into_iter()
+        .map(|name| contract_attr(None, quote! { kani::modifies(#name) }));
+
+    requires
+        .chain(invariants)
+        .chain(ensures)
+        .chain(modifies)
+        .collect()
+}
+
+/// Makes an attribute that is only active under `cfg(kani)` and the condition's `#[cfg]`.
+fn contract_attr(cfg: Option<&Meta>, contract: TokenStream) -> Attribute {
+    match cfg {
+        Some(cfg) => parse_quote! { #[cfg_attr(all(kani, #cfg), #contract)] },
+        None => parse_quote! { #[cfg_attr(kani, #contract)] },
+    }
+}
+
+/// Whether `kani::any()` can create a value of the type, as far as the syntax tells.
+fn is_arbitrary_type(ty: &Type) -> bool {
+    match ty {
+        Type::ImplTrait(_) | Type::TraitObject(_) | Type::Infer(_) | Type::Macro(_) => false,
+        Type::Reference(reference) if reference.lifetime.is_some() => false,
+        Type::Reference(reference) => match reference.elem.as_ref() {
+            Type::Slice(_) | Type::TraitObject(_) => false,
+            Type::Path(path) if path.qself.is_none() && path.path.is_ident("str") => false,
+            elem => is_arbitrary_type(elem),
+        },
+        Type::Paren(paren) => is_arbitrary_type(&paren.elem),
+        Type::Group(group) => is_arbitrary_type(&group.elem),
+        _ => true,
+    }
+}
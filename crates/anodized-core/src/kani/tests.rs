@@ -0,0 +1,184 @@
+use super::*;
+
+use crate::test_util::assert_tokens_eq;
+
+#[test]
+fn harness() {
+    let spec: Spec = parse_quote! {
+        requires: divisor != 0,
+        captures: dividend as original,
+        ensures: *output <= original,
+    };
+    let func: ItemFn = parse_quote! {
+        fn divide(dividend: u32, divisor: u32) -> u32 {
+            dividend / divisor
+        }
+    };
+
+    let expected: ItemFn = parse_quote! {
+        #[cfg(kani)]
+        #[kani::proof]
+        fn __anodized_kani_divide() {
+            let dividend: u32 = ::kani::any();
+            let divisor: u32 = ::kani::any();
+            ::kani::assume(divisor != 0);
+            let (original, __anodized_output): (_, u32) = (dividend, divide(dividend, divisor));
+            ::kani::assert(
+                (|output: &u32| *output <= original)(&__anodized_output),
+                "Postcondition failed: | output | * output <= original"
+            );
+        }
+    };
+
+    assert_tokens_eq(&build_harness(&func, &spec).unwrap(), &expected);
+}
+
+#[test]
+fn harness_with_invariants_and_attributes() {
+    let spec: Spec = parse_quote! {
+        #[cfg(debug_assertions)]
+        requires: limit > 0,
+        #[label = "in range"]
+        #[sample(1 in 100)]
+        maintains: *value < limit,
+        #[warn]
+        ensures: |()| true,
+    };
+    let func: ItemFn = parse_quote! {
+        unsafe fn step(mut limit: u8, value: &mut u8) {}
+    };
+
+    let expected: ItemFn = parse_quote! {
+        #[cfg(kani)]
+        #[kani::proof]
+        fn __anodized_kani_step() {
+            let mut limit: u8 = ::kani::any();
+            let value: &mut u8 = &mut ::kani::any();
+            if cfg!(debug_assertions) {
+                ::kani::assume(limit > 0);
+            }
+            ::kani::assume(*value < limit);
+            let (__anodized_output): (()) = (unsafe { step(limit, value) });
+            ::kani::assert(*value < limit, "Post-invariant failed: in range: * value < limit");
+            ::kani::assert((|(): &()| true)(&__anodized_output), "Postcondition failed: | () | true");
+        }
+    };
+
+    assert_tokens_eq(&build_harness(&func, &spec).unwrap(), &expected);
+}
+
+#[test]
+fn unsupported_signatures() {
+    let spec: Spec = parse_quote! { requires: true };
+    let funcs: [ItemFn; 9] = [
+        parse_quote! { fn f(&self) {} },
+        parse_quote! { fn f() -> Self {} },
+        parse_quote! { fn f<T>(x: T) {} },
+        parse_quote! { async fn f() {} },
+        parse_quote! { fn f((a, b): (u8, u8)) {} },
+        parse_quote! { fn f(x: impl Copy) {} },
+        parse_quote! { fn f(name: &str) {} },
+        parse_quote! { fn f(items: &[u8]) {} },
+        parse_quote! { fn f(x: &'static u8) {} },
+    ];
+
+    for func in funcs {
+        assert!(
+            build_harness(&func, &spec).is_none(),
+            "expected no harness for `{}`",
+            func.sig.to_token_stream()
+        );
+    }
+}
+
+#[test]
+fn contract() {
+    let spec: Spec = parse_quote! {
+        requires: *balance >= amount,
+        #[cfg(debug_assertions)]
+        maintains: *balance <= MAX,
+        captures: [*balance as initial, amount],
+        #[label = "debited"]
+        ensures: |receipt| *balance == initial - old_amount && receipt.amount == old_amount,
+    };
+    let func: ItemFn = parse_quote! {
+        fn withdraw(balance: &mut u64, amount: u64, log: &Log) -> Receipt {}
+    };
+
+    let expected: Vec<Attribute> = vec![
+        parse_quote! { #[cfg_attr(kani, kani::requires(*balance >= amount))] },
+        parse_quote! { #[cfg_attr(all(kani, debug_assertions), kani::requires(*balance <= MAX))] },
+        parse_quote! {
+            #[cfg_attr(all(kani, debug_assertions), kani::ensures(|_: &Receipt| *balance <= MAX))]
+        },
+        parse_quote! {
+            #[cfg_attr(kani, kani::ensures(
+                |receipt: &Receipt| *balance == (old(*balance)) - (old(amount))
+                    && receipt.amount == (old(amount))
+            ))]
+        },
+        parse_quote! { #[cfg_attr(kani, kani::modifies(balance))] },
+    ];
+
+    let observed = build_contract(&func.sig, &spec);
+    assert_eq!(observed.len(), expected.len());
+    for (observed, expected) in observed.iter().zip(&expected) {
+        assert_tokens_eq(observed, expected);
+    }
+}
+
+#[test]
+fn contract_of_method() {
+    let spec: Spec = parse_quote! {
+        captures: self.len as len,
+        ensures: self.len == len + 1,
+    };
+    let func: ItemFn = parse_quote! {
+        fn push(&mut self, value: u8) {}
+    };
+
+    let expected: Vec<Attribute> = vec![
+        parse_quote! {
+            #[cfg_attr(kani, kani::ensures(|output: &()| self.len == (old(self.len)) + 1))]
+        },
+        parse_quote! { #[cfg_attr(kani, kani::modifies(self))] },
+    ];
+
+    let observed = build_contract(&func.sig, &spec);
+    assert_eq!(observed.len(), expected.len());
+    for (observed, expected) in observed.iter().zip(&expected) {
+        assert_tokens_eq(observed, expected);
+    }
+}
+
+#[test]
+fn contract_with_shadowed_capture() {
+    let spec: Spec = parse_quote! {
+        captures: self.len as len,
+        ensures: self.items.iter().all(|len| len.len > 0) && {
+            let total = len;
+            self.len > total
+        } && self.len == len + 1,
+    };
+    let func: ItemFn = parse_quote! {
+        fn push(&mut self, value: Item) {}
+    };
+
+    let expected: Attribute = parse_quote! {
+        #[cfg_attr(kani, kani::ensures(|output: &()| self.items.iter().all(|len| len.len > 0) && {
+            let total = (old(self.len));
+            self.len > total
+        } && self.len == (old(self.len)) + 1))]
+    };
+
+    let observed = build_contract(&func.sig, &spec);
+    assert_tokens_eq(&observed[0], &expected);
+}
+
+#[test]
+fn no_contract_for_async_fn() {
+    let spec: Spec = parse_quote! { requires: true };
+    let func: ItemFn = parse_quote! { async fn f() {} };
+
+    assert!(build_contract(&func.sig, &spec).is_empty());
+}
@@ -1,17 +1,32 @@
 #![doc = include_str!("../README.md")]
 
 use proc_macro2::Span;
-use syn::{Expr, Meta, Pat};
+use syn::{Attribute, Expr, LitStr, Meta, Pat};
 
 pub mod annotate;
+pub mod creusot;
+pub mod export;
+pub mod flux;
+pub mod fuzz;
 pub mod instrument;
+pub mod kani;
+pub mod proptest;
+pub mod prusti;
+pub mod verus;
 
 #[cfg(test)]
 mod test_util;
+mod translate;
+
+pub use translate::mut_ref_args;
 
 /// Specifies the intended behavior of a function or method.
 #[derive(Debug)]
 pub struct Spec {
+    /// **Static analyzers can safely ignore this field.**
+    ///
+    /// Runtime behavior for this spec's checks, overriding the one selected for the crate.
+    pub mode: Option<Mode>,
     /// Preconditions: conditions that must hold when the function is called.
     pub requires: Vec<PreCondition>,
     /// Invariants: conditions that must hold both when the function is called and when it returns.
@@ -27,7 +42,8 @@ pub struct Spec {
 impl Spec {
     /// Returns `true` if the spec contract is empty (specifies nothing), otherwise returns `false`
     pub fn is_empty(&self) -> bool {
-        self.requires.is_empty()
+        self.mode.is_none()
+            && self.requires.is_empty()
             && self.maintains.is_empty()
             && self.ensures.is_empty()
             && self.captures.is_empty()
@@ -38,6 +54,13 @@ impl Spec {
     }
 }
 
+/// Whether an attribute is `#[spec]`, possibly as `#[anodized::spec]`.
+pub fn is_spec_attr(attr: &Attribute) -> bool {
+    let segments = &attr.path().segments;
+    segments.last().is_some_and(|last| last.ident == "spec")
+        && (segments.len() == 1 || (segments.len() == 2 && segments[0].ident == "anodized"))
+}
+
 /// A precondition represented by a `bool`-valued expression.
 #[derive(Debug)]
 pub struct PreCondition {
@@ -49,6 +72,20 @@ pub struct PreCondition {
     /// Build configuration filter to decide whether to add runtime checks.
     /// Passed to a `cfg!()` guard in the instrumented function.
     pub cfg: Option<Meta>,
+    /// A human-readable description of the condition, reported on violation.
+    pub label: Option<LitStr>,
+    /// **Static analyzers can safely ignore this field.**
+    ///
+    /// How a violation is handled, overriding the spec's runtime behavior.
+    pub severity: Option<Severity>,
+    /// **Static analyzers can safely ignore this field.**
+    ///
+    /// The share of calls that run the runtime check, if not all of them.
+    pub sample: Option<Sample>,
+    /// Whether the condition is costly to check, as marked by `#[expensive]`.
+    ///
+    /// Builds can drop the runtime checks of expensive conditions while keeping the others.
+    pub expensive: bool,
 }
 
 /// A postcondition represented by a closure that takes the return value as a reference.
@@ -62,6 +99,84 @@ pub struct PostCondition {
     /// Build configuration filter to decide whether to add runtime checks.
     /// Passed to a `cfg!()` guard in the instrumented function.
     pub cfg: Option<Meta>,
+    /// A human-readable description of the condition, reported on violation.
+    pub label: Option<LitStr>,
+    /// **Static analyzers can safely ignore this field.**
+    ///
+    /// How a violation is handled, overriding the spec's runtime behavior.
+    pub severity: Option<Severity>,
+    /// **Static analyzers can safely ignore this field.**
+    ///
+    /// The share of calls that run the runtime check, if not all of them.
+    pub sample: Option<Sample>,
+    /// Whether the condition is costly to check, as marked by `#[expensive]`.
+    ///
+    /// Builds can drop the runtime checks of expensive conditions while keeping the others.
+    pub expensive: bool,
+}
+
+/// The runtime behavior that a spec selects with `mode: <name>`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// `panic`, like `check-and-panic`.
+    Panic,
+    /// `print`, like `check-and-print`.
+    Print,
+    /// `log`, like `check-and-log`.
+    Log,
+    /// `trace`, like `check-and-trace`.
+    Trace,
+    /// `trap`, like `check-and-trap`.
+    Trap,
+    /// `no_check`, like `no-check`.
+    NoCheck,
+}
+
+impl Mode {
+    /// Parses a mode name as written in a spec, e.g. `print`.
+    pub fn from_name(name: &str) -> Option<Mode> {
+        match name {
+            "panic" => Some(Mode::Panic),
+            "print" => Some(Mode::Print),
+            "log" => Some(Mode::Log),
+            "trace" => Some(Mode::Trace),
+            "trap" => Some(Mode::Trap),
+            "no_check" => Some(Mode::NoCheck),
+            _ => None,
+        }
+    }
+
+    /// The name of the mode as written in a spec, e.g. `print`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Mode::Panic => "panic",
+            Mode::Print => "print",
+            Mode::Log => "log",
+            Mode::Trace => "trace",
+            Mode::Trap => "trap",
+            Mode::NoCheck => "no_check",
+        }
+    }
+}
+
+/// How a violation of a condition is handled, regardless of the runtime behavior.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    /// `#[warn]`: report the violation and continue, never panic.
+    Warn,
+    /// `#[deny]`: always panic, even if checks are otherwise disabled.
+    Deny,
+}
+
+/// Runtime checking of a condition on `count` out of every `period` calls,
+/// as given by `#[sample(count in period)]`.
+///
+/// Sampling is per condition: a spec has no sampling of its own, so each sampled condition
+/// carries its own `Sample`, which a list of conditions shares.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Sample {
+    pub count: u32,
+    pub period: u32,
 }
 
 /// Captures an expression's value at function entry.
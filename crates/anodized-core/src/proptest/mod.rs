@@ -0,0 +1,291 @@
+//! Property-based tests with [proptest](https://github.com/proptest-rs/proptest).
+//!
+//! A property-based test turns a spec into the properties one would otherwise write by
+//! hand: it generates arguments, keeps those that satisfy the preconditions and invariants,
+//! calls the function, and checks the invariants and postconditions. On failure, proptest
+//! shrinks the arguments, and the test reports the minimal failing input together with
+//! the violated condition.
+//!
+//! Conditions are checked whatever their `#[warn]`, `#[deny]`, `#[sample]`, or
+//! `#[expensive]` attributes; a `#[cfg]` still applies.
+
+#[cfg(test)]
+mod tests;
+
+use proc_macro2::{Span, TokenStream};
+use quote::{ToTokens, format_ident, quote};
+use syn::{
+    Attribute, Expr, FnArg, Ident, ItemFn, Pat, Token, Type,
+    parse::{Parse, ParseStream},
+    parse_quote,
+    punctuated::Punctuated,
+};
+
+use crate::{
+    Spec,
+    instrument::{CheckKind, fns::annotate_postcondition_closure_argument},
+    translate::{guard, mentions_self, message, owned_type, return_type},
+};
+
+/// The arguments of `#[spec_proptest]`: attributes for the test, then a strategy for some
+/// of the function's arguments, e.g. `#[ignore] divisor in 1u32..`.
+pub struct TestArgs {
+    /// Attributes added to the test, e.g. `#[ignore]`.
+    pub attrs: Vec<Attribute>,
+    /// The strategies, by argument name.
+    pub strategies: Vec<(Ident, Expr)>,
+}
+
+impl Parse for TestArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let strategies = Punctuated::<ArgStrategy, Token![,]>::parse_terminated(input)?;
+        let mut parsed: Vec<(Ident, Expr)> = Vec::new();
+        for ArgStrategy { name, strategy } in strategies {
+            if parsed.iter().any(|(other, _)| *other == name) {
+                return Err(syn::Error::new(
+                    name.span(),
+                    format!("multiple strategies for `{name}`"),
+                ));
+            }
+            parsed.push((name, strategy));
+        }
+        Ok(TestArgs {
+            attrs,
+            strategies: parsed,
+        })
+    }
+}
+
+/// A strategy for an argument, e.g. `divisor in 1u32..`.
+struct ArgStrategy {
+    name: Ident,
+    strategy: Expr,
+}
+
+impl Parse for ArgStrategy {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name = input.parse()?;
+        input.parse::<Token![in]>()?;
+        let strategy = input.parse()?;
+        Ok(ArgStrategy { name, strategy })
+    }
+}
+
+/// Builds a proptest test of a function with the given spec.
+///
+/// The test is named after the function, e.g. `__anodized_proptest_divide` for `divide`,
+/// and is only compiled under `cfg(test)`, so the crate needs `proptest` as a
+/// dev-dependency. An argument without a strategy in `args` is generated with
+/// `proptest::arbitrary::any`, so its type must implement `Arbitrary`; an argument `&T` or
+/// `&mut T` borrows a generated `T`, where `&str` borrows a `String` and `&[T]` a `Vec<T>`.
+///
+/// Inputs that violate a precondition or invariant are rejected, so a spec that few inputs
+/// satisfy needs strategies for its arguments. Proptest runs the cases as configured by
+/// its `PROPTEST_*` environment variables.
+///
+/// Returns an error if the function can't be called from a test generically: if it has a
+/// receiver, mentions `Self`, has generic parameters, is `async` or variadic, or has an
+/// argument that is a pattern other than a name, or an `impl Trait` without a strategy. A
+/// strategy for an argument the function doesn't have is an error too.
+pub fn build_test(func: &ItemFn, spec: &Spec, args: &TestArgs) -> syn::Result<ItemFn> {
+    let sig = &func.sig;
+    let unsupported = if sig.asyncness.is_some() {
+        Some("`async` functions")
+    } else if sig.variadic.is_some() {
+        Some("variadic functions")
+    } else if !sig.generics.params.is_empty() {
+        Some("generic functions")
+    } else if sig.receiver().is_some() {
+        Some("methods")
+    } else if mentions_self(sig.to_token_stream()) {
+        Some("functions that mention `Self`")
+    } else {
+        None
+    };
+    if let Some(unsupported) = unsupported {
+        return Err(syn::Error::new_spanned(
+            &sig.ident,
+            format!("`#[spec_proptest]` can't test {unsupported}"),
+        ));
+    }
+
+    let fn_name = &sig.ident;
+    for (name, _) in &args.strategies {
+        let is_arg = sig.inputs.iter().any(|input| match input {
+            FnArg::Typed(input) => {
+                matches!(input.pat.as_ref(), Pat::Ident(pat) if pat.ident == *name)
+            }
+            FnArg::Receiver(_) => false,
+        });
+        if !is_arg {
+            return Err(syn::Error::new(
+                name.span(),
+                format!("`{name}` is not an argument of `{fn_name}`"),
+            ));
+        }
+    }
+
+    let mut value_names = Vec::with_capacity(sig.inputs.len());
+    let mut names = Vec::with_capacity(sig.inputs.len());
+    let mut strategies = Vec::with_capacity(sig.inputs.len());
+    let mut borrows = Vec::new();
+    for input in &sig.inputs {
+        let FnArg::Typed(input) = input else {
+            unreachable!("methods are rejected above");
+        };
+        let Pat::Ident(pat) = input.pat.as_ref() else {
+            return Err(syn::Error::new_spanned(
+                &input.pat,
+                "`#[spec_proptest]` needs a name for each argument",
+            ));
+        };
+        let (name, ty) = (&pat.ident, input.ty.as_ref());
+        let strategy = match args.strategies.iter().find(|(other, _)| other == name) {
+            Some((_, strategy)) => strategy.to_token_stream(),
+            None => {
+                let value_type = owned_type(ty).ok_or_else(|| {
+                    syn::Error::new_spanned(
+                        ty,
+                        format!(
+                            "`#[spec_proptest]` can't generate this type; give a strategy, \
+                             e.g. `#[spec_proptest({name} in ...)]`"
+                        ),
+                    )
+                })?;
+                quote! { ::proptest::arbitrary::any::<#value_type>() }
+            }
+        };
+        if let Type::Reference(reference) = ty {
+            let mutability = &reference.mutability;
+            borrows.push(quote! { let #name: #ty = &#mutability #name; });
+            names.push(quote! { #mutability #name });
+        } else {
+            let mutability = &pat.mutability;
+            names.push(quote! { #mutability #name });
+        }
+        value_names.push(name);
+        strategies.push(strategy);
+    }
+
+    let assumptions = spec
+        .requires
+        .iter()
+        .chain(&spec.maintains)
+        .map(|condition| {
+            let body = &condition.closure.body;
+            let reason = body.to_token_stream().to_string();
+            guard(
+                condition.cfg.as_ref(),
+                quote! {
+                    if !(#body) {
+                        return ::core::result::Result::Err(
+                            ::proptest::test_runner::TestCaseError::reject(#reason),
+                        );
+                    }
+                },
+            )
+        });
+
+    let call = quote! { #fn_name(#(#value_names),*) };
+    let call = if sig.unsafety.is_some() {
+        quote! { unsafe { #call } }
+    } else {
+        call
+    };
+
+    let return_type = return_type(sig);
+    let output = Ident::new("__anodized_output", Span::mixed_site());
+    let aliases = spec.captures.iter().map(|capture| &capture.pat);
+    let capture_exprs = spec.captures.iter().map(|capture| &capture.expr);
+    let types = spec.captures.iter().map(|_| quote! { _ });
+
+    let fail = |kind, label, repr, check: TokenStream| {
+        let message = message(kind, label, repr);
+        quote! {
+            if !(#check) {
+                return ::core::result::Result::Err(
+                    ::proptest::test_runner::TestCaseError::fail(#message),
+                );
+            }
+        }
+    };
+    let checks = spec
+        .maintains
+        .iter()
+        .map(|condition| {
+            let body = &condition.closure.body;
+            guard(
+                condition.cfg.as_ref(),
+                fail(
+                    CheckKind::PostInvariant,
+                    condition.label.as_ref(),
+                    body.to_token_stream(),
+                    body.to_token_stream(),
+                ),
+            )
+        })
+        .chain(spec.ensures.iter().map(|postcondition| {
+            let closure = annotate_postcondition_closure_argument(
+                postcondition.closure.clone(),
+                return_type.clone(),
+            );
+            guard(
+                postcondition.cfg.as_ref(),
+                fail(
+                    CheckKind::Postcondition,
+                    postcondition.label.as_ref(),
+                    postcondition.closure.to_token_stream(),
+                    quote! { (#closure)(&#output) },
+                ),
+            )
+        }));
+
+    // The minimal failing input, named after the arguments.
+    let input_format = value_names
+        .iter()
+        .map(|name| format!("{name} = {{:?}}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let failure_format = format!("{{}}\nminimal failing input: {input_format}");
+    let abort_format = format!(
+        "{{}}; few generated inputs satisfy the preconditions of `{fn_name}`, so give \
+         strategies for its arguments, e.g. `#[spec_proptest(arg in ...)]`"
+    );
+
+    let runner = Ident::new("__anodized_runner", Span::mixed_site());
+    let result = Ident::new("__anodized_result", Span::mixed_site());
+    let reason = Ident::new("__anodized_reason", Span::mixed_site());
+    let test_attrs = &args.attrs;
+    let test_name = format_ident!("__anodized_proptest_{}", fn_name);
+    Ok(parse_quote! {
+        #[cfg(test)]
+        #[test]
+        #(#test_attrs)*
+        fn #test_name() {
+            let mut #runner = ::proptest::test_runner::TestRunner::new(
+                ::proptest::test_runner::Config {
+                    source_file: ::core::option::Option::Some(::core::file!()),
+                    ..::proptest::test_runner::Config::default()
+                },
+            );
+            let #result = #runner.run(&(#(#strategies,)*), |(#(#names,)*)| {
+                #(#borrows)*
+                #(#assumptions)*
+                let (#(#aliases,)* #output): (#(#types,)* #return_type) = (#(#capture_exprs,)* #call);
+                #(#checks)*
+                ::core::result::Result::Ok(())
+            });
+            match #result {
+                ::core::result::Result::Ok(()) => {}
+                ::core::result::Result::Err(::proptest::test_runner::TestError::Fail(
+                    #reason,
+                    (#(#value_names,)*),
+                )) => ::core::panic!(#failure_format, #reason, #(#value_names),*),
+                ::core::result::Result::Err(::proptest::test_runner::TestError::Abort(#reason)) => {
+                    ::core::panic!(#abort_format, #reason)
+                }
+            }
+        }
+    })
+}
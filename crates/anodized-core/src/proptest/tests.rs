@@ -0,0 +1,193 @@
+use super::*;
+
+use crate::test_util::assert_tokens_eq;
+
+fn error_message(func: ItemFn, spec: Spec, args: TestArgs) -> String {
+    match build_test(&func, &spec, &args) {
+        Ok(_) => panic!("expected an error"),
+        Err(error) => error.to_string(),
+    }
+}
+
+#[test]
+fn test() {
+    let spec: Spec = parse_quote! {
+        requires: divisor != 0,
+        captures: dividend as original,
+        ensures: *output <= original,
+    };
+    let func: ItemFn = parse_quote! {
+        fn divide(dividend: u32, divisor: u32) -> u32 {
+            dividend / divisor
+        }
+    };
+    let args: TestArgs = parse_quote! {};
+
+    let expected: ItemFn = parse_quote! {
+        #[cfg(test)]
+        #[test]
+        fn __anodized_proptest_divide() {
+            let mut __anodized_runner = ::proptest::test_runner::TestRunner::new(
+                ::proptest::test_runner::Config {
+                    source_file: ::core::option::Option::Some(::core::file!()),
+                    ..::proptest::test_runner::Config::default()
+                },
+            );
+            let __anodized_result = __anodized_runner.run(
+                &(
+                    ::proptest::arbitrary::any::<u32>(),
+                    ::proptest::arbitrary::any::<u32>(),
+                ),
+                |(dividend, divisor,)| {
+                    if !(divisor != 0) {
+                        return ::core::result::Result::Err(
+                            ::proptest::test_runner::TestCaseError::reject("divisor != 0"),
+                        );
+                    }
+                    let (original, __anodized_output): (_, u32) =
+                        (dividend, divide(dividend, divisor));
+                    if !((|output: &u32| *output <= original)(&__anodized_output)) {
+                        return ::core::result::Result::Err(
+                            ::proptest::test_runner::TestCaseError::fail(
+                                "Postcondition failed: | output | * output <= original"
+                            ),
+                        );
+                    }
+                    ::core::result::Result::Ok(())
+                }
+            );
+            match __anodized_result {
+                ::core::result::Result::Ok(()) => {}
+                ::core::result::Result::Err(::proptest::test_runner::TestError::Fail(
+                    __anodized_reason,
+                    (dividend, divisor,),
+                )) => ::core::panic!(
+                    "{}\nminimal failing input: dividend = {:?}, divisor = {:?}",
+                    __anodized_reason,
+                    dividend,
+                    divisor
+                ),
+                ::core::result::Result::Err(::proptest::test_runner::TestError::Abort(
+                    __anodized_reason
+                )) => {
+                    ::core::panic!(
+                        "{}; few generated inputs satisfy the preconditions of `divide`, so give strategies for its arguments, e.g. `#[spec_proptest(arg in ...)]`",
+                        __anodized_reason
+                    )
+                }
+            }
+        }
+    };
+
+    assert_tokens_eq(&build_test(&func, &spec, &args).unwrap(), &expected);
+}
+
+#[test]
+fn test_with_strategies_and_references() {
+    let spec: Spec = parse_quote! {
+        #[cfg(debug_assertions)]
+        requires: *count > 0,
+        #[label = "bounded"]
+        maintains: *count < 100,
+    };
+    let func: ItemFn = parse_quote! {
+        unsafe fn step(count: &mut u8, name: &str, items: &[u8]) {}
+    };
+    let args: TestArgs = parse_quote! { #[ignore] count in 1..100u8 };
+
+    let test = build_test(&func, &spec, &args).unwrap();
+
+    let expected_attrs: Vec<Attribute> = vec![
+        parse_quote! { #[cfg(test)] },
+        parse_quote! { #[test] },
+        parse_quote! { #[ignore] },
+    ];
+    assert_eq!(test.attrs, expected_attrs);
+    let body = test.block.to_token_stream().to_string();
+    for expected in [
+        quote! {
+            &(
+                1..100u8,
+                ::proptest::arbitrary::any::<::std::string::String>(),
+                ::proptest::arbitrary::any::<::std::vec::Vec<u8> >(),
+            )
+        },
+        quote! { |(mut count, name, items,)| },
+        quote! {
+            let count: &mut u8 = &mut count;
+            let name: &str = &name;
+            let items: &[u8] = &items;
+            if cfg!(debug_assertions) {
+                if !(*count > 0) {
+                    return ::core::result::Result::Err(
+                        ::proptest::test_runner::TestCaseError::reject("* count > 0"),
+                    );
+                }
+            }
+        },
+        quote! { unsafe { step(count, name, items) } },
+        quote! {
+            ::proptest::test_runner::TestCaseError::fail(
+                "Post-invariant failed: bounded: * count < 100"
+            )
+        },
+    ] {
+        assert!(
+            body.contains(&expected.to_string()),
+            "missing `{expected}` in `{body}`"
+        );
+    }
+}
+
+#[test]
+fn unsupported() {
+    assert_eq!(
+        error_message(
+            parse_quote! { fn get(&self) -> u8 {} },
+            parse_quote! { requires: true },
+            parse_quote! {},
+        ),
+        "`#[spec_proptest]` can't test methods"
+    );
+    assert_eq!(
+        error_message(
+            parse_quote! { fn first<T>(items: Vec<T>) {} },
+            parse_quote! { requires: true },
+            parse_quote! {},
+        ),
+        "`#[spec_proptest]` can't test generic functions"
+    );
+    assert_eq!(
+        error_message(
+            parse_quote! { fn f((a, b): (u8, u8)) {} },
+            parse_quote! { requires: true },
+            parse_quote! {},
+        ),
+        "`#[spec_proptest]` needs a name for each argument"
+    );
+    assert_eq!(
+        error_message(
+            parse_quote! { fn f(items: impl Iterator<Item = u8>) {} },
+            parse_quote! { requires: true },
+            parse_quote! {},
+        ),
+        "`#[spec_proptest]` can't generate this type; give a strategy, e.g. \
+         `#[spec_proptest(items in ...)]`"
+    );
+    assert_eq!(
+        error_message(
+            parse_quote! { fn f(x: u8) {} },
+            parse_quote! { requires: true },
+            parse_quote! { y in 0..10u8 },
+        ),
+        "`y` is not an argument of `f`"
+    );
+}
+
+#[test]
+fn duplicate_strategies() {
+    let error = syn::parse_str::<TestArgs>("x in 0..1, x in 2..3")
+        .err()
+        .unwrap();
+    assert_eq!(error.to_string(), "multiple strategies for `x`");
+}
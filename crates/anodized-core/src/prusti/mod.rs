@@ -0,0 +1,122 @@
+//! Contracts for the [Prusti](https://github.com/viperproject/prusti-dev) verifier.
+//!
+//! Prusti states contracts with `#[requires]` and `#[ensures]` attributes, which may only
+//! call `#[pure]` functions. A postcondition refers to the return value as `result`, and to
+//! the value of an expression at entry as `old(...)`, so the translation of a postcondition:
+//! - replaces the return value with `result`, and the names in a tuple pattern of `binds:`
+//!   with its fields, e.g. `result.0`,
+//! - replaces each capture with `old(...)` of the captured expression.
+//!
+//! Conditions are translated whatever their `#[warn]`, `#[deny]`, `#[sample]`, or
+//! `#[expensive]` attributes; a `#[cfg]` still applies.
+
+#[cfg(test)]
+mod tests;
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Attribute, Expr, Meta, Signature, Type, parse_quote, spanned::Spanned};
+
+use crate::{
+    Spec,
+    translate::{PostRewrite, condition_error, mut_ref_args, return_type, unsupported_constructs},
+};
+
+/// Builds the Prusti contract attributes of a function with the given spec.
+///
+/// Each attribute is only active under `cfg(prusti)`, and under the `#[cfg]` of its
+/// condition. A `requires` condition becomes `requires`, an `ensures` condition becomes
+/// `ensures`, and a `maintains` condition becomes both.
+///
+/// A `const fn` that returns a value and takes no `&mut` arguments is also marked `pure`, so
+/// that the conditions of other functions can call it. Functions that aren't `const` can't
+/// be told apart from ones with side effects, so they must be marked by hand.
+///
+/// Returns an error for each condition that calls what can't be a pure function, e.g. a
+/// macro or a function taking a mutable borrow, or that Prusti can't express otherwise,
+/// and for a postcondition that destructures the return value with a pattern other than a
+/// tuple of names.
+pub fn build_contract(sig: &Signature, spec: &Spec) -> syn::Result<Vec<Attribute>> {
+    let mut errors = Vec::new();
+    let rewrite = PostRewrite {
+        result: parse_quote! { result },
+        captures: &spec.captures,
+        capture: |expr| parse_quote! { old(#expr) },
+        // Prusti reads a mutable reference in a postcondition as its value at exit.
+        mut_refs: &[],
+        final_value: |ident| parse_quote! { #ident },
+    };
+
+    let mut attrs = Vec::new();
+    if is_pure(sig) {
+        attrs.push(parse_quote! { #[cfg_attr(prusti, prusti_contracts::pure)] });
+    }
+    for condition in spec.requires.iter().chain(&spec.maintains) {
+        let body = &condition.closure.body;
+        check(body, &mut errors);
+        attrs.push(contract_attr(
+            condition.cfg.as_ref(),
+            quote! { prusti_contracts::requires(#body) },
+        ));
+    }
+    for condition in &spec.maintains {
+        let body = rewrite.condition(&condition.closure.body);
+        attrs.push(contract_attr(
+            condition.cfg.as_ref(),
+            quote! { prusti_contracts::ensures(#body) },
+        ));
+    }
+    for postcondition in &spec.ensures {
+        check(&postcondition.closure.body, &mut errors);
+        let Some(body) = rewrite.postcondition(postcondition) else {
+            errors.push(condition_error(
+                postcondition.closure.span(),
+                "Prusti translation cannot destructure the return value",
+                &postcondition.closure,
+            ));
+            continue;
+        };
+        attrs.push(contract_attr(
+            postcondition.cfg.as_ref(),
+            quote! { prusti_contracts::ensures(#body) },
+        ));
+    }
+
+    match errors.into_iter().reduce(|mut combined, error| {
+        combined.combine(error);
+        combined
+    }) {
+        Some(error) => Err(error),
+        None => Ok(attrs),
+    }
+}
+
+/// Whether a function can be marked `pure`, as far as the signature tells.
+fn is_pure(sig: &Signature) -> bool {
+    sig.constness.is_some()
+        && sig.asyncness.is_none()
+        && sig.unsafety.is_none()
+        && mut_ref_args(sig).is_empty()
+        && !matches!(return_type(sig), Type::Tuple(tuple) if tuple.elems.is_empty())
+}
+
+/// Adds an error for each construct in the condition that isn't pure.
+fn check(condition: &Expr, errors: &mut Vec<syn::Error>) {
+    for (span, construct) in unsupported_constructs(condition) {
+        errors.push(condition_error(
+            span,
+            &format!(
+                "Prusti conditions may only call pure functions, and cannot contain {construct}"
+            ),
+            condition,
+        ));
+    }
+}
+
+/// Makes an attribute that is only active under `cfg(prusti)` and the condition's `#[cfg]`.
+fn contract_attr(cfg: Option<&Meta>, contract: TokenStream) -> Attribute {
+    match cfg {
+        Some(cfg) => parse_quote! { #[cfg_attr(all(prusti, #cfg), #contract)] },
+        None => parse_quote! { #[cfg_attr(prusti, #contract)] },
+    }
+}
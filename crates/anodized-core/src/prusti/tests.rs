@@ -0,0 +1,106 @@
+use super::*;
+
+use quote::ToTokens;
+use syn::ItemFn;
+
+use crate::test_util::assert_tokens_eq;
+
+fn assert_attrs_eq(observed: &[Attribute], expected: &[Attribute]) {
+    assert_eq!(observed.len(), expected.len(), "number of attributes");
+    for (observed, expected) in observed.iter().zip(expected) {
+        assert_tokens_eq(observed, expected);
+    }
+}
+
+#[test]
+fn contract() {
+    let spec: Spec = parse_quote! {
+        requires: *balance >= amount,
+        #[cfg(debug_assertions)]
+        maintains: *balance <= MAX,
+        captures: *balance as initial,
+        binds: (new_balance, receipt),
+        ensures: [
+            *new_balance == initial - amount,
+            *balance == *new_balance,
+            receipt.amount == amount,
+        ],
+    };
+    let func: ItemFn = parse_quote! {
+        fn withdraw(balance: &mut u64, amount: u64) -> (u64, Receipt) {}
+    };
+
+    let expected: [Attribute; 6] = [
+        parse_quote! { #[cfg_attr(prusti, prusti_contracts::requires(*balance >= amount))] },
+        parse_quote! {
+            #[cfg_attr(all(prusti, debug_assertions), prusti_contracts::requires(*balance <= MAX))]
+        },
+        parse_quote! {
+            #[cfg_attr(all(prusti, debug_assertions), prusti_contracts::ensures(*balance <= MAX))]
+        },
+        parse_quote! {
+            #[cfg_attr(prusti, prusti_contracts::ensures(result.0 == (old(*balance)) - amount))]
+        },
+        parse_quote! { #[cfg_attr(prusti, prusti_contracts::ensures(*balance == result.0))] },
+        parse_quote! { #[cfg_attr(prusti, prusti_contracts::ensures(result.1.amount == amount))] },
+    ];
+
+    assert_attrs_eq(&build_contract(&func.sig, &spec).unwrap(), &expected);
+}
+
+#[test]
+fn pure_fn() {
+    let spec: Spec = parse_quote! {
+        ensures: *output <= x,
+    };
+    let func: ItemFn = parse_quote! {
+        const fn half(x: u32) -> u32 {}
+    };
+
+    let expected: [Attribute; 2] = [
+        parse_quote! { #[cfg_attr(prusti, prusti_contracts::pure)] },
+        parse_quote! { #[cfg_attr(prusti, prusti_contracts::ensures(result <= x))] },
+    ];
+
+    assert_attrs_eq(&build_contract(&func.sig, &spec).unwrap(), &expected);
+
+    let not_pure: [ItemFn; 3] = [
+        parse_quote! { fn half(x: u32) -> u32 {} },
+        parse_quote! { const fn clear(x: &mut u32) -> u32 {} },
+        parse_quote! { const fn nothing(x: u32) {} },
+    ];
+    for func in not_pure {
+        let attrs = build_contract(&func.sig, &spec).unwrap();
+        assert!(
+            !attrs
+                .iter()
+                .any(|attr| attr.to_token_stream().to_string().contains("pure")),
+            "expected `{}` not to be pure",
+            func.sig.to_token_stream()
+        );
+    }
+}
+
+#[test]
+fn impure_conditions() {
+    let spec: Spec = parse_quote! {
+        requires: log_call!(x),
+        ensures: take(&mut buffer) == *output,
+    };
+    let func: ItemFn = parse_quote! {
+        fn f(x: u32, buffer: Vec<u8>) -> Vec<u8> {}
+    };
+
+    let errors = build_contract(&func.sig, &spec)
+        .unwrap_err()
+        .into_iter()
+        .map(|error| error.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        errors,
+        [
+            "Prusti conditions may only call pure functions, and cannot contain macro calls: `log_call ! (x)`",
+            "Prusti conditions may only call pure functions, and cannot contain mutable borrows: `take (& mut buffer) == * output`",
+        ]
+    );
+}
@@ -10,6 +10,7 @@ pub fn assert_tokens_eq(left: &impl ToTokens, right: &impl ToTokens) {
 pub fn assert_spec_eq(left: &Spec, right: &Spec) {
     // Destructure to ensure we handle all fields - compilation will fail if fields are added
     let Spec {
+        mode: left_mode,
         requires: left_requires,
         maintains: left_maintains,
         captures: left_captures,
@@ -18,6 +19,7 @@ pub fn assert_spec_eq(left: &Spec, right: &Spec) {
     } = left;
 
     let Spec {
+        mode: right_mode,
         requires: right_requires,
         maintains: right_maintains,
         captures: right_captures,
@@ -25,6 +27,7 @@ pub fn assert_spec_eq(left: &Spec, right: &Spec) {
         span: _,
     } = right;
 
+    assert_eq!(left_mode, right_mode, "`mode` does not match");
     assert_slice_eq(
         left_requires,
         right_requires,
@@ -68,11 +71,19 @@ fn assert_precondition_eq(left: &PreCondition, right: &PreCondition, msg_prefix:
     let PreCondition {
         closure: left_expr,
         cfg: left_cfg,
+        label: left_label,
+        severity: left_severity,
+        sample: left_sample,
+        expensive: left_expensive,
     } = left;
 
     let PreCondition {
         closure: right_expr,
         cfg: right_cfg,
+        label: right_label,
+        severity: right_severity,
+        sample: right_sample,
+        expensive: right_expensive,
     } = right;
 
     assert_eq!(
@@ -88,6 +99,31 @@ fn assert_precondition_eq(left: &PreCondition, right: &PreCondition, msg_prefix:
         "{}`cfg` does not match",
         msg_prefix
     );
+
+    assert_eq!(
+        left_label.to_token_stream().to_string(),
+        right_label.to_token_stream().to_string(),
+        "{}`label` does not match",
+        msg_prefix
+    );
+
+    assert_eq!(
+        left_severity, right_severity,
+        "{}`severity` does not match",
+        msg_prefix
+    );
+
+    assert_eq!(
+        left_sample, right_sample,
+        "{}`sample` does not match",
+        msg_prefix
+    );
+
+    assert_eq!(
+        left_expensive, right_expensive,
+        "{}`expensive` does not match",
+        msg_prefix
+    );
 }
 
 fn assert_postcondition_eq(left: &PostCondition, right: &PostCondition, msg_prefix: &str) {
@@ -95,11 +131,19 @@ fn assert_postcondition_eq(left: &PostCondition, right: &PostCondition, msg_pref
     let PostCondition {
         closure: left_closure,
         cfg: left_cfg,
+        label: left_label,
+        severity: left_severity,
+        sample: left_sample,
+        expensive: left_expensive,
     } = left;
 
     let PostCondition {
         closure: right_closure,
         cfg: right_cfg,
+        label: right_label,
+        severity: right_severity,
+        sample: right_sample,
+        expensive: right_expensive,
     } = right;
 
     assert_eq!(
@@ -115,6 +159,31 @@ fn assert_postcondition_eq(left: &PostCondition, right: &PostCondition, msg_pref
         "{}`cfg` does not match",
         msg_prefix
     );
+
+    assert_eq!(
+        left_label.to_token_stream().to_string(),
+        right_label.to_token_stream().to_string(),
+        "{}`label` does not match",
+        msg_prefix
+    );
+
+    assert_eq!(
+        left_severity, right_severity,
+        "{}`severity` does not match",
+        msg_prefix
+    );
+
+    assert_eq!(
+        left_sample, right_sample,
+        "{}`sample` does not match",
+        msg_prefix
+    );
+
+    assert_eq!(
+        left_expensive, right_expensive,
+        "{}`expensive` does not match",
+        msg_prefix
+    );
 }
 
 fn assert_capture_eq(left: &Capture, right: &Capture, msg_prefix: &str) {
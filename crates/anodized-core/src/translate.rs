@@ -0,0 +1,394 @@
+//! Rewriting of conditions, shared by the translations of specs for verifiers.
+
+use std::collections::HashMap;
+
+use proc_macro2::{Span, TokenStream, TokenTree};
+use quote::{ToTokens, quote};
+use syn::{
+    Arm, BinOp, Block, Expr, ExprClosure, ExprForLoop, ExprIf, ExprPath, ExprWhile, FnArg, Ident,
+    LitStr, Meta, Pat, PatIdent, Signature, Stmt, Type, UnOp, parse_quote,
+    spanned::Spanned,
+    visit::{self, Visit},
+    visit_mut::{self, VisitMut},
+};
+
+use crate::{Capture, PostCondition, instrument::CheckKind};
+
+/// The return type of a function, `()` if it has none.
+pub fn return_type(sig: &Signature) -> Type {
+    match &sig.output {
+        syn::ReturnType::Default => parse_quote!(()),
+        syn::ReturnType::Type(_, ty) => ty.as_ref().clone(),
+    }
+}
+
+/// The names of the arguments that are mutable references, including `self` for `&mut self`.
+pub fn mut_ref_args(sig: &Signature) -> Vec<Ident> {
+    sig.inputs
+        .iter()
+        .filter_map(|input| match input {
+            FnArg::Receiver(receiver)
+                if receiver.reference.is_some() && receiver.mutability.is_some() =>
+            {
+                Some(Ident::new("self", receiver.self_token.span))
+            }
+            FnArg::Typed(input) => match (input.pat.as_ref(), input.ty.as_ref()) {
+                (Pat::Ident(pat), Type::Reference(reference)) if reference.mutability.is_some() => {
+                    Some(pat.ident.clone())
+                }
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+/// Wraps a statement in `if cfg!(...)` for a condition with a `#[cfg]`.
+pub fn guard(cfg: Option<&Meta>, tokens: TokenStream) -> TokenStream {
+    match cfg {
+        Some(cfg) => quote! { if cfg!(#cfg) { #tokens } },
+        None => tokens,
+    }
+}
+
+/// The message of an assertion, as in the report of a runtime check.
+pub fn message(kind: CheckKind, label: Option<&LitStr>, repr: TokenStream) -> LitStr {
+    let message = match label {
+        Some(label) => format!("{} failed: {}: {repr}", kind.title(), label.value()),
+        None => format!("{} failed: {repr}", kind.title()),
+    };
+    LitStr::new(&message, Span::call_site())
+}
+
+/// Whether the tokens mention `Self`.
+pub fn mentions_self(tokens: TokenStream) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(ident) => ident == "Self",
+        TokenTree::Group(group) => mentions_self(group.stream()),
+        _ => false,
+    })
+}
+
+/// The type of a value to generate for an argument of the given type, which the argument
+/// borrows if it's a reference, or `None` if there's no such type.
+pub fn owned_type(ty: &Type) -> Option<Type> {
+    match ty {
+        Type::ImplTrait(_) | Type::TraitObject(_) | Type::Infer(_) | Type::Macro(_) => None,
+        Type::Reference(reference) => match reference.elem.as_ref() {
+            Type::Path(path) if path.qself.is_none() && path.path.is_ident("str") => {
+                Some(parse_quote! { ::std::string::String })
+            }
+            Type::Slice(slice) => {
+                let elem = &slice.elem;
+                Some(parse_quote! { ::std::vec::Vec<#elem> })
+            }
+            elem => owned_type(elem),
+        },
+        Type::Paren(paren) => owned_type(&paren.elem),
+        Type::Group(group) => owned_type(&group.elem),
+        ty => Some(ty.clone()),
+    }
+}
+
+/// How a postcondition is rewritten into the language of a verifier.
+pub struct PostRewrite<'a> {
+    /// The expression for the return value, by value, e.g. `result`.
+    pub result: Expr,
+    /// The expression for the value of each capture at entry.
+    pub captures: &'a [Capture],
+    /// Wraps a captured expression, e.g. in `old(...)`.
+    pub capture: fn(&Expr) -> Expr,
+    /// Arguments whose values at exit are written with `final_value`.
+    pub mut_refs: &'a [Ident],
+    /// The value at exit of a mutable reference, e.g. `(^x)`.
+    pub final_value: fn(&Ident) -> Expr,
+}
+
+impl PostRewrite<'_> {
+    /// Rewrites the body of a postcondition, whose closure takes the return value by
+    /// reference. A tuple pattern of names binds each name to a field of the return value.
+    /// Returns `None` for any other pattern that destructures the return value.
+    pub fn postcondition(&self, postcondition: &PostCondition) -> Option<Expr> {
+        let result = &self.result;
+        let outputs = match postcondition.closure.inputs.first() {
+            Some(Pat::Ident(pat)) if pat.by_ref.is_none() && pat.subpat.is_none() => {
+                HashMap::from([(pat.ident.clone(), result.clone())])
+            }
+            Some(Pat::Wild(_)) | None => HashMap::new(),
+            Some(Pat::Tuple(tuple)) => tuple
+                .elems
+                .iter()
+                .enumerate()
+                .map(|(index, elem)| match elem {
+                    Pat::Ident(pat) if pat.by_ref.is_none() && pat.subpat.is_none() => {
+                        let index = syn::Index::from(index);
+                        Some((pat.ident.clone(), parse_quote! { #result.#index }))
+                    }
+                    _ => None,
+                })
+                .collect::<Option<_>>()?,
+            Some(_) => return None,
+        };
+        Some(self.rewrite(&postcondition.closure.body, outputs))
+    }
+
+    /// Rewrites a condition that holds on exit and doesn't refer to the return value.
+    pub fn condition(&self, condition: &Expr) -> Expr {
+        self.rewrite(condition, HashMap::new())
+    }
+
+    fn rewrite(&self, condition: &Expr, outputs: HashMap<Ident, Expr>) -> Expr {
+        let captures = self
+            .captures
+            .iter()
+            .filter_map(|capture| match &capture.pat {
+                Pat::Ident(pat) => Some((pat.ident.clone(), &capture.expr)),
+                _ => None,
+            })
+            .collect();
+        let mut rewriter = Rewriter {
+            rewrite: self,
+            outputs,
+            captures,
+            shadowed: Vec::new(),
+        };
+        let mut condition = condition.clone();
+        rewriter.visit_expr_mut(&mut condition);
+        condition
+    }
+}
+
+struct Rewriter<'a> {
+    rewrite: &'a PostRewrite<'a>,
+    /// The names bound to the return value, or its parts, by reference.
+    outputs: HashMap<Ident, Expr>,
+    captures: HashMap<Ident, &'a Expr>,
+    /// The names bound in the condition where it's being rewritten, which shadow the names
+    /// above.
+    shadowed: Vec<Ident>,
+}
+
+impl Rewriter<'_> {
+    fn path_ident<'e>(&self, expr: &'e Expr) -> Option<&'e Ident> {
+        match expr {
+            Expr::Path(ExprPath {
+                qself: None, path, ..
+            }) => path
+                .get_ident()
+                .filter(|ident| !self.shadowed.contains(ident)),
+            _ => None,
+        }
+    }
+
+    /// Rewrites in the scope of the names bound by the patterns.
+    fn in_scope<'p>(&mut self, pats: impl IntoIterator<Item = &'p Pat>, f: impl FnOnce(&mut Self)) {
+        let len = self.shadowed.len();
+        for pat in pats {
+            self.bind(pat);
+        }
+        f(self);
+        self.shadowed.truncate(len);
+    }
+
+    fn bind(&mut self, pat: &Pat) {
+        let mut names = BoundNames(Vec::new());
+        names.visit_pat(pat);
+        self.shadowed.extend(names.0);
+    }
+
+    fn output(&self, expr: &Expr) -> Option<Expr> {
+        self.path_ident(expr)
+            .and_then(|ident| self.outputs.get(ident))
+            .cloned()
+    }
+
+    fn mut_ref(&self, expr: &Expr) -> Option<Ident> {
+        self.path_ident(expr)
+            .filter(|ident| self.rewrite.mut_refs.contains(ident))
+            .cloned()
+    }
+}
+
+impl VisitMut for Rewriter<'_> {
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        // The output, by reference, whether dereferenced or not.
+        if let Expr::Unary(unary) = expr
+            && matches!(unary.op, UnOp::Deref(_))
+            && let Some(output) = self.output(&unary.expr)
+        {
+            *expr = output;
+            return;
+        }
+        if let Some(output) = self.output(expr) {
+            *expr = output;
+            return;
+        }
+
+        // A capture stands for the value of its expression at entry.
+        if let Some(captured) = self
+            .path_ident(expr)
+            .and_then(|ident| self.captures.get(ident))
+        {
+            let captured = (self.rewrite.capture)(captured);
+            *expr = parse_quote! { (#captured) };
+            return;
+        }
+
+        // A mutable reference argument, dereferenced or accessed, stands for its value at exit.
+        match expr {
+            Expr::Unary(unary) if matches!(unary.op, UnOp::Deref(_)) => {
+                if let Some(ident) = self.mut_ref(&unary.expr) {
+                    *expr = (self.rewrite.final_value)(&ident);
+                    return;
+                }
+            }
+            Expr::Field(field) => {
+                if let Some(ident) = self.mut_ref(&field.base) {
+                    *field.base = (self.rewrite.final_value)(&ident);
+                    return;
+                }
+            }
+            Expr::MethodCall(call) => {
+                if let Some(ident) = self.mut_ref(&call.receiver) {
+                    *call.receiver = (self.rewrite.final_value)(&ident);
+                    for arg in &mut call.args {
+                        self.visit_expr_mut(arg);
+                    }
+                    return;
+                }
+            }
+            _ => {}
+        }
+
+        visit_mut::visit_expr_mut(self, expr);
+    }
+
+    fn visit_block_mut(&mut self, block: &mut Block) {
+        let len = self.shadowed.len();
+        for stmt in &mut block.stmts {
+            self.visit_stmt_mut(stmt);
+            if let Stmt::Local(local) = stmt {
+                self.bind(&local.pat);
+            }
+        }
+        self.shadowed.truncate(len);
+    }
+
+    fn visit_expr_closure_mut(&mut self, closure: &mut ExprClosure) {
+        let inputs = closure.inputs.clone();
+        self.in_scope(&inputs, |this| this.visit_expr_mut(&mut closure.body));
+    }
+
+    fn visit_arm_mut(&mut self, arm: &mut Arm) {
+        let pat = arm.pat.clone();
+        self.in_scope([&pat], |this| {
+            if let Some((_, guard)) = &mut arm.guard {
+                this.visit_expr_mut(guard);
+            }
+            this.visit_expr_mut(&mut arm.body);
+        });
+    }
+
+    fn visit_expr_for_loop_mut(&mut self, for_loop: &mut ExprForLoop) {
+        self.visit_expr_mut(&mut for_loop.expr);
+        let pat = for_loop.pat.clone();
+        self.in_scope([pat.as_ref()], |this| {
+            this.visit_block_mut(&mut for_loop.body)
+        });
+    }
+
+    fn visit_expr_if_mut(&mut self, expr_if: &mut ExprIf) {
+        self.visit_expr_mut(&mut expr_if.cond);
+        let pats = let_pats(&expr_if.cond);
+        self.in_scope(&pats, |this| this.visit_block_mut(&mut expr_if.then_branch));
+        if let Some((_, else_branch)) = &mut expr_if.else_branch {
+            self.visit_expr_mut(else_branch);
+        }
+    }
+
+    fn visit_expr_while_mut(&mut self, expr_while: &mut ExprWhile) {
+        self.visit_expr_mut(&mut expr_while.cond);
+        let pats = let_pats(&expr_while.cond);
+        self.in_scope(&pats, |this| this.visit_block_mut(&mut expr_while.body));
+    }
+}
+
+/// The patterns of the `let`s in the condition of an `if` or `while`, e.g. in `let` chains.
+fn let_pats(cond: &Expr) -> Vec<Pat> {
+    match cond {
+        Expr::Let(expr_let) => vec![(*expr_let.pat).clone()],
+        Expr::Binary(binary) if matches!(binary.op, BinOp::And(_)) => {
+            let mut pats = let_pats(&binary.left);
+            pats.extend(let_pats(&binary.right));
+            pats
+        }
+        Expr::Paren(paren) => let_pats(&paren.expr),
+        _ => Vec::new(),
+    }
+}
+
+/// Collects the names that a pattern binds.
+struct BoundNames(Vec<Ident>);
+
+impl<'ast> Visit<'ast> for BoundNames {
+    fn visit_pat_ident(&mut self, pat: &'ast PatIdent) {
+        self.0.push(pat.ident.clone());
+        visit::visit_pat_ident(self, pat);
+    }
+}
+
+/// Finds the constructs in a condition that a verifier's specification language can't
+/// express, described for an error message, e.g. "closures".
+pub fn unsupported_constructs(condition: &Expr) -> Vec<(Span, &'static str)> {
+    let mut finder = UnsupportedFinder { found: Vec::new() };
+    finder.visit_expr(condition);
+    finder.found
+}
+
+struct UnsupportedFinder {
+    found: Vec<(Span, &'static str)>,
+}
+
+impl<'ast> Visit<'ast> for UnsupportedFinder {
+    fn visit_expr(&mut self, expr: &'ast Expr) {
+        let construct = match expr {
+            Expr::Closure(_) => Some("closures"),
+            Expr::Async(_) | Expr::Await(_) => Some("async code"),
+            Expr::Loop(_) | Expr::While(_) | Expr::ForLoop(_) => Some("loops"),
+            Expr::Assign(_) => Some("assignments"),
+            Expr::Binary(binary) if is_compound_assignment(binary) => Some("assignments"),
+            Expr::Macro(_) => Some("macro calls"),
+            Expr::Unsafe(_) => Some("unsafe blocks"),
+            Expr::Try(_) | Expr::TryBlock(_) => Some("the `?` operator"),
+            Expr::Return(_) | Expr::Break(_) | Expr::Continue(_) => Some("control flow"),
+            Expr::Reference(reference) if reference.mutability.is_some() => Some("mutable borrows"),
+            _ => None,
+        };
+        match construct {
+            Some(construct) => self.found.push((expr.span(), construct)),
+            None => visit::visit_expr(self, expr),
+        }
+    }
+}
+
+pub fn is_compound_assignment(binary: &syn::ExprBinary) -> bool {
+    matches!(
+        binary.op,
+        BinOp::AddAssign(_)
+            | BinOp::SubAssign(_)
+            | BinOp::MulAssign(_)
+            | BinOp::DivAssign(_)
+            | BinOp::RemAssign(_)
+            | BinOp::BitXorAssign(_)
+            | BinOp::BitAndAssign(_)
+            | BinOp::BitOrAssign(_)
+            | BinOp::ShlAssign(_)
+            | BinOp::ShrAssign(_)
+    )
+}
+
+/// An error at the given span, with a message that quotes the condition.
+pub fn condition_error(span: Span, message: &str, condition: &impl ToTokens) -> syn::Error {
+    let condition = condition.to_token_stream().to_string();
+    syn::Error::new(span, format!("{message}: `{condition}`"))
+}
@@ -0,0 +1,209 @@
+//! Functions for the [Verus](https://github.com/verus-lang/verus) verifier.
+//!
+//! Verus reads a function's contract from `requires` and `ensures` clauses between its
+//! signature and its body, in the syntax of its `verus!` macro. A postcondition names the
+//! return value in the signature, e.g. `-> (result: u32)`, reads a `&mut` argument `x` as
+//! its value at exit, and reads `old(x)` as its value at entry, so the translation:
+//! - replaces each `&mut` argument `x` in a precondition or a captured expression with
+//!   `old(x)`,
+//! - replaces the return value with `result`, and the names in a tuple pattern of `binds:`
+//!   with its fields, e.g. `result.0`,
+//! - replaces each capture with the captured expression.
+//!
+//! Only arithmetic and comparisons of arguments, constants, and their fields are
+//! translated for now. Conditions are translated whatever their `#[warn]`, `#[deny]`,
+//! `#[sample]`, or `#[expensive]` attributes.
+
+#[cfg(test)]
+mod tests;
+
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{
+    Expr, ExprPath, Ident, ItemFn, ReturnType, Type, parse_quote,
+    spanned::Spanned,
+    visit::{self, Visit},
+    visit_mut::{self, VisitMut},
+};
+
+use crate::{
+    Capture, Spec,
+    translate::{PostRewrite, condition_error, is_compound_assignment, mut_ref_args},
+};
+
+/// Builds a `verus!` invocation that defines the function with its spec as Verus clauses.
+///
+/// The return value is named `result` in the signature. A `requires` condition becomes a
+/// `requires` clause, an `ensures` condition becomes an `ensures` clause, and a `maintains`
+/// condition becomes both. The body is kept as it is, without runtime checks.
+///
+/// Returns an error for each condition that isn't arithmetic or a comparison, e.g. one that
+/// calls a function or a method, for a condition with a `#[cfg]`, which a clause can't
+/// have, for a postcondition that destructures the return value with a pattern other than a
+/// tuple of names, and for a function that is `async` or has a receiver, which has to be
+/// defined in a `verus!` invocation together with its `impl`.
+pub fn build_fn(func: &ItemFn, spec: &Spec) -> syn::Result<TokenStream> {
+    let mut errors = Vec::new();
+    let sig = &func.sig;
+    if sig.asyncness.is_some() {
+        errors.push(syn::Error::new(
+            sig.asyncness.span(),
+            "Verus translation cannot handle `async` functions",
+        ));
+    }
+    if let Some(receiver) = sig.receiver() {
+        errors.push(syn::Error::new(
+            receiver.span(),
+            "Verus translation cannot handle methods; define the `impl` in `verus!` instead",
+        ));
+    }
+
+    let mut_refs = mut_ref_args(sig);
+    let captures = spec
+        .captures
+        .iter()
+        .map(|capture| {
+            check(&capture.expr, &mut errors);
+            Capture {
+                expr: entry_value(&capture.expr, &mut_refs),
+                pat: capture.pat.clone(),
+            }
+        })
+        .collect::<Vec<_>>();
+    let rewrite = PostRewrite {
+        result: parse_quote! { result },
+        captures: &captures,
+        capture: Expr::clone,
+        // Verus reads a mutable reference in a postcondition as its value at exit.
+        mut_refs: &[],
+        final_value: |ident| parse_quote! { #ident },
+    };
+
+    let mut requires = Vec::new();
+    let mut ensures = Vec::new();
+    for condition in spec.requires.iter().chain(&spec.maintains) {
+        let body = &condition.closure.body;
+        check_cfg(condition.cfg.as_ref(), body, &mut errors);
+        check(body, &mut errors);
+        requires.push(entry_value(body, &mut_refs));
+    }
+    for condition in &spec.maintains {
+        ensures.push(rewrite.condition(&condition.closure.body));
+    }
+    for postcondition in &spec.ensures {
+        let body = &postcondition.closure.body;
+        check_cfg(postcondition.cfg.as_ref(), body, &mut errors);
+        check(body, &mut errors);
+        match rewrite.postcondition(postcondition) {
+            Some(body) => ensures.push(body),
+            None => errors.push(condition_error(
+                postcondition.closure.span(),
+                "Verus translation cannot destructure the return value",
+                &postcondition.closure,
+            )),
+        }
+    }
+
+    if let Some(error) = errors.into_iter().reduce(|mut combined, error| {
+        combined.combine(error);
+        combined
+    }) {
+        return Err(error);
+    }
+
+    // Verus names the return value in the signature, which isn't a Rust type.
+    let mut sig = sig.clone();
+    if let ReturnType::Type(_, ty) = &mut sig.output {
+        **ty = Type::Verbatim(quote! { (result: #ty) });
+    }
+    let requires = (!requires.is_empty()).then(|| quote! { requires #(#requires,)* });
+    let ensures = (!ensures.is_empty()).then(|| quote! { ensures #(#ensures,)* });
+    let ItemFn {
+        attrs, vis, block, ..
+    } = func;
+    Ok(quote! {
+        ::vstd::prelude::verus! {
+            #(#attrs)*
+            #vis #sig
+                #requires
+                #ensures
+            #block
+        }
+    })
+}
+
+/// Adds an error if the condition has a `#[cfg]`, which a Verus clause can't have.
+fn check_cfg(cfg: Option<&syn::Meta>, condition: &Expr, errors: &mut Vec<syn::Error>) {
+    if let Some(cfg) = cfg {
+        errors.push(condition_error(
+            cfg.span(),
+            "Verus translation cannot apply `#[cfg]` to a condition",
+            condition,
+        ));
+    }
+}
+
+/// Adds an error for each part of the condition that isn't arithmetic or a comparison.
+fn check(condition: &Expr, errors: &mut Vec<syn::Error>) {
+    let mut finder = UnsupportedFinder { found: Vec::new() };
+    finder.visit_expr(condition);
+    for (span, expr) in finder.found {
+        errors.push(condition_error(
+            span,
+            "Verus translation supports only arithmetic and comparisons, but found",
+            expr,
+        ));
+    }
+}
+
+struct UnsupportedFinder<'ast> {
+    found: Vec<(Span, &'ast Expr)>,
+}
+
+impl<'ast> Visit<'ast> for UnsupportedFinder<'ast> {
+    fn visit_expr(&mut self, expr: &'ast Expr) {
+        let supported = match expr {
+            Expr::Lit(_)
+            | Expr::Path(_)
+            | Expr::Unary(_)
+            | Expr::Paren(_)
+            | Expr::Group(_)
+            | Expr::Field(_)
+            | Expr::Cast(_) => true,
+            Expr::Binary(binary) => !is_compound_assignment(binary),
+            _ => false,
+        };
+        if supported {
+            visit::visit_expr(self, expr);
+        } else {
+            self.found.push((expr.span(), expr));
+        }
+    }
+}
+
+/// Replaces each `&mut` argument `x` in an expression evaluated at entry with `old(x)`.
+fn entry_value(expr: &Expr, mut_refs: &[Ident]) -> Expr {
+    let mut rewriter = EntryRewriter { mut_refs };
+    let mut expr = expr.clone();
+    rewriter.visit_expr_mut(&mut expr);
+    expr
+}
+
+struct EntryRewriter<'a> {
+    mut_refs: &'a [Ident],
+}
+
+impl VisitMut for EntryRewriter<'_> {
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        if let Expr::Path(ExprPath {
+            qself: None, path, ..
+        }) = expr
+            && let Some(ident) = path.get_ident()
+            && self.mut_refs.contains(ident)
+        {
+            *expr = parse_quote! { old(#ident) };
+            return;
+        }
+        visit_mut::visit_expr_mut(self, expr);
+    }
+}
@@ -0,0 +1,117 @@
+use super::*;
+
+use quote::ToTokens;
+
+use crate::test_util::assert_tokens_eq;
+
+#[test]
+fn arithmetic_and_comparisons() {
+    let spec: Spec = parse_quote! {
+        requires: [divisor != 0, dividend <= MAX],
+        binds: (quotient, remainder),
+        ensures: [
+            quotient * divisor + remainder == dividend,
+            remainder < divisor,
+        ],
+    };
+    let func: ItemFn = parse_quote! {
+        /// Divides with remainder.
+        pub fn divide(dividend: u32, divisor: u32) -> (u32, u32) {
+            (dividend / divisor, dividend % divisor)
+        }
+    };
+
+    let expected = quote! {
+        ::vstd::prelude::verus! {
+            /// Divides with remainder.
+            pub fn divide(dividend: u32, divisor: u32) -> (result: (u32, u32))
+                requires divisor != 0, dividend <= MAX,
+                ensures
+                    result.0 * divisor + result.1 == dividend,
+                    result.1 < divisor,
+            {
+                (dividend / divisor, dividend % divisor)
+            }
+        }
+    };
+
+    assert_tokens_eq(&build_fn(&func, &spec).unwrap(), &expected);
+}
+
+#[test]
+fn mutable_references() {
+    let spec: Spec = parse_quote! {
+        requires: *balance >= amount,
+        maintains: *balance <= MAX,
+        captures: *balance as initial,
+        ensures: *balance == initial - amount,
+    };
+    let func: ItemFn = parse_quote! {
+        fn withdraw(balance: &mut u64, amount: u64) {
+            *balance -= amount;
+        }
+    };
+
+    let expected = quote! {
+        ::vstd::prelude::verus! {
+            fn withdraw(balance: &mut u64, amount: u64)
+                requires *old(balance) >= amount, *old(balance) <= MAX,
+                ensures *balance <= MAX, *balance == (*old(balance)) - amount,
+            {
+                *balance -= amount;
+            }
+        }
+    };
+
+    assert_tokens_eq(&build_fn(&func, &spec).unwrap(), &expected);
+}
+
+#[test]
+fn no_spec() {
+    let spec: Spec = parse_quote! {};
+    let func: ItemFn = parse_quote! {
+        fn zero() -> u8 { 0 }
+    };
+
+    let expected = quote! {
+        ::vstd::prelude::verus! {
+            fn zero() -> (result: u8) { 0 }
+        }
+    };
+
+    assert_tokens_eq(&build_fn(&func, &spec).unwrap(), &expected);
+}
+
+#[test]
+fn unsupported() {
+    let spec: Spec = parse_quote! {
+        requires: items.len() > 0,
+        #[cfg(debug_assertions)]
+        maintains: count < 10,
+        binds: Some(first),
+        ensures: first >= 1,
+    };
+    let func: ItemFn = parse_quote! {
+        async fn first(&self, items: &[u8], count: usize) -> Option<u8> {}
+    };
+
+    let messages = build_fn(&func, &spec)
+        .unwrap_err()
+        .into_iter()
+        .map(|error| error.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        [
+            "Verus translation cannot handle `async` functions",
+            "Verus translation cannot handle methods; define the `impl` in `verus!` instead",
+            "Verus translation supports only arithmetic and comparisons, but found: `items . len ()`",
+            "Verus translation cannot apply `#[cfg]` to a condition: `count < 10`",
+            format!(
+                "Verus translation cannot destructure the return value: `{}`",
+                spec.ensures[0].closure.to_token_stream(),
+            )
+            .as_str(),
+        ]
+    );
+}
@@ -0,0 +1,82 @@
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::{fs, io};
+
+use anodized_core::export::export_crate;
+use clap::Parser;
+
+#[derive(Parser)]
+#[command(
+    name = "anodized-export",
+    version,
+    about = "Export the #[spec] annotations of a crate as JSON",
+    long_about = "Reads a crate's source, starting from its root file and following `mod` declarations, and writes the specs of all its functions as a versioned JSON document."
+)]
+struct Cli {
+    /// Crate directory, or the root file of the crate (default: current directory)
+    #[arg(value_name = "PATH")]
+    path: Option<PathBuf>,
+
+    /// Write the document to this file instead of standard output
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<PathBuf>,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let root = match find_root(cli.path.as_deref().unwrap_or(Path::new("."))) {
+        Ok(root) => root,
+        Err(error) => {
+            eprintln!("error: {error}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let document = match export_crate(&root) {
+        Ok(document) => document,
+        Err(error) => {
+            eprintln!("error: {error}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let written = match &cli.output {
+        Some(output) => fs::write(output, document + "\n")
+            .map_err(|error| format!("cannot write {}: {error}", output.display())),
+        None => {
+            println!("{document}");
+            Ok(())
+        }
+    };
+    if let Err(error) = written {
+        eprintln!("error: {error}");
+        return ExitCode::FAILURE;
+    }
+
+    ExitCode::SUCCESS
+}
+
+/// Find the root file of the crate at the given path: the path itself if it's a file,
+/// otherwise `src/lib.rs` or `src/main.rs` in the directory.
+fn find_root(path: &Path) -> io::Result<PathBuf> {
+    if path.is_file() {
+        return Ok(path.to_path_buf());
+    }
+    // Keep the paths in the document short when exporting the current directory.
+    let dir = if path == Path::new(".") {
+        Path::new("")
+    } else {
+        path
+    };
+    ["src/lib.rs", "src/main.rs"]
+        .into_iter()
+        .map(|root| dir.join(root))
+        .find(|root| root.is_file())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no src/lib.rs or src/main.rs in {}", path.display()),
+            )
+        })
+}
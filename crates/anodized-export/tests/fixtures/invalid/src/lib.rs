@@ -0,0 +1,9 @@
+use anodized::spec;
+
+#[spec(
+    requires: x > 0,
+    invariant: x < 10,
+)]
+pub fn digit(x: u32) -> u32 {
+    x
+}
@@ -0,0 +1,27 @@
+use anodized::spec;
+
+mod area;
+
+pub struct Rect {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rect {
+    #[spec(
+        #[warn]
+        requires: width > 0 && height > 0,
+        ensures: output.width == width,
+    )]
+    pub fn new(width: u32, height: u32) -> Self {
+        Rect { width, height }
+    }
+}
+
+#[anodized::spec(
+    requires: factor > 0,
+    ensures: output.width >= rect.width,
+)]
+pub fn scale(rect: &Rect, factor: u32) -> Rect {
+    Rect::new(rect.width * factor, rect.height * factor)
+}
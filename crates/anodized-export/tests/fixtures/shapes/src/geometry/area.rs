@@ -0,0 +1,11 @@
+use anodized::spec;
+
+use super::Rect;
+
+#[spec(
+    #[expensive]
+    ensures: *output == rect.width as u64 * rect.height as u64,
+)]
+pub fn area(rect: &Rect) -> u64 {
+    rect.width as u64 * rect.height as u64
+}
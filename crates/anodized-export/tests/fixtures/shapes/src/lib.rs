@@ -0,0 +1,41 @@
+use anodized::spec;
+
+mod geometry;
+
+#[spec(
+    requires: divisor != 0,
+    captures: dividend as original,
+    ensures: *output <= original,
+)]
+pub fn divide(dividend: u32, divisor: u32) -> u32 {
+    dividend / divisor
+}
+
+pub fn unchecked(x: u32) -> u32 {
+    x
+}
+
+#[spec]
+pub trait Counter {
+    #[spec(
+        #[label = "room to count"]
+        requires: self.count() < u32::MAX,
+        captures: self.count() as old_count,
+        ensures: |count| *count == old_count + 1,
+    )]
+    fn increment(&mut self) -> u32;
+
+    fn count(&self) -> u32;
+}
+
+pub mod units {
+    use anodized::spec;
+
+    #[spec(
+        #[cfg(debug_assertions)]
+        requires: meters.is_finite(),
+    )]
+    pub fn to_feet(meters: f64) -> f64 {
+        meters * 3.28084
+    }
+}
@@ -0,0 +1,31 @@
+use std::path::Path;
+use std::process::{Command, Output};
+
+fn export(fixture: &str) -> Output {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(fixture);
+    Command::new(env!("CARGO_BIN_EXE_anodized-export"))
+        .current_dir(dir)
+        .output()
+        .expect("failed to run anodized-export")
+}
+
+#[test]
+fn export_crate() {
+    let output = export("shapes");
+    assert!(output.status.success());
+
+    let expected = include_str!("fixtures/shapes.json");
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), expected);
+}
+
+#[test]
+fn invalid_spec() {
+    let output = export("invalid");
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "error: src/lib.rs:5:5: unknown spec keyword `invariant`\n"
+    );
+}
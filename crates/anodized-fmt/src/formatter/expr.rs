@@ -1,5 +1,5 @@
 use quote::ToTokens;
-use syn::{Expr, Pat};
+use syn::{Attribute, Expr, Pat};
 
 /// Format an expression using prettyplease
 /// This properly formats Rust expressions without excessive whitespace
@@ -48,6 +48,31 @@ pub fn format_pattern(pat: &Pat) -> String {
     remove_spaces_before_commas(&result)
 }
 
+/// Format an attribute on a spec argument (e.g. `#[cfg(test)]`)
+pub fn format_attribute(attr: &Attribute) -> String {
+    // prettyplease::unparse works on syn::File, so we attach the attribute
+    // to a unit struct and format that
+    let item: syn::Item = syn::parse_quote! {
+        #attr
+        struct DUMMY;
+    };
+
+    let file = syn::File {
+        shebang: None,
+        attrs: vec![],
+        items: vec![item],
+    };
+
+    let formatted = prettyplease::unparse(&file);
+
+    // Extract the attribute from "<attr>\nstruct DUMMY;"
+    formatted
+        .strip_suffix("struct DUMMY;\n")
+        .unwrap_or(attr.to_token_stream().to_string().as_str())
+        .trim()
+        .to_string()
+}
+
 /// Remove spaces before commas in formatted output
 /// This handles prettyplease's formatting of arrays/tuples like `[a , b , c]` -> `[a, b, c]`
 fn remove_spaces_before_commas(s: &str) -> String {
@@ -120,6 +145,15 @@ mod tests {
         assert!(!formatted.contains("* balance"));
     }
 
+    #[test]
+    fn test_format_attributes() {
+        let cfg: Attribute = parse_quote!(#[cfg(not(debug_assertions))]);
+        assert_eq!(format_attribute(&cfg), "#[cfg(not(debug_assertions))]");
+
+        let label: Attribute = parse_quote!(#[label = "must be positive"]);
+        assert_eq!(format_attribute(&label), r#"#[label = "must be positive"]"#);
+    }
+
     #[test]
     fn test_format_nested_deref() {
         let expr: Expr = parse_quote!(**ptr);
@@ -1,14 +1,13 @@
 use std::collections::HashMap;
 
 use anodized_core::annotate::syntax::{CaptureExpr, Captures, SpecArg, SpecArgValue};
-use syn::Meta;
 
 use crate::config::{Config, TrailingComma};
 
 mod expr;
 mod spec;
 
-use expr::{format_expr, format_pattern};
+use expr::{format_attribute, format_expr, format_pattern};
 
 pub use spec::format_spec_attribute;
 
@@ -114,11 +113,9 @@ impl<'a> Formatter<'a> {
 
     /// Format a SpecArg into the output.
     pub fn format_spec_arg(&mut self, arg: &SpecArg) {
-        // Add cfg attribute if present
-        if let Some(cfg_attr) = Self::find_cfg_attribute(&arg.attrs)
-            && let Ok(meta) = cfg_attr.parse_args::<Meta>()
-        {
-            self.write(&Self::format_cfg_attr(&meta));
+        // Add attributes (e.g. `#[cfg(...)]`), one per line
+        for attr in &arg.attrs {
+            self.write(&format_attribute(attr));
             self.newline();
             self.write_indent();
         }
@@ -204,15 +201,4 @@ impl<'a> Formatter<'a> {
 
         result
     }
-
-    /// Find a cfg attribute in the attribute list.
-    fn find_cfg_attribute(attrs: &[syn::Attribute]) -> Option<&syn::Attribute> {
-        attrs.iter().find(|attr| attr.path().is_ident("cfg"))
-    }
-
-    /// Format a cfg attribute.
-    fn format_cfg_attr(meta: &Meta) -> String {
-        let tokens = quote::quote!(#meta);
-        format!("#[cfg({})]", tokens)
-    }
 }
@@ -0,0 +1,8 @@
+use anodized::spec;
+
+#[spec(#[label = "enough funds"] requires: *balance >= amount,
+    #[cfg(not(debug_assertions))]   #[label="no overdraft"]
+        ensures: *balance <= initial,)]
+fn withdraw(balance: &mut u64, amount: u64, initial: u64) {
+    *balance -= amount;
+}
@@ -67,6 +67,17 @@ fn test_format_capture_patterns() {
     assert_eq!(formatted, expected);
 }
 
+#[test]
+fn test_format_attributes() {
+    let input = include_str!("fixtures/input/with_attributes.rs");
+    let expected = include_str!("fixtures/expected/with_attributes.rs");
+
+    let config = Config::default();
+    let formatted = format_file(input, &config).expect("Failed to format");
+
+    assert_eq!(formatted, expected);
+}
+
 #[test]
 fn test_format_with_comments() {
     let input = include_str!("fixtures/input/with_comments.rs");
@@ -0,0 +1,160 @@
+mod manifest;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use anodized_core::fuzz::fuzz_crate;
+use clap::Parser;
+
+use manifest::{GENERATED, fuzz_manifest, read_crate};
+
+/// The end of the first line of a generated target, which marks it as safe to remove.
+const GENERATED_TARGET: &str = "Generated by `anodized-fuzz`.";
+
+/// The driver that runs the targets with libFuzzer, or on their own.
+const DRIVER: &str = include_str!("../templates/driver.rs");
+
+#[derive(Parser)]
+#[command(
+    name = "anodized-fuzz",
+    version,
+    about = "Generate cargo-fuzz targets from the #[spec] annotations of a crate",
+    long_about = "Writes a cargo-fuzz package to the crate's `fuzz` directory, with a target for each public function that has a spec. A target decodes the arguments with `arbitrary`, skips inputs that violate the preconditions, calls the function, and checks the postconditions itself, so that a violated postcondition is a crash whatever the crate's runtime behavior. Run a target with `cargo fuzz run <target>`, or without libFuzzer with `cargo run --bin <target>` in the `fuzz` directory."
+)]
+struct Cli {
+    /// Crate directory (default: current directory)
+    #[arg(value_name = "PATH")]
+    path: Option<PathBuf>,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    // Keep the reported paths short when fuzzing the current directory.
+    let dir = match cli.path.as_deref() {
+        None => Path::new(""),
+        Some(path) if path == Path::new(".") => Path::new(""),
+        Some(path) => path,
+    };
+
+    match generate(dir) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Writes the fuzz package of the crate in the given directory.
+fn generate(dir: &Path) -> Result<(), String> {
+    let krate = read_crate(dir)?;
+    let fns = fuzz_crate(&krate.root, &krate.lib).map_err(|error| error.to_string())?;
+
+    let fuzz_dir = dir.join("fuzz");
+    let manifest_path = fuzz_dir.join("Cargo.toml");
+    if let Ok(existing) = fs::read_to_string(&manifest_path)
+        && existing.lines().next() != Some(GENERATED)
+    {
+        return Err(format!(
+            "{} exists and wasn't generated by anodized-fuzz",
+            manifest_path.display()
+        ));
+    }
+
+    let mut targets = Vec::new();
+    let mut skipped = 0;
+    for found in fns {
+        match found.target {
+            Ok(target) => {
+                let path = fuzz_dir
+                    .join("fuzz_targets")
+                    .join(format!("{}.rs", target.name));
+                write(&path, &format_target(target.file))?;
+                println!("{}: {}", path.display(), found.path);
+                targets.push(target.name);
+            }
+            Err(reason) => {
+                eprintln!(
+                    "warning: {}:{}: can't fuzz `{}`: {reason}",
+                    found.file.display(),
+                    found.line,
+                    found.path
+                );
+                skipped += 1;
+            }
+        }
+    }
+    if targets.is_empty() {
+        return Err(format!(
+            "no public function with a spec in {} can be fuzzed",
+            krate.package
+        ));
+    }
+    remove_stale_targets(&fuzz_dir.join("fuzz_targets"), &targets)?;
+    write(&fuzz_dir.join("driver.rs"), DRIVER)?;
+    write(&manifest_path, &fuzz_manifest(&krate, &targets))?;
+
+    eprintln!(
+        "Wrote {} fuzz target(s) to {}, skipped {skipped} function(s)",
+        targets.len(),
+        fuzz_dir.display()
+    );
+    Ok(())
+}
+
+/// Removes the targets of earlier runs that weren't generated again, e.g. for a function
+/// whose spec was removed, and leaves targets written by hand alone.
+fn remove_stale_targets(dir: &Path, targets: &[String]) -> Result<(), String> {
+    let entries =
+        fs::read_dir(dir).map_err(|error| format!("cannot read {}: {error}", dir.display()))?;
+    for entry in entries {
+        let path = entry
+            .map_err(|error| format!("cannot read {}: {error}", dir.display()))?
+            .path();
+        let stale = path.extension().is_some_and(|extension| extension == "rs")
+            && path
+                .file_stem()
+                .is_some_and(|stem| !targets.iter().any(|target| stem == target.as_str()));
+        let generated = || {
+            fs::read_to_string(&path).is_ok_and(|existing| {
+                existing
+                    .lines()
+                    .next()
+                    .is_some_and(|line| line.starts_with("//!") && line.ends_with(GENERATED_TARGET))
+            })
+        };
+        if stale && generated() {
+            fs::remove_file(&path)
+                .map_err(|error| format!("cannot remove {}: {error}", path.display()))?;
+            eprintln!("Removed stale target {}", path.display());
+        }
+    }
+    Ok(())
+}
+
+/// Formats a target, with a blank line between items as rustfmt keeps them.
+fn format_target(file: syn::File) -> String {
+    let header = syn::File {
+        items: Vec::new(),
+        ..file.clone()
+    };
+    std::iter::once(prettyplease::unparse(&header))
+        .chain(file.items.into_iter().map(|item| {
+            prettyplease::unparse(&syn::File {
+                shebang: None,
+                attrs: Vec::new(),
+                items: vec![item],
+            })
+        }))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn write(path: &Path, contents: &str) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|error| format!("cannot create {}: {error}", parent.display()))?;
+    }
+    fs::write(path, contents).map_err(|error| format!("cannot write {}: {error}", path.display()))
+}
@@ -0,0 +1,183 @@
+//! Reading the manifest of the fuzzed crate, and writing that of its fuzz package.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use toml::{Table, Value};
+
+/// The first line of a generated manifest, which marks it as safe to overwrite.
+pub const GENERATED: &str =
+    "# Generated by anodized-fuzz; changes are overwritten when it runs again.";
+
+/// The runtime feature of `anodized` that turns violations into crashes.
+const RUNTIME_FEATURE: &str = "runtime-check-and-panic";
+
+/// What a fuzz package needs to know about the fuzzed crate.
+pub struct Crate {
+    /// The name of the package, e.g. `shapes-geometry`.
+    pub package: String,
+    /// The name of the library in code, e.g. `shapes_geometry`.
+    pub lib: String,
+    /// The root file of the library.
+    pub root: PathBuf,
+    /// The crate's dependency on `anodized`, as seen from its fuzz package.
+    pub anodized: Table,
+}
+
+/// Reads the manifest of the crate in the given directory.
+pub fn read_crate(dir: &Path) -> Result<Crate, String> {
+    let manifest = read_manifest(&dir.join("Cargo.toml"))?;
+    let package = manifest
+        .get("package")
+        .and_then(|package| package.get("name"))
+        .and_then(Value::as_str)
+        .ok_or_else(|| format!("no package name in {}", dir.join("Cargo.toml").display()))?
+        .to_string();
+    let lib_table = manifest.get("lib");
+    let lib = lib_table
+        .and_then(|lib| lib.get("name"))
+        .and_then(Value::as_str)
+        .map_or_else(|| package.replace('-', "_"), str::to_string);
+    let root = dir.join(
+        lib_table
+            .and_then(|lib| lib.get("path"))
+            .and_then(Value::as_str)
+            .unwrap_or("src/lib.rs"),
+    );
+    if !root.is_file() {
+        return Err(format!(
+            "no library in {}; only libraries can be fuzzed",
+            dir.display()
+        ));
+    }
+
+    let dependency = manifest
+        .get("dependencies")
+        .and_then(|dependencies| dependencies.get("anodized"))
+        .ok_or_else(|| format!("{package} doesn't depend on `anodized`"))?;
+    let anodized = match dependency {
+        Value::Table(table) if table.get("workspace").and_then(Value::as_bool) == Some(true) => {
+            workspace_dependency(dir)?
+        }
+        dependency => rebase(dependency, Path::new(".."))
+            .ok_or_else(|| format!("invalid `anodized` dependency of {package}"))?,
+    };
+
+    Ok(Crate {
+        package,
+        lib,
+        root,
+        anodized,
+    })
+}
+
+/// The `anodized` dependency in the manifest of the workspace that the crate in the given
+/// directory inherits it from.
+fn workspace_dependency(dir: &Path) -> Result<Table, String> {
+    let dir = fs::canonicalize(dir.join("."))
+        .map_err(|error| format!("cannot read {}: {error}", dir.display()))?;
+    let mut relative = PathBuf::from("..");
+    for ancestor in dir.ancestors().skip(1) {
+        let path = ancestor.join("Cargo.toml");
+        if path.is_file() {
+            let manifest = read_manifest(&path)?;
+            if let Some(workspace) = manifest.get("workspace") {
+                return workspace
+                    .get("dependencies")
+                    .and_then(|dependencies| dependencies.get("anodized"))
+                    .and_then(|dependency| rebase(dependency, &relative))
+                    .ok_or_else(|| format!("no `anodized` dependency in {}", path.display()));
+            }
+        }
+        relative.push("..");
+    }
+    Err(format!(
+        "no workspace for the `anodized` dependency of {}",
+        dir.display()
+    ))
+}
+
+/// A dependency, given by its version or a table, as seen from a directory at `relative`
+/// from the manifest that declares it, without the settings that only apply there.
+fn rebase(dependency: &Value, relative: &Path) -> Option<Table> {
+    let mut dependency = match dependency {
+        Value::String(version) => {
+            Table::from_iter([("version".to_string(), version.as_str().into())])
+        }
+        Value::Table(table) => table.clone(),
+        _ => return None,
+    };
+    if let Some(Value::String(path)) = dependency.get_mut("path")
+        && Path::new(path).is_relative()
+    {
+        *path = relative.join(&*path).display().to_string();
+    }
+    dependency.remove("optional");
+    Some(dependency)
+}
+
+fn read_manifest(path: &Path) -> Result<Table, String> {
+    let text = fs::read_to_string(path)
+        .map_err(|error| format!("cannot read {}: {error}", path.display()))?;
+    text.parse()
+        .map_err(|error| format!("cannot parse {}: {error}", path.display()))
+}
+
+/// The manifest of the fuzz package of a crate, with a binary for each target.
+pub fn fuzz_manifest(krate: &Crate, targets: &[String]) -> String {
+    let mut anodized = krate.anodized.clone();
+    let mut features = match anodized.remove("features") {
+        Some(Value::Array(features)) => features,
+        _ => Vec::new(),
+    };
+    if !features
+        .iter()
+        .any(|feature| feature.as_str() == Some(RUNTIME_FEATURE))
+    {
+        features.push(RUNTIME_FEATURE.into());
+    }
+    anodized.insert("features".to_string(), Value::Array(features));
+
+    let mut manifest = format!(
+        r#"{GENERATED}
+
+[package]
+name = "{package}-fuzz"
+version = "0.0.0"
+publish = false
+edition = "2021"
+
+[package.metadata]
+cargo-fuzz = true
+
+[dependencies]
+arbitrary = "1"
+{package} = {{ path = ".." }}
+anodized = {anodized}
+
+[target.'cfg(fuzzing)'.dependencies]
+libfuzzer-sys = "0.4"
+
+[lints.rust]
+unexpected_cfgs = {{ level = "warn", check-cfg = ["cfg(fuzzing)"] }}
+"#,
+        package = krate.package,
+        anodized = Value::Table(anodized),
+    );
+    for target in targets {
+        let _ = write!(
+            manifest,
+            r#"
+[[bin]]
+name = "{target}"
+path = "fuzz_targets/{target}.rs"
+test = false
+doc = false
+bench = false
+"#
+        );
+    }
+    manifest.push_str("\n# Keep the fuzz package out of the crate's workspace.\n[workspace]\n");
+    manifest
+}
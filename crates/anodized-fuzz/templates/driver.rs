@@ -0,0 +1,100 @@
+//! Runs a fuzz target under libFuzzer with `cargo fuzz`, or on its own with `cargo run`.
+//! Generated by `anodized-fuzz`.
+//!
+//! On its own, a target runs on each file given as an argument, and on each file in a
+//! directory given as an argument, e.g. a corpus or the artifacts of `cargo fuzz`. Without
+//! arguments, it runs on `ANODIZED_FUZZ_RUNS` random inputs (default: 10000), generated
+//! from the seed `ANODIZED_FUZZ_SEED` (default: the current time). If a random input
+//! crashes the target, it is saved to a file for running again.
+#![cfg_attr(fuzzing, allow(dead_code, unused_imports))]
+
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{env, fs, panic};
+
+/// Defines the entry point of a fuzz target that runs the given function on each input.
+macro_rules! fuzz_main {
+    ($run:path) => {
+        #[cfg(fuzzing)]
+        ::libfuzzer_sys::fuzz_target!(|data: &[u8]| $run(data));
+
+        #[cfg(not(fuzzing))]
+        fn main() {
+            driver::run_locally($run);
+        }
+    };
+}
+pub(crate) use fuzz_main;
+
+/// Runs a fuzz target without libFuzzer, on the inputs given as arguments or random ones.
+pub fn run_locally(run: fn(&[u8])) {
+    let paths: Vec<PathBuf> = env::args_os().skip(1).map(PathBuf::from).collect();
+    if paths.is_empty() {
+        run_random(run);
+    }
+    for path in paths {
+        run_path(run, &path);
+    }
+}
+
+/// Runs a fuzz target on a file, or on each file in a directory.
+fn run_path(run: fn(&[u8]), path: &Path) {
+    if path.is_dir() {
+        let entries = fs::read_dir(path)
+            .unwrap_or_else(|error| panic!("cannot read {}: {error}", path.display()));
+        let mut paths: Vec<PathBuf> = entries
+            .map(|entry| entry.expect("cannot read directory entry").path())
+            .collect();
+        paths.sort();
+        for path in paths {
+            run_path(run, &path);
+        }
+    } else {
+        let data = fs::read(path)
+            .unwrap_or_else(|error| panic!("cannot read {}: {error}", path.display()));
+        eprintln!("Running {}", path.display());
+        run(&data);
+    }
+}
+
+/// Runs a fuzz target on random inputs of up to 256 bytes.
+fn run_random(run: fn(&[u8])) {
+    let runs = env_number("ANODIZED_FUZZ_RUNS").unwrap_or(10_000);
+    let seed = env_number("ANODIZED_FUZZ_SEED").unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_nanos() as u64)
+    });
+    // xorshift64, whose state must not be zero.
+    let mut state = seed | 1;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    let mut data = Vec::new();
+    for index in 0..runs {
+        let len = (next() % 257) as usize;
+        data.clear();
+        data.extend((0..len).map(|_| next() as u8));
+        if let Err(payload) = panic::catch_unwind(|| run(&data)) {
+            let path = format!("crash-{seed}-{index}");
+            match fs::write(&path, &data) {
+                Ok(()) => eprintln!("Crashed on random input {index}, saved to {path}"),
+                Err(error) => eprintln!("Crashed on random input {index}: {error}"),
+            }
+            panic::resume_unwind(payload);
+        }
+    }
+    eprintln!("Ran {runs} random inputs with ANODIZED_FUZZ_SEED={seed}");
+}
+
+fn env_number(name: &str) -> Option<u64> {
+    let value = env::var(name).ok()?;
+    Some(
+        value
+            .parse()
+            .unwrap_or_else(|_| panic!("{name} must be a number, not `{value}`")),
+    )
+}
@@ -0,0 +1,34 @@
+//! Fuzzes `shapes::geometry::push` against its spec. Generated by `anodized-fuzz`.
+#![cfg_attr(fuzzing, no_main)]
+#![allow(
+    clippy::let_unit_value,
+    clippy::nonminimal_bool,
+    clippy::redundant_closure_call
+)]
+
+#[path = "../driver.rs"]
+mod driver;
+
+#[allow(unused_imports)]
+use shapes::geometry::*;
+
+fn run(data: &[u8]) {
+    let Ok((mut stack, value)) = <(
+        Vec<u8>,
+        u8,
+    ) as ::arbitrary::Arbitrary>::arbitrary_take_rest(
+        ::arbitrary::Unstructured::new(data),
+    ) else {
+        return;
+    };
+    let stack: &mut Vec<u8> = &mut stack;
+    if !(stack.len() <= 8) {
+        return;
+    }
+    let __anodized_output: () = shapes::geometry::push(stack, value);
+    if !(stack.len() <= 8) {
+        ::core::panic!("{}", "Post-invariant failed: stack . len () <= 8");
+    }
+}
+
+driver::fuzz_main!(run);
@@ -0,0 +1,33 @@
+use anodized::spec;
+
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Point {
+    #[spec(ensures: *output >= 0)]
+    pub fn norm1(&self) -> i64 {
+        (self.x as i64).abs() + (self.y as i64).abs()
+    }
+}
+
+#[spec(
+    requires: !name.is_empty(),
+    ensures: output.len() == name.len() + 1,
+)]
+pub fn label(name: &str) -> String {
+    format!("{name}!")
+}
+
+#[spec(
+    maintains: stack.len() <= 8,
+)]
+pub fn push(stack: &mut Vec<u8>, value: u8) {
+    stack.push(value);
+}
+
+#[spec(requires: !items.is_empty())]
+pub fn first<T: Copy>(items: &[T]) -> T {
+    items[0]
+}
@@ -0,0 +1,6 @@
+use anodized::spec;
+
+#[spec(requires: x > 0)]
+pub fn decrement(x: u32) -> u32 {
+    x - 1
+}
@@ -0,0 +1,21 @@
+use anodized::spec;
+
+pub mod geometry;
+mod internal;
+
+#[spec(
+    requires: divisor != 0,
+    ensures: *output <= dividend,
+)]
+pub fn divide(dividend: u32, divisor: u32) -> u32 {
+    dividend / divisor
+}
+
+#[spec(
+    #[warn]
+    ensures: a.min(b) <= *output && *output <= a.max(b),
+)]
+pub fn midpoint(a: u8, b: u8) -> u8 {
+    // Loses the remainders of both halves.
+    a / 2 + b / 2
+}
@@ -0,0 +1,175 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// Copy the fixture crate to a fresh directory, with a manifest that depends on `anodized`
+/// as given.
+fn copy_fixture(test: &str, anodized: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(test);
+    let _ = fs::remove_dir_all(&dir);
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/shapes/src");
+    fs::create_dir_all(dir.join("src")).unwrap();
+    for entry in fs::read_dir(fixture).unwrap() {
+        let path = entry.unwrap().path();
+        fs::copy(&path, dir.join("src").join(path.file_name().unwrap())).unwrap();
+    }
+    fs::write(
+        dir.join("Cargo.toml"),
+        format!(
+            "[package]\nname = \"shapes\"\nversion = \"0.1.0\"\nedition = \"2024\"\n\n\
+             [dependencies]\nanodized = {anodized}\n"
+        ),
+    )
+    .unwrap();
+    dir
+}
+
+fn fuzz(dir: &Path) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_anodized-fuzz"))
+        .current_dir(dir)
+        .output()
+        .expect("failed to run anodized-fuzz")
+}
+
+#[test]
+fn generate_targets() {
+    let dir = copy_fixture("generate_targets", r#"{ path = "../anodized" }"#);
+    let output = fuzz(&dir);
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "\
+fuzz/fuzz_targets/geometry_label.rs: shapes::geometry::label
+fuzz/fuzz_targets/geometry_push.rs: shapes::geometry::push
+fuzz/fuzz_targets/divide.rs: shapes::divide
+fuzz/fuzz_targets/midpoint.rs: shapes::midpoint
+"
+    );
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "\
+warning: src/geometry.rs:30: can't fuzz `shapes::geometry::first`: it's generic
+Wrote 4 fuzz target(s) to fuzz, skipped 1 function(s)
+"
+    );
+
+    let fuzz_dir = dir.join("fuzz");
+    assert_eq!(
+        fs::read_to_string(fuzz_dir.join("Cargo.toml")).unwrap(),
+        include_str!("fixtures/expected/fuzz.toml")
+    );
+    assert_eq!(
+        fs::read_to_string(fuzz_dir.join("fuzz_targets/geometry_push.rs")).unwrap(),
+        include_str!("fixtures/expected/geometry_push.rs")
+    );
+    assert_eq!(
+        fs::read_to_string(fuzz_dir.join("driver.rs")).unwrap(),
+        include_str!("../templates/driver.rs")
+    );
+
+    // Running again overwrites the generated files.
+    assert!(fuzz(&dir).status.success());
+}
+
+#[test]
+fn remove_stale_targets() {
+    let dir = copy_fixture("remove_stale_targets", r#"{ path = "../anodized" }"#);
+    assert!(fuzz(&dir).status.success());
+
+    // A target for a function that has since lost its spec, and one written by hand.
+    let targets = dir.join("fuzz/fuzz_targets");
+    fs::rename(targets.join("divide.rs"), targets.join("scale.rs")).unwrap();
+    fs::write(
+        targets.join("custom.rs"),
+        "//! Fuzzes `shapes::divide` by hand.\n",
+    )
+    .unwrap();
+    let output = fuzz(&dir);
+
+    assert!(output.status.success());
+    assert!(
+        String::from_utf8(output.stderr)
+            .unwrap()
+            .contains("Removed stale target fuzz/fuzz_targets/scale.rs\n")
+    );
+    assert!(!targets.join("scale.rs").exists());
+    assert!(targets.join("divide.rs").is_file());
+    assert_eq!(
+        fs::read_to_string(targets.join("custom.rs")).unwrap(),
+        "//! Fuzzes `shapes::divide` by hand.\n"
+    );
+}
+
+#[test]
+fn keep_existing_package() {
+    let dir = copy_fixture("keep_existing_package", r#""0.3""#);
+    fs::create_dir_all(dir.join("fuzz")).unwrap();
+    fs::write(dir.join("fuzz/Cargo.toml"), "[package]\n").unwrap();
+    let output = fuzz(&dir);
+
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "error: fuzz/Cargo.toml exists and wasn't generated by anodized-fuzz\n"
+    );
+    assert_eq!(
+        fs::read_to_string(dir.join("fuzz/Cargo.toml")).unwrap(),
+        "[package]\n"
+    );
+}
+
+#[test]
+fn missing_dependency() {
+    let dir = copy_fixture("missing_dependency", r#""0.3""#);
+    fs::write(
+        dir.join("Cargo.toml"),
+        "[package]\nname = \"shapes\"\nversion = \"0.1.0\"\n",
+    )
+    .unwrap();
+    let output = fuzz(&dir);
+
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "error: shapes doesn't depend on `anodized`\n"
+    );
+}
+
+/// A target checks the spec itself, so it crashes on a violated `#[warn]` postcondition,
+/// even if the crate doesn't check its specs at all.
+#[test]
+fn warned_postcondition_crashes() {
+    let anodized = Path::new(env!("CARGO_MANIFEST_DIR")).join("../anodized");
+    let dir = copy_fixture(
+        "warned_postcondition_crashes",
+        &format!("{{ path = {:?} }}", anodized.display().to_string()),
+    );
+    let mut manifest = fs::read_to_string(dir.join("Cargo.toml")).unwrap();
+    manifest.push_str("\n[package.metadata.anodized]\nruntime = \"no-check\"\n");
+    fs::write(dir.join("Cargo.toml"), manifest).unwrap();
+    assert!(fuzz(&dir).status.success());
+
+    // Use the versions of the workspace's dependencies, which are already downloaded.
+    let fuzz_dir = dir.join("fuzz");
+    let lock = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../Cargo.lock");
+    if lock.is_file() {
+        fs::copy(lock, fuzz_dir.join("Cargo.lock")).unwrap();
+    }
+    let output = Command::new(env!("CARGO"))
+        .args(["run", "--quiet", "--bin", "midpoint"])
+        .current_dir(&fuzz_dir)
+        .env("CARGO_TARGET_DIR", fuzz_dir.join("target"))
+        .env("ANODIZED_FUZZ_SEED", "1")
+        .env("RUST_BACKTRACE", "0")
+        .output()
+        .unwrap();
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(!output.status.success(), "{stderr}");
+    assert!(
+        stderr.contains("Postcondition failed: | output | a . min (b) <= * output"),
+        "{stderr}"
+    );
+    assert!(stderr.contains("Crashed on random input"), "{stderr}");
+}
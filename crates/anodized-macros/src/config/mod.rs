@@ -0,0 +1,312 @@
+//! Selection of the runtime behavior, and other code generation, for the crate being compiled.
+//!
+//! A crate may choose its own runtime behavior in its manifest. This takes
+//! precedence over the `runtime-*` features, which Cargo unifies across the
+//! whole dependency graph, and `#[spec]` warns if it overrides a feature that selects
+//! another behavior:
+//!
+//! ```toml
+//! [package.metadata.anodized]
+//! runtime = "check-and-panic"
+//! # Or, separately for builds with and without `debug_assertions`:
+//! # runtime = { debug = "check-and-panic", release = "no-check" }
+//!
+//! # Settings for specific runtime behaviors, all optional:
+//! log-level = "warn"
+//! trap-fallback = "print"
+//! trace-spans = true
+//! # Whether checks consult the switches of `anodized::runtime`, defaults to `false`:
+//! runtime-control = true
+//! # Whether to count evaluations and failures in `anodized::stats`, and to time them:
+//! stats = true
+//! stats-timing = true
+//! # Whether to drop the checks of conditions marked `#[expensive]`:
+//! skip-expensive = true
+//! # A function to report violations with, instead of `eprintln!`, e.g. in `no_std` crates:
+//! print-fn = "crate::report"
+//!
+//! # Whether to generate a Kani proof harness for each spec'd function, see `anodized_core::kani`:
+//! kani-harnesses = true
+//! # Whether to add Kani contract attributes to each spec'd function:
+//! kani-contracts = true
+//! # Whether to add Creusot contract attributes to each spec'd function:
+//! creusot-contracts = true
+//! # Whether to add Prusti contract attributes to each spec'd function:
+//! prusti-contracts = true
+//! # Whether to define each spec'd function in Verus syntax for Verus builds:
+//! verus-functions = true
+//! # Whether to add a Flux signature for the linear integer preconditions of each spec'd function:
+//! flux-signatures = true
+//! ```
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use anodized_core::{
+    Mode,
+    instrument::{BuiltinBackend, LogLevel, Settings, TrapFallback},
+};
+use toml::{Table, Value};
+
+/// What `#[spec]` generates for the crate being compiled.
+pub struct Config {
+    /// The backends that instrument functions with runtime checks.
+    pub runtime: Runtime,
+    /// Whether to generate a Kani proof harness next to each function with a spec.
+    pub kani_harnesses: bool,
+    /// Whether to add Kani contract attributes to each function with a spec.
+    pub kani_contracts: bool,
+    /// Whether to add Creusot contract attributes to each function with a spec.
+    pub creusot_contracts: bool,
+    /// Whether to add Prusti contract attributes to each function with a spec.
+    pub prusti_contracts: bool,
+    /// Whether to define each function with a spec in Verus syntax for Verus builds.
+    pub verus_functions: bool,
+    /// Whether to add a Flux signature to each function with a spec.
+    pub flux_signatures: bool,
+    /// Problems with the configuration that don't prevent expansion.
+    pub warnings: Vec<String>,
+}
+
+/// The backends used to instrument the crate being compiled.
+pub enum Runtime {
+    /// The same backend in every build.
+    Uniform(BuiltinBackend),
+    /// Different backends for builds with and without `debug_assertions`.
+    PerBuild {
+        debug: BuiltinBackend,
+        release: BuiltinBackend,
+    },
+}
+
+/// The settings that apply when a crate's manifest doesn't specify them.
+#[derive(Clone)]
+pub struct Defaults {
+    pub runtime: Option<&'static str>,
+    pub settings: Settings,
+}
+
+/// The names of the runtime behaviors, as accepted by `runtime = "..."`.
+const RUNTIME_NAMES: &str =
+    "check-and-panic, check-and-print, check-and-log, check-and-trace, check-and-trap, no-check";
+
+/// Select the runtime and other code generation for the crate being compiled.
+pub fn load_config(defaults: &Defaults) -> Result<Config, String> {
+    let Some(manifest_dir) = std::env::var_os("CARGO_MANIFEST_DIR") else {
+        return config_from_defaults(defaults);
+    };
+    let manifest_path = Path::new(&manifest_dir).join("Cargo.toml");
+    let Some(metadata) = load_metadata(&manifest_path)? else {
+        return config_from_defaults(defaults);
+    };
+    config_from_metadata(&metadata, defaults).map_err(|err| {
+        format!(
+            "{err} in `[package.metadata.anodized]` of {}",
+            manifest_path.display()
+        )
+    })
+}
+
+fn config_from_defaults(defaults: &Defaults) -> Result<Config, String> {
+    Ok(Config {
+        runtime: runtime_from_defaults(defaults)?,
+        kani_harnesses: false,
+        kani_contracts: false,
+        creusot_contracts: false,
+        prusti_contracts: false,
+        verus_functions: false,
+        flux_signatures: false,
+        warnings: Vec::new(),
+    })
+}
+
+fn config_from_metadata(metadata: &Table, defaults: &Defaults) -> Result<Config, String> {
+    let flag = |key: &str| match metadata.get(key) {
+        None => Ok(false),
+        Some(value) => value
+            .as_bool()
+            .ok_or_else(|| format!("`{key}` must be a boolean")),
+    };
+    Ok(Config {
+        runtime: runtime_from_metadata(metadata, defaults)?,
+        kani_harnesses: flag("kani-harnesses")?,
+        kani_contracts: flag("kani-contracts")?,
+        creusot_contracts: flag("creusot-contracts")?,
+        prusti_contracts: flag("prusti-contracts")?,
+        verus_functions: flag("verus-functions")?,
+        flux_signatures: flag("flux-signatures")?,
+        warnings: overridden_feature(metadata, defaults).into_iter().collect(),
+    })
+}
+
+/// A warning if `runtime` in the metadata overrides the behavior selected by a feature.
+fn overridden_feature(metadata: &Table, defaults: &Defaults) -> Option<String> {
+    let feature = defaults.runtime?;
+    let names = match metadata.get("runtime")? {
+        Value::String(name) => vec![name.as_str()],
+        Value::Table(per_build) => per_build.values().filter_map(Value::as_str).collect(),
+        _ => return None,
+    };
+    names.iter().any(|name| *name != feature).then(|| {
+        format!(
+            "`runtime` in `[package.metadata.anodized]` overrides the `runtime-{feature}` \
+             feature of `anodized`"
+        )
+    })
+}
+
+fn runtime_from_defaults(defaults: &Defaults) -> Result<Runtime, String> {
+    match defaults.runtime {
+        Some(name) => Ok(Runtime::Uniform(backend_from_name(
+            name,
+            defaults.settings.clone(),
+        )?)),
+        None => Err(format!(
+            "a runtime behavior must be selected, either with a `runtime-*` feature \
+             or with `runtime = \"...\"` in `[package.metadata.anodized]`; \
+             available behaviors: {RUNTIME_NAMES}"
+        )),
+    }
+}
+
+fn runtime_from_metadata(metadata: &Table, defaults: &Defaults) -> Result<Runtime, String> {
+    let mut defaults = defaults.clone();
+    let settings = &mut defaults.settings;
+
+    for (key, value) in metadata {
+        match key.as_str() {
+            "runtime" | "kani-harnesses" | "kani-contracts" | "creusot-contracts"
+            | "prusti-contracts" | "verus-functions" | "flux-signatures" => {}
+            "log-level" => {
+                settings.log_level = value
+                    .as_str()
+                    .and_then(LogLevel::from_name)
+                    .ok_or("`log-level` must be one of: error, warn, info, debug, trace")?;
+            }
+            "trap-fallback" => {
+                settings.trap_fallback = value
+                    .as_str()
+                    .and_then(TrapFallback::from_name)
+                    .ok_or("`trap-fallback` must be one of: panic, print")?;
+            }
+            "trace-spans" => {
+                settings.trace_spans = value.as_bool().ok_or("`trace-spans` must be a boolean")?;
+            }
+            "stats" => {
+                settings.stats = value.as_bool().ok_or("`stats` must be a boolean")?;
+            }
+            "stats-timing" => {
+                settings.stats_timing =
+                    value.as_bool().ok_or("`stats-timing` must be a boolean")?;
+            }
+            "skip-expensive" => {
+                settings.skip_expensive = value
+                    .as_bool()
+                    .ok_or("`skip-expensive` must be a boolean")?;
+            }
+            "print-fn" => {
+                settings.print_fn = Some(
+                    value
+                        .as_str()
+                        .and_then(|path| syn::parse_str(path).ok())
+                        .ok_or(
+                            "`print-fn` must be the path of a function, e.g. \"crate::report\"",
+                        )?,
+                );
+            }
+            "runtime-control" => {
+                settings.runtime_control = value
+                    .as_bool()
+                    .ok_or("`runtime-control` must be a boolean")?;
+            }
+            _ => return Err(format!("unknown key `{key}`")),
+        }
+    }
+
+    let settings = &defaults.settings;
+    match metadata.get("runtime") {
+        None => runtime_from_defaults(&defaults),
+        Some(Value::String(name)) => {
+            Ok(Runtime::Uniform(backend_from_name(name, settings.clone())?))
+        }
+        Some(Value::Table(per_build)) => {
+            if let Some(key) = per_build
+                .keys()
+                .find(|key| *key != "debug" && *key != "release")
+            {
+                return Err(format!("unknown key `runtime.{key}`"));
+            }
+            let backend_for = |build: &str| match per_build.get(build) {
+                Some(Value::String(name)) => backend_from_name(name, settings.clone()),
+                Some(_) => Err(format!("`runtime.{build}` must be a string")),
+                None => Err(format!("`runtime.{build}` is missing")),
+            };
+            Ok(Runtime::PerBuild {
+                debug: backend_for("debug")?,
+                release: backend_for("release")?,
+            })
+        }
+        Some(_) => {
+            Err("`runtime` must be a string or a table with `debug` and `release` keys".to_string())
+        }
+    }
+}
+
+fn backend_from_name(name: &str, settings: Settings) -> Result<BuiltinBackend, String> {
+    let mode = match name {
+        "check-and-panic" => Mode::Panic,
+        "check-and-print" => Mode::Print,
+        "check-and-log" => Mode::Log,
+        "check-and-trace" => Mode::Trace,
+        "check-and-trap" => Mode::Trap,
+        "no-check" => Mode::NoCheck,
+        _ => {
+            return Err(format!(
+                "unknown runtime behavior `{name}`; available behaviors: {RUNTIME_NAMES}"
+            ));
+        }
+    };
+    Ok(BuiltinBackend::new(mode, settings))
+}
+
+thread_local! {
+    /// Parsed `[package.metadata.anodized]` tables, by manifest path.
+    ///
+    /// The proc macro may expand many specs of the same crate, so each manifest is read once.
+    static METADATA: RefCell<HashMap<PathBuf, Option<Table>>> = RefCell::new(HashMap::new());
+}
+
+fn load_metadata(manifest_path: &Path) -> Result<Option<Table>, String> {
+    if let Some(metadata) = METADATA.with_borrow(|cache| cache.get(manifest_path).cloned()) {
+        return Ok(metadata);
+    }
+
+    let manifest = std::fs::read_to_string(manifest_path)
+        .map_err(|err| format!("cannot read {}: {err}", manifest_path.display()))?;
+    let manifest: Table = manifest
+        .parse()
+        .map_err(|err| format!("cannot parse {}: {err}", manifest_path.display()))?;
+    let metadata = match manifest
+        .get("package")
+        .and_then(|package| package.get("metadata"))
+        .and_then(|metadata| metadata.get("anodized"))
+    {
+        None => None,
+        Some(Value::Table(metadata)) => Some(metadata.clone()),
+        Some(_) => {
+            return Err(format!(
+                "`package.metadata.anodized` must be a table in {}",
+                manifest_path.display()
+            ));
+        }
+    };
+
+    METADATA.with_borrow_mut(|cache| cache.insert(manifest_path.to_path_buf(), metadata.clone()));
+    Ok(metadata)
+}
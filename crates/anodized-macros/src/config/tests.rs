@@ -0,0 +1,305 @@
+use super::*;
+
+use anodized_core::instrument::fns::instrument_fn;
+use quote::ToTokens;
+use syn::parse_quote;
+
+const DEFAULTS: Defaults = Defaults {
+    runtime: Some("check-and-print"),
+    settings: Settings::DEFAULT,
+};
+
+fn parse_metadata(source: &str) -> Table {
+    source.parse().unwrap()
+}
+
+/// Instrument a small fn to observe which backend was selected.
+fn instrumented(backend: &BuiltinBackend) -> String {
+    instrument_fn(
+        backend,
+        parse_quote! { requires: CONDITION },
+        parse_quote! { fn f() {} },
+    )
+    .unwrap()
+    .to_token_stream()
+    .to_string()
+}
+
+fn uniform(runtime: Runtime) -> String {
+    match runtime {
+        Runtime::Uniform(backend) => instrumented(&backend),
+        Runtime::PerBuild { .. } => panic!("expected a uniform runtime"),
+    }
+}
+
+#[test]
+fn empty_metadata_uses_defaults() {
+    let runtime = runtime_from_metadata(&parse_metadata(""), &DEFAULTS).unwrap();
+    assert!(uniform(runtime).contains("eprintln !"));
+}
+
+#[test]
+fn no_default_runtime() {
+    let defaults = Defaults {
+        runtime: None,
+        ..DEFAULTS
+    };
+    let Err(err) = runtime_from_metadata(&parse_metadata(""), &defaults) else {
+        panic!("expected an error");
+    };
+    assert!(err.starts_with("a runtime behavior must be selected"));
+}
+
+#[test]
+fn runtime_overrides_default() {
+    let metadata = parse_metadata(r#"runtime = "check-and-panic""#);
+    let runtime = runtime_from_metadata(&metadata, &DEFAULTS).unwrap();
+    assert!(uniform(runtime).contains("assert !"));
+}
+
+#[test]
+fn runtime_settings() {
+    let metadata = parse_metadata(
+        r#"
+        runtime = "check-and-log"
+        log-level = "warn"
+        "#,
+    );
+    let runtime = runtime_from_metadata(&metadata, &DEFAULTS).unwrap();
+    assert!(uniform(runtime).contains(":: log :: Level :: Warn"));
+
+    let metadata = parse_metadata(
+        r#"
+        runtime = "check-and-trap"
+        trap-fallback = "print"
+        "#,
+    );
+    let runtime = runtime_from_metadata(&metadata, &DEFAULTS).unwrap();
+    assert!(uniform(runtime).contains("trap_or_print"));
+
+    let metadata = parse_metadata(
+        r#"
+        runtime = "check-and-trace"
+        trace-spans = true
+        "#,
+    );
+    let runtime = runtime_from_metadata(&metadata, &DEFAULTS).unwrap();
+    assert!(uniform(runtime).contains("info_span !"));
+}
+
+#[test]
+fn runtime_control_setting() {
+    let runtime = runtime_from_metadata(&parse_metadata(""), &DEFAULTS).unwrap();
+    assert!(!uniform(runtime).contains("checks_enabled"));
+
+    let metadata = parse_metadata("runtime-control = true");
+    let runtime = runtime_from_metadata(&metadata, &DEFAULTS).unwrap();
+    assert!(uniform(runtime).contains("checks_enabled"));
+}
+
+#[test]
+fn stats_settings() {
+    let metadata = parse_metadata("stats = true");
+    let runtime = runtime_from_metadata(&metadata, &DEFAULTS).unwrap();
+    assert!(uniform(runtime).contains("COUNTER . record ("));
+
+    let metadata = parse_metadata(
+        r#"
+        stats = true
+        stats-timing = true
+        "#,
+    );
+    let runtime = runtime_from_metadata(&metadata, &DEFAULTS).unwrap();
+    assert!(uniform(runtime).contains("COUNTER . record_timed ("));
+}
+
+#[test]
+fn skip_expensive_setting() {
+    let instrumented_with = |metadata| {
+        let runtime = runtime_from_metadata(&parse_metadata(metadata), &DEFAULTS).unwrap();
+        let Runtime::Uniform(backend) = runtime else {
+            panic!("expected a uniform runtime");
+        };
+        instrument_fn(
+            &backend,
+            parse_quote! { #[expensive] requires: CONDITION },
+            parse_quote! { fn f() {} },
+        )
+        .unwrap()
+        .to_token_stream()
+        .to_string()
+    };
+    assert!(instrumented_with("").contains("eprintln !"));
+    assert!(instrumented_with("skip-expensive = true").contains("if false"));
+}
+
+#[test]
+fn print_fn_setting() {
+    let metadata = parse_metadata(r#"print-fn = "crate::report""#);
+    let runtime = runtime_from_metadata(&metadata, &DEFAULTS).unwrap();
+    assert!(uniform(runtime).contains("crate :: report (format_args !"));
+
+    let metadata = parse_metadata(r#"print-fn = "not a path""#);
+    let Err(err) = runtime_from_metadata(&metadata, &DEFAULTS) else {
+        panic!("expected an error");
+    };
+    assert!(err.contains("`print-fn` must be the path of a function"));
+}
+
+#[test]
+fn kani_harnesses_setting() {
+    let config = config_from_metadata(&parse_metadata(""), &DEFAULTS).unwrap();
+    assert!(!config.kani_harnesses);
+
+    let metadata = parse_metadata("kani-harnesses = true");
+    let config = config_from_metadata(&metadata, &DEFAULTS).unwrap();
+    assert!(config.kani_harnesses);
+    assert_eq!(
+        uniform(config.runtime),
+        instrumented(&BuiltinBackend::CHECK_AND_PRINT)
+    );
+
+    let metadata = parse_metadata(r#"kani-harnesses = "yes""#);
+    let Err(err) = config_from_metadata(&metadata, &DEFAULTS) else {
+        panic!("expected an error");
+    };
+    assert_eq!(err, "`kani-harnesses` must be a boolean");
+}
+
+#[test]
+fn kani_contracts_setting() {
+    let config = config_from_metadata(&parse_metadata(""), &DEFAULTS).unwrap();
+    assert!(!config.kani_contracts);
+
+    let metadata = parse_metadata("kani-contracts = true");
+    let config = config_from_metadata(&metadata, &DEFAULTS).unwrap();
+    assert!(config.kani_contracts);
+    assert!(!config.kani_harnesses);
+}
+
+#[test]
+fn creusot_contracts_setting() {
+    let config = config_from_metadata(&parse_metadata(""), &DEFAULTS).unwrap();
+    assert!(!config.creusot_contracts);
+
+    let metadata = parse_metadata("creusot-contracts = true");
+    let config = config_from_metadata(&metadata, &DEFAULTS).unwrap();
+    assert!(config.creusot_contracts);
+}
+
+#[test]
+fn prusti_contracts_setting() {
+    let config = config_from_metadata(&parse_metadata(""), &DEFAULTS).unwrap();
+    assert!(!config.prusti_contracts);
+
+    let metadata = parse_metadata("prusti-contracts = true");
+    let config = config_from_metadata(&metadata, &DEFAULTS).unwrap();
+    assert!(config.prusti_contracts);
+}
+
+#[test]
+fn verus_functions_setting() {
+    let config = config_from_metadata(&parse_metadata(""), &DEFAULTS).unwrap();
+    assert!(!config.verus_functions);
+
+    let metadata = parse_metadata("verus-functions = true");
+    let config = config_from_metadata(&metadata, &DEFAULTS).unwrap();
+    assert!(config.verus_functions);
+}
+
+#[test]
+fn flux_signatures_setting() {
+    let config = config_from_metadata(&parse_metadata(""), &DEFAULTS).unwrap();
+    assert!(!config.flux_signatures);
+
+    let metadata = parse_metadata("flux-signatures = true");
+    let config = config_from_metadata(&metadata, &DEFAULTS).unwrap();
+    assert!(config.flux_signatures);
+}
+
+#[test]
+fn per_build_runtime() {
+    let metadata =
+        parse_metadata(r#"runtime = { debug = "check-and-panic", release = "no-check" }"#);
+    let Runtime::PerBuild { debug, release } = runtime_from_metadata(&metadata, &DEFAULTS).unwrap()
+    else {
+        panic!("expected a per-build runtime");
+    };
+    assert!(instrumented(&debug).contains("; assert !"));
+    assert!(instrumented(&release).contains("; if false"));
+}
+
+#[test]
+fn per_build_runtime_missing_build() {
+    let metadata = parse_metadata(r#"runtime = { debug = "check-and-panic" }"#);
+    let Err(err) = runtime_from_metadata(&metadata, &DEFAULTS) else {
+        panic!("expected an error");
+    };
+    assert_eq!(err, "`runtime.release` is missing");
+}
+
+#[test]
+fn unknown_runtime() {
+    let metadata = parse_metadata(r#"runtime = "check-and-explode""#);
+    let Err(err) = runtime_from_metadata(&metadata, &DEFAULTS) else {
+        panic!("expected an error");
+    };
+    assert!(err.starts_with("unknown runtime behavior `check-and-explode`"));
+}
+
+#[test]
+fn unknown_key() {
+    let metadata = parse_metadata(r#"runtim = "no-check""#);
+    let Err(err) = runtime_from_metadata(&metadata, &DEFAULTS) else {
+        panic!("expected an error");
+    };
+    assert_eq!(err, "unknown key `runtim`");
+}
+
+#[test]
+fn invalid_setting() {
+    let metadata = parse_metadata(r#"log-level = "loud""#);
+    let Err(err) = runtime_from_metadata(&metadata, &DEFAULTS) else {
+        panic!("expected an error");
+    };
+    assert_eq!(
+        err,
+        "`log-level` must be one of: error, warn, info, debug, trace"
+    );
+}
+
+#[test]
+fn metadata_overriding_feature_warns() {
+    let defaults = Defaults {
+        runtime: Some("check-and-panic"),
+        ..DEFAULTS
+    };
+    let warnings = |metadata| {
+        config_from_metadata(&parse_metadata(metadata), &defaults)
+            .unwrap()
+            .warnings
+    };
+    assert_eq!(
+        warnings(r#"runtime = "check-and-print""#),
+        ["`runtime` in `[package.metadata.anodized]` overrides the \
+          `runtime-check-and-panic` feature of `anodized`"]
+    );
+    assert_eq!(
+        warnings(r#"runtime = { debug = "check-and-panic", release = "no-check" }"#).len(),
+        1
+    );
+    assert!(warnings(r#"runtime = "check-and-panic""#).is_empty());
+    assert!(warnings("stats = true").is_empty());
+
+    let no_feature = Defaults {
+        runtime: None,
+        ..DEFAULTS
+    };
+    let metadata = parse_metadata(r#"runtime = "check-and-print""#);
+    assert!(
+        config_from_metadata(&metadata, &no_feature)
+            .unwrap()
+            .warnings
+            .is_empty()
+    );
+}
@@ -0,0 +1,111 @@
+//! Finding out whether a function with a spec is an associated function of an `impl` block.
+//!
+//! An attribute on a function only sees the function, but some of the generated code
+//! depends on its scope, e.g. a Kani proof harness, which calls the function by its name.
+//! So the source file of the attribute is parsed to find the functions with a spec in and
+//! outside of `impl` blocks. Spans don't locate the items of a file parsed inside a proc
+//! macro, so the functions are told apart by their signatures.
+//!
+//! This is a heuristic, so it may not find out. The scope is unknown if the source file
+//! isn't available, as in some IDEs, if the function isn't in it, as for code generated by
+//! a macro, or if functions with the same signature are both in and outside of `impl`
+//! blocks. Code that depends on the scope must handle that case safely.
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anodized_core::is_spec_attr;
+use proc_macro2::Span;
+use quote::ToTokens;
+use syn::{ImplItemFn, ItemFn, Signature, visit::Visit};
+
+thread_local! {
+    /// The signatures of the functions with a spec in each source file, by path, and whether
+    /// each is in an `impl` block.
+    static SOURCES: RefCell<HashMap<PathBuf, Vec<(String, bool)>>> = RefCell::new(HashMap::new());
+}
+
+/// Where a function with a spec is, as far as its source file tells.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scope {
+    /// Outside of any `impl` block.
+    Free,
+    /// In an `impl` block.
+    Impl,
+    /// Either, see the [module documentation](self).
+    Unknown,
+}
+
+/// The scope of a function with a spec and the given signature, in the source file of the
+/// span, e.g. that of its `#[spec]` attribute.
+pub fn scope(span: Span, sig: &Signature) -> Scope {
+    let Some(path) = span.local_file() else {
+        return Scope::Unknown;
+    };
+    let sig = sig.to_token_stream().to_string();
+    SOURCES.with_borrow_mut(|sources| {
+        let specs = sources
+            .entry(path)
+            .or_insert_with_key(|path| read_specs(path));
+        scope_in(specs, &sig)
+    })
+}
+
+/// The scope of the function with the given signature among the functions with a spec in
+/// a file.
+fn scope_in(specs: &[(String, bool)], sig: &str) -> Scope {
+    let mut found = specs
+        .iter()
+        .filter(|(found, _)| found == sig)
+        .map(|(_, in_impl)| *in_impl);
+    match found.next() {
+        None => Scope::Unknown,
+        Some(in_impl) if found.any(|other| other != in_impl) => Scope::Unknown,
+        Some(true) => Scope::Impl,
+        Some(false) => Scope::Free,
+    }
+}
+
+fn read_specs(path: &Path) -> Vec<(String, bool)> {
+    match fs::read_to_string(path).map(|text| syn::parse_file(&text)) {
+        Ok(Ok(file)) => find_specs(&file),
+        _ => Vec::new(),
+    }
+}
+
+/// The signatures of the functions with a spec in a file, and whether each is in an `impl`
+/// block.
+fn find_specs(file: &syn::File) -> Vec<(String, bool)> {
+    let mut finder = SpecFinder { specs: Vec::new() };
+    finder.visit_file(file);
+    finder.specs
+}
+
+struct SpecFinder {
+    specs: Vec<(String, bool)>,
+}
+
+impl<'ast> Visit<'ast> for SpecFinder {
+    fn visit_item_fn(&mut self, func: &'ast ItemFn) {
+        if func.attrs.iter().any(is_spec_attr) {
+            self.specs
+                .push((func.sig.to_token_stream().to_string(), false));
+        }
+        syn::visit::visit_item_fn(self, func);
+    }
+
+    fn visit_impl_item_fn(&mut self, func: &'ast ImplItemFn) {
+        if func.attrs.iter().any(is_spec_attr) {
+            self.specs
+                .push((func.sig.to_token_stream().to_string(), true));
+        }
+        syn::visit::visit_impl_item_fn(self, func);
+    }
+}
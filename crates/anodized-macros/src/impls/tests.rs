@@ -0,0 +1,56 @@
+use super::*;
+
+use syn::parse_quote;
+
+#[test]
+fn specs_in_impls() {
+    let file: syn::File = parse_quote! {
+        #[spec(requires: x > 0)]
+        fn free(x: i32) -> i32 { x }
+
+        impl Point {
+            #[anodized::spec(requires: x > 0)]
+            fn make(x: i32) -> Point {
+                #[spec(requires: y > 0)]
+                fn nested(y: i32) -> i32 { y }
+                Point { x: nested(x) }
+            }
+
+            fn unchecked(x: i32) -> Point { Point { x } }
+        }
+
+        mod inner {
+            impl Point {
+                #[spec(ensures: *output > 0)]
+                fn get(&self) -> i32 { self.x }
+            }
+        }
+    };
+
+    let specs = find_specs(&file);
+    let sig = |sig: Signature| sig.to_token_stream().to_string();
+    assert_eq!(
+        specs,
+        [
+            (sig(parse_quote! { fn free(x: i32) -> i32 }), false),
+            (sig(parse_quote! { fn make(x: i32) -> Point }), true),
+            (sig(parse_quote! { fn nested(y: i32) -> i32 }), false),
+            (sig(parse_quote! { fn get(&self) -> i32 }), true),
+        ]
+    );
+}
+
+#[test]
+fn scopes() {
+    let specs = [
+        ("fn free(x: i32) -> i32".to_string(), false),
+        ("fn make(x: i32) -> Point".to_string(), true),
+        ("fn twice(x: i32)".to_string(), false),
+        ("fn twice(x: i32)".to_string(), true),
+    ];
+
+    assert_eq!(scope_in(&specs, "fn free(x: i32) -> i32"), Scope::Free);
+    assert_eq!(scope_in(&specs, "fn make(x: i32) -> Point"), Scope::Impl);
+    assert_eq!(scope_in(&specs, "fn twice(x: i32)"), Scope::Unknown);
+    assert_eq!(scope_in(&specs, "fn generated()"), Scope::Unknown);
+}
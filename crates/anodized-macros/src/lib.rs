@@ -0,0 +1,384 @@
+#![doc = include_str!("../README.md")]
+
+use std::cell::Cell;
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::{ToTokens, quote};
+
+use anodized_core::{
+    Spec, creusot, flux,
+    instrument::{self, BuiltinBackend, LogLevel, Settings, TrapFallback},
+    is_spec_attr, kani, proptest, prusti, verus,
+};
+use syn::{Attribute, Ident, ItemFn, Signature, Stmt};
+
+mod config;
+mod impls;
+
+use config::{Config, Defaults, Runtime};
+use impls::Scope;
+
+/// The settings used by crates that don't choose their own in `[package.metadata.anodized]`.
+const DEFAULTS: Defaults = Defaults {
+    runtime: FEATURE_RUNTIME,
+    settings: Settings {
+        log_level: LOG_LEVEL,
+        trap_fallback: TRAP_FALLBACK,
+        trace_spans: cfg!(feature = "trace-spans"),
+        runtime_control: false,
+        stats: cfg!(feature = "stats"),
+        stats_timing: cfg!(feature = "stats-timing"),
+        skip_expensive: cfg!(feature = "skip-expensive"),
+        print_fn: None,
+    },
+};
+
+/// The runtime behavior selected by a `runtime-*` feature.
+///
+/// Cargo unifies features across the dependency graph, so several of them may be
+/// enabled at once. In that case, the behavior that checks most strictly wins. The
+/// `runtime` key in `[package.metadata.anodized]` overrides it, with a warning if they
+/// differ.
+const FEATURE_RUNTIME: Option<&str> = if cfg!(feature = "runtime-check-and-panic") {
+    Some("check-and-panic")
+} else if cfg!(feature = "runtime-check-and-trap") {
+    Some("check-and-trap")
+} else if cfg!(feature = "runtime-check-and-print") {
+    Some("check-and-print")
+} else if cfg!(feature = "runtime-check-and-log") {
+    Some("check-and-log")
+} else if cfg!(feature = "runtime-check-and-trace") {
+    Some("check-and-trace")
+} else if cfg!(feature = "runtime-no-check") {
+    Some("no-check")
+} else {
+    None
+};
+
+/// The level used by `runtime-check-and-log`, set via the `ANODIZED_LOG_LEVEL`
+/// environment variable when this crate is built. Defaults to `error`.
+const LOG_LEVEL: LogLevel = match option_env!("ANODIZED_LOG_LEVEL") {
+    None => LogLevel::Error,
+    Some(name) => match LogLevel::from_name(name) {
+        Some(level) => level,
+        None => {
+            panic!("anodized: `ANODIZED_LOG_LEVEL` must be one of: error, warn, info, debug, trace")
+        }
+    },
+};
+
+/// What `runtime-check-and-trap` does without a debugger, set via the
+/// `ANODIZED_TRAP_FALLBACK` environment variable when this crate is built.
+/// Defaults to `panic`.
+const TRAP_FALLBACK: TrapFallback = match option_env!("ANODIZED_TRAP_FALLBACK") {
+    None => TrapFallback::Panic,
+    Some(name) => match TrapFallback::from_name(name) {
+        Some(fallback) => fallback,
+        None => panic!("anodized: `ANODIZED_TRAP_FALLBACK` must be one of: panic, print"),
+    },
+};
+
+/// Attaches a specification to a fn, or enables specs inside a trait and its impls.
+///
+/// This macro parses spec elements and transforms the item's code to provide
+/// compile-time syntax validation, and depending on settings, runtime checks.
+#[proc_macro_attribute]
+pub fn spec(args: TokenStream, input: TokenStream) -> TokenStream {
+    let Config {
+        runtime,
+        kani_harnesses,
+        kani_contracts,
+        creusot_contracts,
+        prusti_contracts,
+        verus_functions,
+        flux_signatures,
+        warnings,
+    } = match config::load_config(&DEFAULTS) {
+        Ok(config) => config,
+        Err(message) => {
+            let message = format!("anodized: {message}");
+            return syn::Error::new(Span::call_site(), message)
+                .to_compile_error()
+                .into();
+        }
+    };
+
+    let input = if ADDED_ONCE.get() {
+        input
+    } else {
+        let (input, added) = add_to_body(input, || {
+            let mut stmts = warnings
+                .iter()
+                .map(|message| warning(quote! {}, message, Span::call_site()))
+                .collect::<Vec<_>>();
+            if std::env::var_os("CARGO_MANIFEST_DIR").is_some() {
+                stmts.push(manifest_dependency());
+            }
+            stmts
+        });
+        ADDED_ONCE.set(added);
+        input
+    };
+
+    let verifiers = [
+        (kani_contracts, &KANI),
+        (creusot_contracts, &CREUSOT),
+        (prusti_contracts, &PRUSTI),
+    ]
+    .into_iter()
+    .filter_map(|(enabled, verifier)| enabled.then_some(verifier))
+    .collect::<Vec<_>>();
+    let (input, contract_errors) = add_contracts(&verifiers, &args, input);
+    let input = if flux_signatures {
+        add_flux_sig(&args, input)
+    } else {
+        input
+    };
+    let (input, verus_fn) = if verus_functions {
+        add_verus_fn(&args, input)
+    } else {
+        (input, proc_macro2::TokenStream::new())
+    };
+
+    let (input, harness) = if kani_harnesses {
+        add_harness(&args, input)
+    } else {
+        (input, None)
+    };
+
+    let item: proc_macro2::TokenStream = match runtime {
+        Runtime::Uniform(backend) => expand(&backend, args, input).into(),
+        Runtime::PerBuild { debug, release } => {
+            let debug_item =
+                proc_macro2::TokenStream::from(expand(&debug, args.clone(), input.clone()));
+            let release_item = proc_macro2::TokenStream::from(expand(&release, args, input));
+            quote! {
+                #[cfg(debug_assertions)]
+                #debug_item
+                #[cfg(not(debug_assertions))]
+                #release_item
+            }
+        }
+    };
+    quote! { #item #harness #contract_errors #verus_fn }.into()
+}
+
+thread_local! {
+    /// Whether the statements added once per crate were added: the warnings about the
+    /// configuration, and the dependency on the manifest.
+    static ADDED_ONCE: Cell<bool> = const { Cell::new(false) };
+}
+
+/// A statement that makes the crate depend on its manifest, so that Cargo rebuilds it when
+/// `[package.metadata.anodized]` changes, which it otherwise wouldn't notice.
+fn manifest_dependency() -> Stmt {
+    syn::parse_quote! {
+        const _: &[u8] = ::core::include_bytes!(::core::concat!(
+            ::core::env!("CARGO_MANIFEST_DIR"),
+            "/Cargo.toml"
+        ));
+    }
+}
+
+/// Add statements to the start of the body of the item if it's a fn, and whether it is.
+fn add_to_body(input: TokenStream, stmts: impl FnOnce() -> Vec<Stmt>) -> (TokenStream, bool) {
+    match syn::parse::<ItemFn>(input.clone()) {
+        Ok(mut func) => {
+            func.block.stmts.splice(0..0, stmts());
+            (func.into_token_stream().into(), true)
+        }
+        Err(_) => (input, false),
+    }
+}
+
+/// A statement that warns with the given message, with the given attributes, e.g. a `cfg`.
+///
+/// Proc macros can't emit warnings on stable Rust, so the warning is reported as the use of
+/// a deprecated constant.
+fn warning(attrs: proc_macro2::TokenStream, message: &str, span: Span) -> Stmt {
+    syn::parse_quote_spanned! {span=>
+        #attrs
+        const _: () = {
+            #[deprecated(note = #message)]
+            const NOTE: () = ();
+            NOTE
+        };
+    }
+}
+
+/// Generates a `proptest` test of a fn from its `#[spec]`, which must come after this
+/// attribute.
+///
+/// The arguments give strategies for some of the fn's arguments, as in
+/// `#[spec_proptest(divisor in 1u32..)]`, after any attributes for the test, e.g.
+/// `#[ignore]`. The other arguments are generated with `proptest::arbitrary::any`.
+#[proc_macro_attribute]
+pub fn spec_proptest(args: TokenStream, input: TokenStream) -> TokenStream {
+    let test = syn::parse::<proptest::TestArgs>(args).and_then(|args| {
+        let func = syn::parse::<ItemFn>(input.clone())?;
+        let Some(spec_attr) = func.attrs.iter().find(|attr| is_spec_attr(attr)) else {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "`#[spec_proptest]` must be placed above the `#[spec]` of the fn",
+            ));
+        };
+        // Errors in the spec are left to the expansion of `#[spec]`.
+        let Ok(spec) = spec_attr.parse_args::<Spec>() else {
+            return Ok(None);
+        };
+        proptest::build_test(&func, &spec, &args).map(Some)
+    });
+    let test = match test {
+        Ok(test) => test.into_token_stream(),
+        Err(error) => error.to_compile_error(),
+    };
+    let input = proc_macro2::TokenStream::from(input);
+    quote! { #input #test }.into()
+}
+
+/// Build a Kani proof harness if the item is a fn whose spec and signature allow one.
+///
+/// A harness can't be an item of an `impl` block, so for an associated fn, or one that may
+/// be, a warning under `cfg(kani)` is added to its body instead. Errors are left to the
+/// expansion of the item itself.
+fn add_harness(args: &TokenStream, input: TokenStream) -> (TokenStream, Option<ItemFn>) {
+    let Ok(func) = syn::parse::<ItemFn>(input.clone()) else {
+        return (input, None);
+    };
+    let Some(harness) = syn::parse(args.clone())
+        .ok()
+        .and_then(|spec| kani::build_harness(&func, &spec))
+    else {
+        return (input, None);
+    };
+    let reason = match impls::scope(Span::call_site(), &func.sig) {
+        Scope::Free => return (input, Some(harness)),
+        Scope::Impl => "`#[spec]` can't add one for a fn in an `impl` block",
+        Scope::Unknown => {
+            "`#[spec]` can't tell from the source file whether the fn is in an `impl` block"
+        }
+    };
+    let message = format!("no Kani proof harness for `{}`: {reason}", func.sig.ident);
+    let (input, _) = add_to_body(input, || {
+        vec![warning(
+            quote! { #[cfg(kani)] },
+            &message,
+            Span::call_site(),
+        )]
+    });
+    (input, None)
+}
+
+/// A verifier whose contract attributes `#[spec]` can add to functions.
+struct Verifier {
+    /// The `cfg` set when the verifier compiles a crate.
+    cfg: &'static str,
+    build_contract: fn(&Signature, &Spec) -> syn::Result<Vec<Attribute>>,
+}
+
+const KANI: Verifier = Verifier {
+    cfg: "kani",
+    build_contract: |sig, spec| Ok(kani::build_contract(sig, spec)),
+};
+
+const CREUSOT: Verifier = Verifier {
+    cfg: "creusot",
+    build_contract: creusot::build_contract,
+};
+
+const PRUSTI: Verifier = Verifier {
+    cfg: "prusti",
+    build_contract: prusti::build_contract,
+};
+
+/// Add the contract attributes of the verifiers to the item if it's a fn with a valid spec.
+///
+/// Errors in the spec or the item are left to the expansion of the item itself. A spec
+/// that a verifier can't translate becomes a `compile_error!` under the verifier's `cfg`,
+/// so that it doesn't affect other builds.
+fn add_contracts(
+    verifiers: &[&Verifier],
+    args: &TokenStream,
+    input: TokenStream,
+) -> (TokenStream, proc_macro2::TokenStream) {
+    let mut errors = proc_macro2::TokenStream::new();
+    if verifiers.is_empty() {
+        return (input, errors);
+    }
+    let (Ok(mut func), Ok(spec)) = (
+        syn::parse::<ItemFn>(input.clone()),
+        syn::parse::<Spec>(args.clone()),
+    ) else {
+        return (input, errors);
+    };
+    for verifier in verifiers {
+        match (verifier.build_contract)(&func.sig, &spec) {
+            Ok(contract) => func.attrs.extend(contract),
+            Err(error) => {
+                let cfg = Ident::new(verifier.cfg, Span::call_site());
+                for error in error {
+                    let error = error.to_compile_error();
+                    errors.extend(quote! { #[cfg(#cfg)] #error });
+                }
+            }
+        }
+    }
+    (func.into_token_stream().into(), errors)
+}
+
+/// Add the Flux signature to the item if it's a fn with a valid spec.
+///
+/// Each precondition that was left out of the signature is reported with a warning under
+/// `cfg(flux)`, in the body of the fn, which may be in an `impl` block.
+fn add_flux_sig(args: &TokenStream, input: TokenStream) -> TokenStream {
+    let (Ok(mut func), Ok(spec)) = (
+        syn::parse::<ItemFn>(input.clone()),
+        syn::parse::<Spec>(args.clone()),
+    ) else {
+        return input;
+    };
+    let (sig, notes) = flux::build_sig(&func.sig, &spec);
+    let warnings = notes
+        .into_iter()
+        .map(|note| warning(quote! { #[cfg(flux)] }, &note.to_string(), note.span));
+    func.block.stmts.splice(0..0, warnings);
+    func.attrs.extend(sig);
+    func.into_token_stream().into()
+}
+
+/// The `cfg` set when Verus verifies a crate.
+const VERUS_CFG: &str = "verus_keep_ghost";
+
+/// Define the item in Verus syntax for Verus, if it's a fn with a valid spec.
+///
+/// Returns the item, left out of Verus builds if it has a definition for them, and the
+/// definition. As with contracts, a spec that can't be translated becomes a
+/// `compile_error!` under Verus's `cfg`.
+fn add_verus_fn(args: &TokenStream, input: TokenStream) -> (TokenStream, proc_macro2::TokenStream) {
+    let (Ok(mut func), Ok(spec)) = (
+        syn::parse::<ItemFn>(input.clone()),
+        syn::parse::<Spec>(args.clone()),
+    ) else {
+        return (input, proc_macro2::TokenStream::new());
+    };
+    let cfg = Ident::new(VERUS_CFG, Span::call_site());
+    match verus::build_fn(&func, &spec) {
+        Ok(verus_fn) => {
+            func.attrs.push(syn::parse_quote! { #[cfg(not(#cfg))] });
+            (
+                func.into_token_stream().into(),
+                quote! { #[cfg(#cfg)] #verus_fn },
+            )
+        }
+        Err(error) => {
+            let errors = error.into_iter().map(|error| error.to_compile_error());
+            (input, quote! { #(#[cfg(#cfg)] #errors)* })
+        }
+    }
+}
+
+/// Expand `#[spec]` on an item using the given backend.
+fn expand(backend: &BuiltinBackend, args: TokenStream, input: TokenStream) -> TokenStream {
+    instrument::expand(backend, args.into(), input.into()).into()
+}
@@ -0,0 +1,500 @@
+//! Lifting of the argument checks at the start of functions into `#[spec]`.
+
+use proc_macro2::{Span, TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::{
+    Attribute, Block, Expr, ExprClosure, ExprLit, ExprPath, FnArg, ImplItemFn, ItemFn, ItemImpl,
+    ItemTrait, Lit, Macro, Signature, Stmt, Token,
+    parse::Parser,
+    parse_file,
+    punctuated::Punctuated,
+    spanned::Spanned,
+    visit::{self, Visit},
+};
+
+use anodized_core::is_spec_attr;
+
+use crate::{
+    Migration, Skipped, collect_idents,
+    source_text::{SourceText, apply_edits},
+};
+
+/// Lift the assertions at the start of functions in a Rust source file into a `#[spec]`.
+///
+/// The leading `assert!`, `assert_eq!`, and `assert_ne!` statements of a function, and
+/// their `debug_` variants, become its preconditions, in order:
+/// - `assert!(cond)` becomes `requires: cond`, and `assert_eq!(a, b)` and
+///   `assert_ne!(a, b)` become `requires: a == b` and `requires: a != b`,
+/// - the `debug_` variants get `#[cfg(debug_assertions)]`,
+/// - a message without format arguments, as in `assert!(cond, "message")`, becomes
+///   `#[label = "message"]`.
+///
+/// Lifting stops at the first statement that isn't an assertion, or at an assertion that
+/// reads anything but the arguments, or whose message has format arguments, since a
+/// later assertion may rely on it; that assertion is reported. So is a function that
+/// already has a `#[spec]`. Functions of traits and of `#[spec]` `impl`s are left alone.
+pub fn lift_asserts(source: &str) -> syn::Result<Migration> {
+    let file = parse_file(source)?;
+    let mut collector = FnCollector::default();
+    collector.visit_file(&file);
+
+    let text = SourceText::new(source);
+    let mut edits = Vec::new();
+    let mut skipped = Vec::new();
+    let mut migrated = 0;
+    for found in collector.fns {
+        let mut lifted = Vec::new();
+        let mut stopped = None;
+        for stmt in &found.block.stmts {
+            let Some((mac, kind)) = assertion(stmt) else {
+                break;
+            };
+            match lift(mac, kind, found.sig, &text) {
+                Ok(requires) => lifted.push((stmt, requires)),
+                Err(reason) => {
+                    stopped = Some((mac.span(), reason));
+                    break;
+                }
+            }
+        }
+        if found.attrs.iter().any(is_spec_attr) {
+            if let Some((stmt, _)) = lifted.first() {
+                stopped = Some((
+                    stmt.span(),
+                    "the function already has a `#[spec]`".to_string(),
+                ));
+            }
+            lifted.clear();
+        }
+        if let Some((span, reason)) = stopped {
+            let start = span.start();
+            skipped.push(Skipped {
+                line: start.line,
+                column: start.column,
+                reason,
+            });
+        }
+        if lifted.is_empty() {
+            continue;
+        }
+
+        let start = text.offset(found.start);
+        let indent = text.indent(found.start);
+        let lines = lifted
+            .iter()
+            .flat_map(|(_, requires)| requires)
+            .map(|line| format!("{indent}    {line}\n"))
+            .collect::<String>();
+        edits.push((
+            start..start,
+            format!("#[spec(\n{lines}{indent})]\n{indent}"),
+        ));
+        for (stmt, _) in &lifted {
+            edits.push((text.line_range(stmt.span()), String::new()));
+        }
+        // Drop the blank lines that separated the assertions from the rest of the body.
+        let (last, _) = lifted.last().unwrap();
+        let end = text.line_range(last.span()).end;
+        let rest = &source[end..];
+        let body = rest.trim_start_matches([' ', '\t', '\n', '\r']);
+        let blank = &rest[..rest.len() - body.len()];
+        let blank_lines = blank.rfind('\n').map_or(0, |index| index + 1);
+        if blank_lines > 0 && !body.starts_with('}') {
+            edits.push((end..end + blank_lines, String::new()));
+        }
+        migrated += 1;
+    }
+
+    skipped.sort_by_key(|skipped| (skipped.line, skipped.column));
+    Ok(Migration {
+        source: apply_edits(source, edits),
+        migrated,
+        skipped,
+    })
+}
+
+#[derive(Clone, Copy)]
+enum AssertKind {
+    Assert,
+    Eq,
+    Ne,
+}
+
+/// The macro of an assertion statement, its kind, and whether it's a `debug_` variant.
+fn assertion(stmt: &Stmt) -> Option<(&Macro, (AssertKind, bool))> {
+    let mac = match stmt {
+        Stmt::Macro(stmt) => &stmt.mac,
+        Stmt::Expr(Expr::Macro(expr), Some(_)) => &expr.mac,
+        _ => return None,
+    };
+    let segments = &mac.path.segments;
+    let name = match segments.len() {
+        1 => segments[0].ident.to_string(),
+        2 if segments[0].ident == "std" || segments[0].ident == "core" => {
+            segments[1].ident.to_string()
+        }
+        _ => return None,
+    };
+    let (debug, name) = match name.strip_prefix("debug_") {
+        Some(name) => (true, name),
+        None => (false, name.as_str()),
+    };
+    let kind = match name {
+        "assert" => AssertKind::Assert,
+        "assert_eq" => AssertKind::Eq,
+        "assert_ne" => AssertKind::Ne,
+        _ => return None,
+    };
+    Some((mac, (kind, debug)))
+}
+
+/// The lines of the precondition equivalent to an assertion, or why there is none.
+fn lift(
+    mac: &Macro,
+    (kind, debug): (AssertKind, bool),
+    sig: &Signature,
+    text: &SourceText,
+) -> Result<Vec<String>, String> {
+    let args = Punctuated::<Expr, Token![,]>::parse_terminated
+        .parse2(mac.tokens.clone())
+        .map_err(|error| format!("invalid assertion: {error}"))?;
+    let args = args.iter().collect::<Vec<_>>();
+    let operands = match kind {
+        AssertKind::Assert => 1,
+        AssertKind::Eq | AssertKind::Ne => 2,
+    };
+    if args.len() < operands {
+        return Err("invalid assertion: missing operands".to_string());
+    }
+    let (operands, message) = args.split_at(operands);
+
+    let condition = match kind {
+        AssertKind::Assert => text.of(operands[0]),
+        AssertKind::Eq | AssertKind::Ne => {
+            let op = if matches!(kind, AssertKind::Eq) {
+                "=="
+            } else {
+                "!="
+            };
+            let [left, right] = [operands[0], operands[1]].map(|operand| {
+                if is_comparison_operand(operand) {
+                    text.of(operand)
+                } else {
+                    format!("({})", text.of(operand))
+                }
+            });
+            format!("{left} {op} {right}")
+        }
+    };
+    let mut checker = ArgsOnly {
+        args: arg_names(sig),
+        bound: Vec::new(),
+        stray: None,
+    };
+    for operand in operands {
+        checker.visit_expr(operand);
+    }
+    if let Some(name) = checker.stray {
+        return Err(format!(
+            "`{condition}` reads `{name}`, which isn't an argument"
+        ));
+    }
+
+    let label = match message {
+        [] => None,
+        [
+            Expr::Lit(ExprLit {
+                lit: Lit::Str(lit), ..
+            }),
+        ] if !lit.value().contains(['{', '}']) => Some(text.of(lit)),
+        _ => {
+            return Err(format!(
+                "the message of `{condition}` has format arguments, which a label can't have"
+            ));
+        }
+    };
+
+    let mut lines = Vec::new();
+    if debug {
+        lines.push("#[cfg(debug_assertions)]".to_string());
+    }
+    if let Some(label) = label {
+        lines.push(format!("#[label = {label}]"));
+    }
+    lines.push(format!("requires: {condition},"));
+    Ok(lines)
+}
+
+/// Whether an expression can be an operand of `==` without parentheses.
+fn is_comparison_operand(expr: &Expr) -> bool {
+    match expr {
+        Expr::Binary(binary) => matches!(
+            binary.op,
+            syn::BinOp::Add(_)
+                | syn::BinOp::Sub(_)
+                | syn::BinOp::Mul(_)
+                | syn::BinOp::Div(_)
+                | syn::BinOp::Rem(_)
+                | syn::BinOp::BitAnd(_)
+                | syn::BinOp::BitOr(_)
+                | syn::BinOp::BitXor(_)
+                | syn::BinOp::Shl(_)
+                | syn::BinOp::Shr(_)
+        ),
+        Expr::Range(_)
+        | Expr::Assign(_)
+        | Expr::Closure(_)
+        | Expr::Return(_)
+        | Expr::Break(_)
+        | Expr::Yield(_)
+        | Expr::Let(_) => false,
+        _ => true,
+    }
+}
+
+/// The names bound by the arguments of a function, including `self`.
+fn arg_names(sig: &Signature) -> Vec<String> {
+    let mut names = Vec::new();
+    for input in &sig.inputs {
+        match input {
+            FnArg::Receiver(_) => names.push("self".to_string()),
+            FnArg::Typed(input) => collect_idents(input.pat.to_token_stream(), &mut names),
+        }
+    }
+    names
+}
+
+/// Visitor that finds the first variable of an expression that isn't an argument.
+///
+/// Names in uppercase are constants, statics, or unit structs, and the names of called
+/// functions are items, so neither is a variable.
+struct ArgsOnly {
+    args: Vec<String>,
+    /// The names bound by the closures being visited.
+    bound: Vec<String>,
+    stray: Option<String>,
+}
+
+impl ArgsOnly {
+    fn check(&mut self, name: String) {
+        let variable = name.starts_with(|c: char| c.is_lowercase() || c == '_');
+        if self.stray.is_none()
+            && variable
+            && !self.args.contains(&name)
+            && !self.bound.contains(&name)
+        {
+            self.stray = Some(name);
+        }
+    }
+
+    fn check_tokens(&mut self, tokens: TokenStream) {
+        let mut names = Vec::new();
+        collect_idents(tokens, &mut names);
+        for name in names {
+            // Keywords and macro syntax, e.g. the `if` of a `matches!` guard.
+            if syn::parse_str::<syn::Ident>(&name).is_ok() {
+                self.check(name);
+            }
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for ArgsOnly {
+    fn visit_expr_path(&mut self, node: &'ast ExprPath) {
+        if node.qself.is_none()
+            && let Some(ident) = node.path.get_ident()
+        {
+            self.check(ident.to_string());
+        }
+    }
+
+    fn visit_expr_call(&mut self, node: &'ast syn::ExprCall) {
+        if !matches!(node.func.as_ref(), Expr::Path(_)) {
+            self.visit_expr(&node.func);
+        }
+        for arg in &node.args {
+            self.visit_expr(arg);
+        }
+    }
+
+    fn visit_expr_closure(&mut self, node: &'ast ExprClosure) {
+        let depth = self.bound.len();
+        for input in &node.inputs {
+            collect_idents(input.to_token_stream(), &mut self.bound);
+        }
+        self.visit_expr(&node.body);
+        self.bound.truncate(depth);
+    }
+
+    fn visit_macro(&mut self, node: &'ast Macro) {
+        // Pattern bindings inside a macro can't be told apart from variables.
+        self.check_tokens(node.tokens.clone());
+    }
+}
+
+/// A function with a body, and where its `#[spec]` goes.
+struct FoundFn<'ast> {
+    attrs: &'ast [Attribute],
+    sig: &'ast Signature,
+    block: &'ast Block,
+    /// The start of the function after its attributes.
+    start: proc_macro2::LineColumn,
+}
+
+/// Visitor that collects the functions whose assertions can be lifted into a `#[spec]`.
+#[derive(Default)]
+struct FnCollector<'ast> {
+    fns: Vec<FoundFn<'ast>>,
+}
+
+/// The span of the first token of an item after its outer attributes.
+fn after_attrs(item: &impl ToTokens) -> Span {
+    let mut tokens = item.to_token_stream().into_iter().peekable();
+    while let Some(TokenTree::Punct(punct)) = tokens.peek()
+        && punct.as_char() == '#'
+    {
+        tokens.next();
+        tokens.next();
+    }
+    tokens
+        .next()
+        .map_or_else(Span::call_site, |token| token.span())
+}
+
+impl<'ast> Visit<'ast> for FnCollector<'ast> {
+    fn visit_item_fn(&mut self, node: &'ast ItemFn) {
+        self.fns.push(FoundFn {
+            attrs: &node.attrs,
+            sig: &node.sig,
+            block: &node.block,
+            start: after_attrs(node).start(),
+        });
+        visit::visit_item_fn(self, node);
+    }
+
+    fn visit_impl_item_fn(&mut self, node: &'ast ImplItemFn) {
+        self.fns.push(FoundFn {
+            attrs: &node.attrs,
+            sig: &node.sig,
+            block: &node.block,
+            start: after_attrs(node).start(),
+        });
+        visit::visit_impl_item_fn(self, node);
+    }
+
+    fn visit_item_impl(&mut self, node: &'ast ItemImpl) {
+        if !node.attrs.iter().any(is_spec_attr) {
+            visit::visit_item_impl(self, node);
+        }
+    }
+
+    fn visit_item_trait(&mut self, _node: &'ast ItemTrait) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lift_asserts() {
+        let source = r#"
+            /// Copies a range.
+            #[inline]
+            pub fn copy(src: &[u8], dst: &mut [u8], (start, end): (usize, usize)) {
+                assert!(start <= end, "start must not exceed end");
+                debug_assert_eq!(src.len(), dst.len() + 0);
+                assert!(src.iter().all(|byte| *byte < MAX));
+
+                dst[start..end].copy_from_slice(&src[start..end]);
+            }
+            "#;
+        let migration = lift_asserts(source).unwrap();
+
+        assert_eq!(migration.migrated, 1);
+        assert!(migration.skipped.is_empty());
+        assert_eq!(
+            migration.source,
+            r#"
+            /// Copies a range.
+            #[inline]
+            #[spec(
+                #[label = "start must not exceed end"]
+                requires: start <= end,
+                #[cfg(debug_assertions)]
+                requires: src.len() == dst.len() + 0,
+                requires: src.iter().all(|byte| *byte < MAX),
+            )]
+            pub fn copy(src: &[u8], dst: &mut [u8], (start, end): (usize, usize)) {
+                dst[start..end].copy_from_slice(&src[start..end]);
+            }
+            "#
+        );
+    }
+
+    #[test]
+    fn test_stops_at_unliftable_assert() {
+        let source = r#"
+            fn get(&self, index: usize) -> u8 {
+                assert!(index < self.len());
+                assert!(index > 0, "bad index {index}");
+                assert!(index < 10);
+                self.items[index]
+            }
+
+            fn f(x: u32) {
+                let y = x + 1;
+                assert!(y > x);
+            }
+
+            fn g(x: u32) {
+                assert!(x < limit);
+            }
+            "#;
+        let migration = lift_asserts(source).unwrap();
+
+        assert_eq!(migration.migrated, 1);
+        assert!(migration.source.contains("requires: index < self.len(),"));
+        assert!(migration.source.contains("assert!(index < 10);"));
+        assert_eq!(
+            migration
+                .skipped
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            [
+                "4:17: the message of `index > 0` has format arguments, which a label can't have",
+                "15:17: `x < limit` reads `limit`, which isn't an argument",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_skips_specced_functions() {
+        let source = r#"
+            #[spec(requires: x > 0)]
+            fn f(x: u32) {
+                assert!(x < 10);
+            }
+
+            #[anodized::spec(requires: x > 0)]
+            fn g(x: u32) {
+                assert!(x < 10);
+            }
+            "#;
+        let migration = lift_asserts(source).unwrap();
+
+        assert_eq!(migration.migrated, 0);
+        assert_eq!(migration.source, source);
+        assert_eq!(
+            migration
+                .skipped
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            [
+                "4:17: the function already has a `#[spec]`",
+                "9:17: the function already has a `#[spec]`",
+            ]
+        );
+    }
+}
@@ -0,0 +1,543 @@
+//! Conversion of the attributes of the [`contracts`](https://crates.io/crates/contracts)
+//! crate into `#[spec]`.
+
+use std::ops::Range;
+
+use proc_macro2::{Delimiter, Spacing, TokenTree};
+use syn::{
+    Attribute, ImplItemFn, ItemFn, ItemImpl, ItemTrait, Meta, TraitItemFn, parse_file,
+    spanned::Spanned,
+    visit::{self, Visit},
+};
+
+use anodized_core::is_spec_attr;
+
+use crate::{
+    Migration, Skipped,
+    source_text::{SourceText, apply_edits},
+};
+
+/// Rewrite the attributes of the `contracts` crate on functions in a Rust source file into
+/// a single `#[spec]` per function.
+///
+/// The attributes are translated as follows:
+/// - `#[requires(cond)]` becomes `requires: cond`, `#[invariant(cond)]` becomes
+///   `maintains: cond`, and `#[ensures(cond)]` becomes `ensures: cond`,
+/// - the `debug_` and `test_` variants, e.g. `#[debug_requires(cond)]`, get
+///   `#[cfg(debug_assertions)]` and `#[cfg(test)]`,
+/// - a message, as in `#[requires(cond, "message")]`, becomes `#[label = "message"]`,
+/// - `ret` becomes `output`, dereferenced unless a field, method, or index follows,
+/// - each `old(expr)` becomes an alias in `captures:`, e.g. `old(self.len())` becomes
+///   `old_len` with `captures: self.len() as old_len`,
+/// - the implication `a -> b` becomes `!(a) || (b)`.
+///
+/// The `#[spec]` takes the place of the first attribute, and the others are removed,
+/// along with their lines if nothing else is on them. Contracts on traits, on trait
+/// methods, and on `impl` blocks are left as they are and reported, as are the contracts of
+/// functions that already have a `#[spec]`.
+pub fn convert_file(source: &str) -> syn::Result<Migration> {
+    let file = parse_file(source)?;
+    let mut collector = ContractCollector::default();
+    collector.visit_file(&file);
+
+    let text = SourceText::new(source);
+    let mut edits = Vec::new();
+    let mut skipped = collector
+        .unsupported
+        .into_iter()
+        .map(|(attr, reason)| skip(attr, reason.to_string()))
+        .collect::<Vec<_>>();
+    let mut migrated = 0;
+    for attrs in collector.fns {
+        let contracts = attrs
+            .iter()
+            .filter_map(|attr| contract_kind(attr).map(|kind| (attr, kind)))
+            .collect::<Vec<_>>();
+        let Some(&(first, _)) = contracts.first() else {
+            continue;
+        };
+        if attrs.iter().any(is_spec_attr) {
+            skipped.push(skip(
+                first,
+                "the function already has a `#[spec]`".to_string(),
+            ));
+            continue;
+        }
+        let indent = text.indent(first.span().start());
+        match build_spec(&contracts, &text, indent) {
+            Ok(spec) => {
+                edits.push((text.range(first.span()), spec));
+                for (attr, _) in &contracts[1..] {
+                    edits.push((text.line_range(attr.span()), String::new()));
+                }
+                migrated += 1;
+            }
+            Err(reason) => skipped.push(skip(first, reason)),
+        }
+    }
+
+    skipped.sort_by_key(|skipped| (skipped.line, skipped.column));
+    Ok(Migration {
+        source: apply_edits(source, edits),
+        migrated,
+        skipped,
+    })
+}
+
+fn skip(attr: &Attribute, reason: String) -> Skipped {
+    let start = attr.span().start();
+    Skipped {
+        line: start.line,
+        column: start.column,
+        reason,
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Keyword {
+    Requires,
+    Invariant,
+    Ensures,
+}
+
+/// The kind of a contract attribute, and the `cfg` of its `debug_` or `test_` variant.
+#[derive(Clone, Copy)]
+struct ContractKind {
+    keyword: Keyword,
+    cfg: Option<&'static str>,
+}
+
+/// The kind of an attribute of the `contracts` crate, e.g. `#[debug_requires]`, written
+/// with or without the `contracts::` prefix.
+fn contract_kind(attr: &Attribute) -> Option<ContractKind> {
+    let segments = &attr.path().segments;
+    let name = match segments.len() {
+        1 => segments[0].ident.to_string(),
+        2 if segments[0].ident == "contracts" => segments[1].ident.to_string(),
+        _ => return None,
+    };
+    let (cfg, name) = if let Some(name) = name.strip_prefix("debug_") {
+        (Some("debug_assertions"), name)
+    } else if let Some(name) = name.strip_prefix("test_") {
+        (Some("test"), name)
+    } else {
+        (None, name.as_str())
+    };
+    let keyword = match name {
+        "requires" => Keyword::Requires,
+        "invariant" => Keyword::Invariant,
+        "ensures" => Keyword::Ensures,
+        _ => return None,
+    };
+    Some(ContractKind { keyword, cfg })
+}
+
+/// A condition of the spec being built.
+struct Condition {
+    keyword: Keyword,
+    cfg: Option<&'static str>,
+    label: Option<String>,
+    text: String,
+}
+
+/// Build the text of the `#[spec]` equivalent to the contract attributes of a function.
+fn build_spec(
+    contracts: &[(&Attribute, ContractKind)],
+    text: &SourceText,
+    indent: &str,
+) -> Result<String, String> {
+    let mut converter = Converter {
+        text,
+        captures: Vec::new(),
+        olds: 0,
+        edits: Vec::new(),
+    };
+    let mut conditions = Vec::new();
+    for &(attr, kind) in contracts {
+        let Meta::List(list) = &attr.meta else {
+            return Err(format!(
+                "`#[{}]` has no condition",
+                attr.path().segments.last().unwrap().ident
+            ));
+        };
+        let mut args = split_args(list.tokens.clone().into_iter().collect());
+        let label = match args.last().map(Vec::as_slice) {
+            Some([TokenTree::Literal(literal)]) if literal.to_string().starts_with('"') => {
+                let label = text.of(literal);
+                args.pop();
+                Some(label)
+            }
+            _ => None,
+        };
+        if args.is_empty() {
+            return Err("a contract attribute has no condition".to_string());
+        }
+        for arg in args {
+            let range =
+                text.offset(arg[0].span().start())..text.offset(arg[arg.len() - 1].span().end());
+            converter.edits.clear();
+            let olds = converter.olds;
+            converter.condition(&arg);
+            if kind.keyword != Keyword::Ensures && converter.olds > olds {
+                return Err("`old(...)` is only allowed in postconditions".to_string());
+            }
+            let edits = converter
+                .edits
+                .drain(..)
+                .map(|(edit, replacement)| {
+                    (
+                        (edit.start - range.start)..(edit.end - range.start),
+                        replacement,
+                    )
+                })
+                .collect();
+            conditions.push(Condition {
+                keyword: kind.keyword,
+                cfg: kind.cfg,
+                label: label.clone(),
+                text: apply_edits(&text.source()[range], edits),
+            });
+        }
+    }
+
+    let mut lines = Vec::new();
+    let push_conditions = |keyword: Keyword, name: &str, lines: &mut Vec<String>| {
+        for condition in conditions.iter().filter(|c| c.keyword == keyword) {
+            if let Some(cfg) = condition.cfg {
+                lines.push(format!("#[cfg({cfg})]"));
+            }
+            if let Some(label) = &condition.label {
+                lines.push(format!("#[label = {label}]"));
+            }
+            lines.push(format!("{name}: {},", condition.text));
+        }
+    };
+    push_conditions(Keyword::Requires, "requires", &mut lines);
+    push_conditions(Keyword::Invariant, "maintains", &mut lines);
+    let captures = converter
+        .captures
+        .iter()
+        .map(|(expr, alias)| format!("{expr} as {alias}"))
+        .collect::<Vec<_>>();
+    match captures.as_slice() {
+        [] => {}
+        [capture] => lines.push(format!("captures: {capture},")),
+        captures => lines.push(format!("captures: [{}],", captures.join(", "))),
+    }
+    push_conditions(Keyword::Ensures, "ensures", &mut lines);
+
+    let body = lines
+        .iter()
+        .map(|line| format!("{indent}    {line}\n"))
+        .collect::<String>();
+    Ok(format!("#[spec(\n{body}{indent})]"))
+}
+
+/// Split the arguments of an attribute at the commas between them.
+fn split_args(tokens: Vec<TokenTree>) -> Vec<Vec<TokenTree>> {
+    let mut args = vec![Vec::new()];
+    for token in tokens {
+        match &token {
+            TokenTree::Punct(punct) if punct.as_char() == ',' => args.push(Vec::new()),
+            _ => args.last_mut().unwrap().push(token),
+        }
+    }
+    args.retain(|arg| !arg.is_empty());
+    args
+}
+
+/// Collects the edits that turn a condition of the `contracts` crate into one of a spec.
+struct Converter<'a> {
+    text: &'a SourceText<'a>,
+    /// The expression and alias of each `old(...)`, in order of appearance.
+    captures: Vec<(String, String)>,
+    /// The number of `old(...)` seen so far, including ones that reuse an alias.
+    olds: usize,
+    edits: Vec<(Range<usize>, String)>,
+}
+
+impl Converter<'_> {
+    /// Rewrite the implications of a condition, and the terms of each side.
+    fn condition(&mut self, tokens: &[TokenTree]) {
+        let arrow = tokens.windows(2).position(|pair| match pair {
+            [TokenTree::Punct(minus), TokenTree::Punct(greater)] => {
+                minus.as_char() == '-'
+                    && minus.spacing() == Spacing::Joint
+                    && greater.as_char() == '>'
+            }
+            _ => false,
+        });
+        let Some(arrow) = arrow.filter(|&arrow| arrow > 0 && arrow + 2 < tokens.len()) else {
+            self.terms(tokens);
+            return;
+        };
+        // `->` is right-associative and binds looser than any operator.
+        let (premise, conclusion) = (&tokens[..arrow], &tokens[arrow + 2..]);
+        let start = self.text.offset(premise[0].span().start());
+        let end = self
+            .text
+            .offset(conclusion[conclusion.len() - 1].span().end());
+        // The arrow takes the whitespace around it, so `a -> b` becomes `!(a) || (b)`.
+        let arrow_range = self.text.offset(premise[premise.len() - 1].span().end())
+            ..self.text.offset(conclusion[0].span().start());
+        self.edits.push((start..start, "!(".to_string()));
+        self.edits.push((arrow_range, ") || (".to_string()));
+        self.edits.push((end..end, ")".to_string()));
+        self.terms(premise);
+        self.condition(conclusion);
+    }
+
+    /// Rewrite `ret` and `old(...)` among the tokens.
+    fn terms(&mut self, tokens: &[TokenTree]) {
+        let mut index = 0;
+        while index < tokens.len() {
+            let after_separator = index > 0
+                && matches!(&tokens[index - 1], TokenTree::Punct(punct) if matches!(punct.as_char(), '.' | ':'));
+            match (&tokens[index], tokens.get(index + 1)) {
+                (TokenTree::Ident(ident), next) if ident == "ret" && !after_separator => {
+                    // A field, method, or index auto-dereferences the output.
+                    let projected = match next {
+                        Some(TokenTree::Punct(punct)) => punct.as_char() == '.',
+                        Some(TokenTree::Group(group)) => group.delimiter() == Delimiter::Bracket,
+                        _ => false,
+                    };
+                    let output = if projected { "output" } else { "*output" };
+                    self.edits
+                        .push((self.text.range(ident.span()), output.to_string()));
+                }
+                (TokenTree::Ident(ident), Some(TokenTree::Group(group)))
+                    if ident == "old"
+                        && !after_separator
+                        && group.delimiter() == Delimiter::Parenthesis =>
+                {
+                    let inner = self.text.offset(group.span_open().end())
+                        ..self.text.offset(group.span_close().start());
+                    let expr = self.text.source()[inner].trim().to_string();
+                    let alias = self.alias(&expr, &group.stream().into_iter().collect::<Vec<_>>());
+                    let range = self.text.offset(ident.span().start())
+                        ..self.text.offset(group.span().end());
+                    self.edits.push((range, alias));
+                    self.olds += 1;
+                    index += 1;
+                }
+                (TokenTree::Group(group), _) => {
+                    self.condition(&group.stream().into_iter().collect::<Vec<_>>());
+                }
+                _ => {}
+            }
+            index += 1;
+        }
+    }
+
+    /// The alias of the value of an expression at entry, e.g. `old_len` for `self.len()`.
+    fn alias(&mut self, expr: &str, tokens: &[TokenTree]) -> String {
+        if let Some((_, alias)) = self.captures.iter().find(|(captured, _)| captured == expr) {
+            return alias.clone();
+        }
+        let mut names = Vec::new();
+        let simple = tokens.iter().all(|token| match token {
+            TokenTree::Ident(ident) => {
+                if ident != "self" {
+                    names.push(ident.to_string());
+                }
+                true
+            }
+            TokenTree::Punct(punct) => matches!(punct.as_char(), '.' | '*' | '&'),
+            TokenTree::Group(group) => {
+                group.delimiter() == Delimiter::Parenthesis && group.stream().is_empty()
+            }
+            TokenTree::Literal(_) => false,
+        });
+        let base = if simple && !names.is_empty() {
+            format!("old_{}", names.join("_"))
+        } else if simple {
+            "old_self".to_string()
+        } else {
+            "old_value".to_string()
+        };
+        let mut alias = base.clone();
+        let mut suffix = 2;
+        while self.captures.iter().any(|(_, taken)| *taken == alias) {
+            alias = format!("{base}_{suffix}");
+            suffix += 1;
+        }
+        self.captures.push((expr.to_string(), alias.clone()));
+        alias
+    }
+}
+
+/// Visitor that collects the attributes of functions, and the contract attributes that
+/// can't be converted.
+#[derive(Default)]
+struct ContractCollector<'ast> {
+    fns: Vec<&'ast [Attribute]>,
+    unsupported: Vec<(&'ast Attribute, &'static str)>,
+}
+
+impl<'ast> ContractCollector<'ast> {
+    fn unsupported(&mut self, attrs: &'ast [Attribute], reason: &'static str) {
+        for attr in attrs {
+            if contract_kind(attr).is_some() || is_contract_trait(attr) {
+                self.unsupported.push((attr, reason));
+            }
+        }
+    }
+}
+
+fn is_contract_trait(attr: &Attribute) -> bool {
+    let segments = &attr.path().segments;
+    segments
+        .last()
+        .is_some_and(|last| last.ident == "contract_trait")
+        && (segments.len() == 1 || segments[0].ident == "contracts")
+}
+
+impl<'ast> Visit<'ast> for ContractCollector<'ast> {
+    fn visit_item_fn(&mut self, node: &'ast ItemFn) {
+        self.fns.push(&node.attrs);
+        visit::visit_item_fn(self, node);
+    }
+
+    fn visit_impl_item_fn(&mut self, node: &'ast ImplItemFn) {
+        self.fns.push(&node.attrs);
+        visit::visit_impl_item_fn(self, node);
+    }
+
+    fn visit_trait_item_fn(&mut self, node: &'ast TraitItemFn) {
+        self.unsupported(
+            &node.attrs,
+            "contracts of trait methods need a `#[spec]` trait; convert them by hand",
+        );
+        visit::visit_trait_item_fn(self, node);
+    }
+
+    fn visit_item_trait(&mut self, node: &'ast ItemTrait) {
+        self.unsupported(
+            &node.attrs,
+            "contracts of traits need a `#[spec]` trait; convert them by hand",
+        );
+        visit::visit_item_trait(self, node);
+    }
+
+    fn visit_item_impl(&mut self, node: &'ast ItemImpl) {
+        self.unsupported(
+            &node.attrs,
+            "specs have no invariants of `impl` blocks; add them to each method by hand",
+        );
+        visit::visit_item_impl(self, node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_attributes() {
+        let source = r#"
+            /// Pops the last item.
+            #[requires(!self.is_empty(), "stack must not be empty")]
+            #[inline]
+            #[debug_invariant(self.len() <= self.capacity())]
+            #[contracts::ensures(ret.is_some() -> self.len() == old(self.len()) - 1)]
+            #[ensures(ret == old(self.peek().copied()))]
+            fn pop(&mut self) -> Option<u8> {
+                self.items.pop()
+            }
+            "#;
+        let migration = convert_file(source).unwrap();
+
+        assert_eq!(migration.migrated, 1);
+        assert!(migration.skipped.is_empty());
+        assert_eq!(
+            migration.source,
+            r#"
+            /// Pops the last item.
+            #[spec(
+                #[label = "stack must not be empty"]
+                requires: !self.is_empty(),
+                #[cfg(debug_assertions)]
+                maintains: self.len() <= self.capacity(),
+                captures: [self.len() as old_len, self.peek().copied() as old_peek_copied],
+                ensures: !(output.is_some()) || (self.len() == old_len - 1),
+                ensures: *output == old_peek_copied,
+            )]
+            #[inline]
+            fn pop(&mut self) -> Option<u8> {
+                self.items.pop()
+            }
+            "#
+        );
+    }
+
+    #[test]
+    fn test_nested_implications() {
+        let source = "#[ensures(a -> (b -> ret[0] > x.ret) -> c, \"message\")]\nfn f() {}\n";
+        let migration = convert_file(source).unwrap();
+
+        assert_eq!(
+            migration.source,
+            "#[spec(\n    #[label = \"message\"]\n    ensures: !(a) || (!((!(b) || (output[0] > x.ret))) || (c)),\n)]\nfn f() {}\n"
+        );
+    }
+
+    #[test]
+    fn test_skips_unsupported() {
+        let source = r#"
+            #[contract_trait]
+            trait Stack {
+                #[requires(!self.is_empty())]
+                fn pop(&mut self) -> u8;
+            }
+
+            #[spec(requires: x > 0)]
+            #[requires(x < 10)]
+            fn f(x: u32) {}
+
+            #[requires(x > old(x))]
+            fn g(x: u32) {}
+
+            #[ensures(ret > old(x))]
+            #[requires(old(x) < 100)]
+            fn h(x: u32) -> u32 { x + 1 }
+            "#;
+        let migration = convert_file(source).unwrap();
+
+        assert_eq!(migration.migrated, 0);
+        assert_eq!(migration.source, source);
+        let reasons = migration
+            .skipped
+            .iter()
+            .map(|skipped| (skipped.line, skipped.reason.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            reasons,
+            [
+                (
+                    2,
+                    "contracts of traits need a `#[spec]` trait; convert them by hand"
+                ),
+                (
+                    4,
+                    "contracts of trait methods need a `#[spec]` trait; convert them by hand"
+                ),
+                (9, "the function already has a `#[spec]`"),
+                (12, "`old(...)` is only allowed in postconditions"),
+                (15, "`old(...)` is only allowed in postconditions"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_precondition_after_capture() {
+        let source = "#[ensures(ret > old(*x))]\n#[requires(*x < 100)]\nfn f(x: &mut u32) -> u32 {\n    *x\n}\n";
+        let migration = convert_file(source).unwrap();
+
+        assert_eq!(migration.migrated, 1);
+        assert!(migration.skipped.is_empty());
+        assert_eq!(
+            migration.source,
+            "#[spec(\n    requires: *x < 100,\n    captures: *x as old_x,\n    ensures: *output > old_x,\n)]\nfn f(x: &mut u32) -> u32 {\n    *x\n}\n"
+        );
+    }
+}
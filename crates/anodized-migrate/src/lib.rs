@@ -0,0 +1,540 @@
+//! Anodized-migrate: migrates #[spec] annotations to and from other contract systems
+//!
+//! [`migrate_file`] replaces each `#[spec]` attribute on a function with the equivalent
+//! `#[core::contracts::requires]` and `#[core::contracts::ensures]` attributes,
+//! [`convert_file`] replaces the attributes of the `contracts` crate with a `#[spec]`, and
+//! [`lift_asserts`] moves the assertions at the start of functions into a `#[spec]`.
+//! All keep the source text of conditions and leave all other code unchanged, and leave
+//! every annotation they can't translate faithfully as it is. [`runtime_warning`] tells
+//! whether a crate's runtime behavior lets lifted assertions stop panicking.
+
+use std::fmt;
+
+use proc_macro2::{TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::{
+    Attribute, BinOp, Expr, ImplItemFn, ItemFn, ItemImpl, ItemTrait, Meta, Signature, parse_file,
+    spanned::Spanned,
+    visit::{self, Visit},
+};
+
+use anodized_core::{Capture, Spec, is_spec_attr, mut_ref_args};
+
+mod asserts;
+mod contracts;
+mod manifest;
+mod source_text;
+
+pub use asserts::lift_asserts;
+pub use contracts::convert_file;
+pub use manifest::runtime_warning;
+use source_text::{SourceText, apply_edits};
+
+/// The result of migrating a source file.
+#[derive(Debug)]
+pub struct Migration {
+    /// The source, with each translated annotation replaced.
+    pub source: String,
+    /// The number of functions whose annotations were translated.
+    pub migrated: usize,
+    /// The annotations that were left as they are.
+    pub skipped: Vec<Skipped>,
+}
+
+/// An annotation that was left as it is, and why.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Skipped {
+    /// The 1-based line of the attribute.
+    pub line: usize,
+    /// The 0-based column of the attribute.
+    pub column: usize,
+    /// What can't be translated.
+    pub reason: String,
+}
+
+impl fmt::Display for Skipped {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column + 1, self.reason)
+    }
+}
+
+/// Rewrite the `#[spec]` attributes of functions in a Rust source file into native contracts.
+///
+/// A function can have at most one native `requires` and one native `ensures`, so a spec is
+/// translated as follows:
+/// - the `requires` and `maintains` conditions are joined with `&&` into
+///   `#[core::contracts::requires(...)]`,
+/// - the `maintains` and `ensures` conditions are joined with `&&` into
+///   `#[core::contracts::ensures(|output| ...)]`, with the pattern of `binds:` as the
+///   closure's argument,
+/// - each `captures: expr as alias` used by a postcondition becomes a `let alias = expr;`,
+///   evaluated at entry before the closure is returned, as in
+///   `#[core::contracts::ensures({ let alias = expr; move |output| ... })]`,
+/// - a condition with `#[cfg(...)]` becomes `(!cfg!(...) || condition)`.
+///
+/// Runtime settings have no native equivalent, so a spec with a `mode`, or a condition
+/// with a label, `#[warn]`, `#[deny]`, `#[sample]`, or `#[expensive]`, is left as it is and
+/// reported. So are specs whose postconditions bind the return value with different
+/// patterns, specs on `async` functions, and the `#[spec]` of traits and their `impl`s.
+pub fn migrate_file(source: &str) -> syn::Result<Migration> {
+    let file = parse_file(source)?;
+    let mut collector = SpecCollector::default();
+    collector.visit_file(&file);
+
+    let text = SourceText::new(source);
+    let mut edits = Vec::new();
+    let mut skipped = Vec::new();
+    for found in collector.found {
+        let start = found.attr.span().start();
+        let indent = text.indent(start);
+        let contract = match found.sig {
+            None => Err(
+                "native contracts can't express the specs of a trait or its `impl`s".to_string(),
+            ),
+            Some(sig) => found
+                .attr
+                .parse_args::<Spec>()
+                .map_err(|error| format!("invalid spec: {error}"))
+                .and_then(|spec| translate(sig, &spec, &text, indent)),
+        };
+        match contract {
+            Ok(attrs) => {
+                let range = text.offset(start)..text.offset(found.attr.span().end());
+                edits.push((range, attrs.join(&format!("\n{indent}"))));
+            }
+            Err(reason) => skipped.push(Skipped {
+                line: start.line,
+                column: start.column,
+                reason,
+            }),
+        }
+    }
+
+    let migrated = edits.len();
+    Ok(Migration {
+        source: apply_edits(source, edits),
+        migrated,
+        skipped,
+    })
+}
+
+/// Translate a spec into native contract attributes, indented to follow the given
+/// indentation, or describe what can't be translated.
+fn translate(
+    sig: &Signature,
+    spec: &Spec,
+    text: &SourceText,
+    indent: &str,
+) -> Result<Vec<String>, String> {
+    if sig.asyncness.is_some() {
+        return Err("native contracts can't be added to `async` functions".to_string());
+    }
+    if let Some(mode) = spec.mode {
+        return Err(format!(
+            "native contracts have no runtime modes, but the spec has `mode: {}`",
+            mode.name()
+        ));
+    }
+    for condition in spec.requires.iter().chain(&spec.maintains) {
+        check_runtime_settings(
+            condition.label.is_some(),
+            condition.severity.is_some(),
+            condition.sample.is_some(),
+            condition.expensive,
+            &condition.closure.body,
+            text,
+        )?;
+    }
+    for postcondition in &spec.ensures {
+        check_runtime_settings(
+            postcondition.label.is_some(),
+            postcondition.severity.is_some(),
+            postcondition.sample.is_some(),
+            postcondition.expensive,
+            &postcondition.closure.body,
+            text,
+        )?;
+    }
+
+    // An ensures closure must be `Copy`, so it can't hold a mutable borrow to read an
+    // argument's value at exit.
+    let mut_refs = mut_ref_args(sig);
+    let post_bodies = spec
+        .maintains
+        .iter()
+        .map(|condition| condition.closure.body.as_ref())
+        .chain(spec.ensures.iter().map(|post| post.closure.body.as_ref()));
+    for body in post_bodies {
+        let mut idents = Vec::new();
+        collect_idents(body.to_token_stream(), &mut idents);
+        if let Some(name) = mut_refs
+            .iter()
+            .find(|name| idents.iter().any(|ident| *name == ident))
+        {
+            return Err(format!(
+                "native postconditions can't read the `&mut` argument `{name}`, but `{}` does",
+                text.of(body)
+            ));
+        }
+    }
+
+    // The postconditions share one closure, so they must bind the return value alike.
+    let mut patterns = spec
+        .ensures
+        .iter()
+        .filter_map(|post| post.closure.inputs.first());
+    if let Some(first) = patterns.next() {
+        let tokens = first.to_token_stream().to_string();
+        if let Some(other) = patterns.find(|pat| pat.to_token_stream().to_string() != tokens) {
+            return Err(format!(
+                "native contracts have a single postcondition closure, but the postconditions \
+                 bind the return value as both `{}` and `{}`",
+                text.of(first),
+                text.of(other)
+            ));
+        }
+    }
+
+    let requires = spec
+        .requires
+        .iter()
+        .chain(&spec.maintains)
+        .map(|condition| conjunct(condition.cfg.as_ref(), &condition.closure.body, text))
+        .collect::<Vec<_>>();
+    let ensures = spec
+        .maintains
+        .iter()
+        .map(|condition| conjunct(condition.cfg.as_ref(), &condition.closure.body, text))
+        .chain(spec.ensures.iter().map(|postcondition| {
+            conjunct(
+                postcondition.cfg.as_ref(),
+                &postcondition.closure.body,
+                text,
+            )
+        }))
+        .collect::<Vec<_>>();
+
+    let mut attrs = Vec::new();
+    if !requires.is_empty() {
+        let condition = conjunction(&requires, indent);
+        attrs.push(if requires.len() == 1 {
+            format!("#[core::contracts::requires({condition})]")
+        } else {
+            format!("#[core::contracts::requires(\n{indent}    {condition}\n{indent})]")
+        });
+    }
+    if !ensures.is_empty() {
+        let pattern = match spec
+            .ensures
+            .first()
+            .and_then(|post| post.closure.inputs.first())
+        {
+            Some(pat) => text.of(pat),
+            None => "_".to_string(),
+        };
+        let condition = conjunction(&ensures, indent);
+        let bodies = spec.ensures.iter().map(|post| post.closure.body.as_ref());
+        let captures = used_captures(&spec.captures, bodies);
+        attrs.push(if !captures.is_empty() {
+            let lets = captures
+                .iter()
+                .map(|capture| {
+                    let (pat, expr) = (text.of(&capture.pat), text.of(&capture.expr));
+                    format!("{indent}    let {pat} = {expr};\n")
+                })
+                .collect::<String>();
+            format!(
+                "#[core::contracts::ensures({{\n{lets}{indent}    move |{pattern}| {condition}\n{indent}}})]"
+            )
+        } else if ensures.len() == 1 {
+            format!("#[core::contracts::ensures(move |{pattern}| {condition})]")
+        } else {
+            format!("#[core::contracts::ensures(\n{indent}    move |{pattern}| {condition}\n{indent})]")
+        });
+    }
+    Ok(attrs)
+}
+
+/// Report the first runtime setting of a condition, which native contracts can't express.
+fn check_runtime_settings(
+    label: bool,
+    severity: bool,
+    sample: bool,
+    expensive: bool,
+    condition: &Expr,
+    text: &SourceText,
+) -> Result<(), String> {
+    let setting = [
+        (label, "a label"),
+        (severity, "`#[warn]` or `#[deny]`"),
+        (sample, "`#[sample]`"),
+        (expensive, "`#[expensive]`"),
+    ]
+    .into_iter()
+    .find_map(|(present, setting)| present.then_some(setting));
+    match setting {
+        Some(setting) => Err(format!(
+            "native contracts have no runtime settings, but `{}` has {setting}",
+            text.of(condition)
+        )),
+        None => Ok(()),
+    }
+}
+
+/// The text of a condition guarded by its `#[cfg]`, if any, and whether it needs
+/// parentheses as an operand of `&&`.
+fn conjunct(cfg: Option<&Meta>, condition: &Expr, text: &SourceText) -> (String, bool) {
+    let condition_text = text.of(condition);
+    match cfg {
+        Some(cfg) => (format!("!cfg!({}) || {condition_text}", text.of(cfg)), true),
+        None => (condition_text, binds_looser_than_and(condition)),
+    }
+}
+
+/// Joins conditions with `&&`, one per line after the first.
+fn conjunction(conditions: &[(String, bool)], indent: &str) -> String {
+    if let [(condition, _)] = conditions {
+        return condition.clone();
+    }
+    conditions
+        .iter()
+        .map(|(condition, needs_parens)| match needs_parens {
+            true => format!("({condition})"),
+            false => condition.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(&format!("\n{indent}        && "))
+}
+
+/// Whether an expression needs parentheses as an operand of `&&`.
+fn binds_looser_than_and(expr: &Expr) -> bool {
+    match expr {
+        Expr::Binary(binary) => matches!(binary.op, BinOp::Or(_)),
+        Expr::Range(_)
+        | Expr::Assign(_)
+        | Expr::Closure(_)
+        | Expr::Return(_)
+        | Expr::Break(_)
+        | Expr::Yield(_) => true,
+        _ => false,
+    }
+}
+
+/// The captures whose names appear in any of the postconditions, in the order of the spec.
+fn used_captures<'a, 'e>(
+    captures: &'a [Capture],
+    bodies: impl Iterator<Item = &'e Expr>,
+) -> Vec<&'a Capture> {
+    let mut idents = Vec::new();
+    for body in bodies {
+        collect_idents(body.to_token_stream(), &mut idents);
+    }
+    captures
+        .iter()
+        .filter(|capture| {
+            let mut bound = Vec::new();
+            collect_idents(capture.pat.to_token_stream(), &mut bound);
+            bound.iter().any(|ident| idents.contains(ident))
+        })
+        .collect()
+}
+
+fn collect_idents(tokens: TokenStream, idents: &mut Vec<String>) {
+    for token in tokens {
+        match token {
+            TokenTree::Ident(ident) => idents.push(ident.to_string()),
+            TokenTree::Group(group) => collect_idents(group.stream(), idents),
+            _ => {}
+        }
+    }
+}
+
+/// A `#[spec]` attribute, with the signature of its function, if it's on one.
+struct FoundSpec<'ast> {
+    attr: &'ast Attribute,
+    sig: Option<&'ast Signature>,
+}
+
+/// Visitor that collects the `#[spec]` attributes of a file, without looking inside items
+/// whose `#[spec]` can't be translated.
+#[derive(Default)]
+struct SpecCollector<'ast> {
+    found: Vec<FoundSpec<'ast>>,
+}
+
+impl<'ast> SpecCollector<'ast> {
+    /// Collect the `#[spec]` attributes among the given ones, returning whether there were any.
+    fn collect(&mut self, attrs: &'ast [Attribute], sig: Option<&'ast Signature>) -> bool {
+        let mut any = false;
+        for attr in attrs.iter().filter(|attr| is_spec_attr(attr)) {
+            self.found.push(FoundSpec { attr, sig });
+            any = true;
+        }
+        any
+    }
+}
+
+impl<'ast> Visit<'ast> for SpecCollector<'ast> {
+    fn visit_item_fn(&mut self, node: &'ast ItemFn) {
+        self.collect(&node.attrs, Some(&node.sig));
+        visit::visit_item_fn(self, node);
+    }
+
+    fn visit_impl_item_fn(&mut self, node: &'ast ImplItemFn) {
+        self.collect(&node.attrs, Some(&node.sig));
+        visit::visit_impl_item_fn(self, node);
+    }
+
+    fn visit_item_trait(&mut self, node: &'ast ItemTrait) {
+        if !self.collect(&node.attrs, None) {
+            visit::visit_item_trait(self, node);
+        }
+    }
+
+    fn visit_item_impl(&mut self, node: &'ast ItemImpl) {
+        if !self.collect(&node.attrs, None) {
+            visit::visit_item_impl(self, node);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keeps_source_text() {
+        let source = r#"
+            #[spec(
+                requires: x > 0 && y.is_some(),
+                binds: (a, b),
+                ensures: *a >= x,
+            )]
+            fn split(x: u32, y: Option<u32>) -> (u32, u32) {
+                (x, y.unwrap())
+            }
+            "#;
+        let migration = migrate_file(source).unwrap();
+
+        assert_eq!(migration.migrated, 1);
+        assert!(migration.skipped.is_empty());
+        assert_eq!(
+            migration.source,
+            r#"
+            #[core::contracts::requires(x > 0 && y.is_some())]
+            #[core::contracts::ensures(move |(a, b)| *a >= x)]
+            fn split(x: u32, y: Option<u32>) -> (u32, u32) {
+                (x, y.unwrap())
+            }
+            "#
+        );
+    }
+
+    #[test]
+    fn test_captures() {
+        let source = r#"
+            impl Stack {
+                #[spec(
+                    captures: [self.len() as len, self.capacity() as capacity],
+                    #[cfg(test)]
+                    ensures: [*output == len + 1, *output > 0],
+                )]
+                fn push(&mut self, item: u8) -> usize {}
+            }
+            "#;
+        let migration = migrate_file(source).unwrap();
+
+        assert_eq!(
+            migration.source,
+            r#"
+            impl Stack {
+                #[core::contracts::ensures({
+                    let len = self.len();
+                    move |output| (!cfg!(test) || *output == len + 1)
+                        && (!cfg!(test) || *output > 0)
+                })]
+                fn push(&mut self, item: u8) -> usize {}
+            }
+            "#
+        );
+    }
+
+    #[test]
+    fn test_skips_different_bindings() {
+        let source = r#"
+            #[spec(
+                ensures: [*output > 0, |r| *r < 10],
+            )]
+            fn digit() -> u32 {}
+            "#;
+        let migration = migrate_file(source).unwrap();
+
+        assert_eq!(migration.migrated, 0);
+        assert_eq!(migration.source, source);
+        assert_eq!(
+            migration.skipped[0].reason,
+            "native contracts have a single postcondition closure, but the postconditions \
+             bind the return value as both `output` and `r`"
+        );
+    }
+
+    #[test]
+    fn test_skips_final_values() {
+        let source = r#"
+            #[spec(
+                captures: *total as initial,
+                ensures: *total == initial + 1,
+            )]
+            fn increment(total: &mut u32) {}
+            "#;
+        let migration = migrate_file(source).unwrap();
+
+        assert_eq!(migration.source, source);
+        assert_eq!(
+            migration.skipped,
+            [Skipped {
+                line: 2,
+                column: 12,
+                reason: "native postconditions can't read the `&mut` argument `total`, but \
+                         `*total == initial + 1` does"
+                    .to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_skips_runtime_settings() {
+        let source = r#"
+            #[spec(
+                #[warn]
+                requires: x > 0,
+            )]
+            fn f(x: u32) {}
+
+            #[spec]
+            trait Shape {}
+            "#;
+        let migration = migrate_file(source).unwrap();
+
+        assert_eq!(migration.migrated, 0);
+        assert_eq!(migration.source, source);
+        assert_eq!(
+            migration.skipped,
+            [
+                Skipped {
+                    line: 2,
+                    column: 12,
+                    reason: "native contracts have no runtime settings, but `x > 0` has \
+                             `#[warn]` or `#[deny]`"
+                        .to_string(),
+                },
+                Skipped {
+                    line: 8,
+                    column: 12,
+                    reason: "native contracts can't express the specs of a trait or its `impl`s"
+                        .to_string(),
+                },
+            ]
+        );
+    }
+}
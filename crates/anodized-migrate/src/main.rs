@@ -0,0 +1,172 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use anodized_migrate::{Migration, convert_file, lift_asserts, migrate_file, runtime_warning};
+use clap::Parser;
+use walkdir::WalkDir;
+
+#[derive(Parser)]
+#[command(
+    name = "anodized-migrate",
+    version,
+    about = "Migrate #[spec] annotations to Rust-native contracts",
+    long_about = "Rewrites the #[spec] attributes of functions into #[core::contracts::requires] and #[core::contracts::ensures] attributes, leaving all other code unchanged, and reports every spec that can't be translated. With --from-contracts, rewrites the attributes of the contracts crate into #[spec] instead, and with --from-asserts, lifts the assertions at the start of functions into #[spec], warning if the crate's runtime behavior doesn't panic on a violated precondition as they did."
+)]
+struct Cli {
+    /// Files or directories to migrate (default: current directory)
+    #[arg(value_name = "PATH")]
+    paths: Vec<PathBuf>,
+
+    /// Report what would be migrated without modifying any files
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Convert the attributes of the `contracts` crate into #[spec] instead
+    #[arg(long)]
+    from_contracts: bool,
+
+    /// Lift the assertions at the start of functions into #[spec] instead
+    #[arg(long, conflicts_with = "from_contracts")]
+    from_asserts: bool,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let paths = if cli.paths.is_empty() {
+        vec![PathBuf::from(".")]
+    } else {
+        cli.paths
+    };
+
+    let mut rust_files = Vec::new();
+    for path in paths {
+        if path.is_file() {
+            rust_files.push(path);
+        } else {
+            for entry in WalkDir::new(&path)
+                .sort_by_file_name()
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .filter(|e| e.path().extension().is_some_and(|ext| ext == "rs"))
+            {
+                rust_files.push(entry.into_path());
+            }
+        }
+    }
+
+    // The rewrite, its verb when done and when planned, and what it rewrites and reports.
+    let (rewrite, done, planned, unit, annotations): (Rewrite, _, _, _, _) = if cli.from_contracts {
+        (
+            convert_file,
+            "Converted",
+            "Would convert",
+            "function(s)",
+            "contract(s)",
+        )
+    } else if cli.from_asserts {
+        (
+            lift_asserts,
+            "Lifted",
+            "Would lift",
+            "function(s)",
+            "assertion(s)",
+        )
+    } else {
+        (
+            migrate_file,
+            "Migrated",
+            "Would migrate",
+            "spec(s)",
+            "spec(s)",
+        )
+    };
+
+    let mut migrated = 0;
+    let mut skipped = 0;
+    let mut failed = false;
+    // The manifests of the crates with lifted assertions.
+    let mut manifests = BTreeSet::new();
+    for path in &rust_files {
+        match migrate(path, rewrite, unit, cli.dry_run) {
+            Ok((file_migrated, file_skipped)) => {
+                migrated += file_migrated;
+                skipped += file_skipped;
+                if cli.from_asserts && file_migrated > 0 {
+                    manifests.extend(manifest_of(path));
+                }
+            }
+            Err(error) => {
+                eprintln!("error: {error}");
+                failed = true;
+            }
+        }
+    }
+    for manifest in &manifests {
+        let warning = fs::read_to_string(manifest)
+            .map_err(|error| error.to_string())
+            .and_then(|text| runtime_warning(&text).map_err(|error| error.to_string()));
+        match warning {
+            Ok(None) => {}
+            Ok(Some(warning)) => eprintln!("warning: {}: {warning}", manifest.display()),
+            Err(error) => {
+                eprintln!("error: cannot read {}: {error}", manifest.display());
+                failed = true;
+            }
+        }
+    }
+
+    let verb = if cli.dry_run { planned } else { done };
+    eprintln!("{verb} {migrated} {unit}, left {skipped} {annotations} unchanged");
+    if failed || skipped > 0 {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// The manifest of the crate that a source file belongs to, if any.
+fn manifest_of(path: &Path) -> Option<PathBuf> {
+    let path = fs::canonicalize(path).ok()?;
+    path.ancestors()
+        .map(|dir| dir.join("Cargo.toml"))
+        .find(|manifest| manifest.is_file())
+}
+
+/// Rewrites the annotations of a source file.
+type Rewrite = fn(&str) -> syn::Result<Migration>;
+
+/// Rewrite a single file, returning the numbers of functions rewritten and annotations left
+/// as they are.
+fn migrate(
+    path: &Path,
+    rewrite: Rewrite,
+    unit: &str,
+    dry_run: bool,
+) -> Result<(usize, usize), String> {
+    let source = fs::read_to_string(path)
+        .map_err(|error| format!("cannot read {}: {error}", path.display()))?;
+    let migration = rewrite(&source).map_err(|error| {
+        let start = error.span().start();
+        format!(
+            "{}:{}:{}: {error}",
+            path.display(),
+            start.line,
+            start.column + 1
+        )
+    })?;
+
+    if migration.migrated > 0 {
+        println!("{}: {} {unit}", path.display(), migration.migrated);
+        if !dry_run {
+            fs::write(path, &migration.source)
+                .map_err(|error| format!("cannot write {}: {error}", path.display()))?;
+        }
+    }
+    for skipped in &migration.skipped {
+        eprintln!("warning: {}:{skipped}", path.display());
+    }
+    Ok((migration.migrated, migration.skipped.len()))
+}
@@ -0,0 +1,121 @@
+//! Reading the runtime behavior that a crate's manifest selects for its specs.
+
+use toml::{Table, Value};
+
+/// The runtime behavior under which a violated precondition panics, as an assertion does.
+const PANIC_RUNTIME: &str = "check-and-panic";
+
+/// The runtime behaviors, in the order in which `anodized` picks among several enabled
+/// `runtime-*` features.
+const RUNTIMES: [&str; 6] = [
+    "check-and-panic",
+    "check-and-trap",
+    "check-and-print",
+    "check-and-log",
+    "check-and-trace",
+    "no-check",
+];
+
+/// A warning if the manifest of a crate selects a runtime behavior under which a violated
+/// precondition doesn't panic, so that lifting assertions into `requires` changes what
+/// the crate does.
+///
+/// The `runtime` key of `[package.metadata.anodized]` takes precedence over the `runtime-*`
+/// features of the crate's dependency on `anodized`. If neither is there, the behavior is
+/// selected elsewhere in the dependency graph, and there's nothing to warn about here.
+pub fn runtime_warning(manifest: &str) -> Result<Option<String>, toml::de::Error> {
+    let manifest: Table = manifest.parse()?;
+    let metadata = manifest
+        .get("package")
+        .and_then(|package| package.get("metadata"))
+        .and_then(|metadata| metadata.get("anodized"))
+        .and_then(|anodized| anodized.get("runtime"));
+    let runtimes = match metadata {
+        Some(Value::String(name)) => vec![name.as_str()],
+        Some(Value::Table(per_build)) => per_build.values().filter_map(Value::as_str).collect(),
+        _ => {
+            let features = manifest
+                .get("dependencies")
+                .and_then(|dependencies| dependencies.get("anodized"))
+                .and_then(|anodized| anodized.get("features"))
+                .and_then(Value::as_array)
+                .map_or(&[][..], Vec::as_slice);
+            let enabled = |name: &&str| {
+                features
+                    .iter()
+                    .any(|feature| feature.as_str() == Some(&format!("runtime-{name}")))
+            };
+            RUNTIMES.iter().copied().find(enabled).into_iter().collect()
+        }
+    };
+
+    let others = runtimes
+        .into_iter()
+        .filter(|name| *name != PANIC_RUNTIME)
+        .collect::<Vec<_>>();
+    if others.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(format!(
+        "the crate checks specs with `{}`, so a violated precondition lifted from an \
+         assertion no longer panics",
+        others.join("` and `")
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runtime_from_metadata() {
+        let manifest = r#"
+            [package]
+            name = "buffer"
+
+            [package.metadata.anodized]
+            runtime = { debug = "check-and-panic", release = "no-check" }
+
+            [dependencies]
+            anodized = { version = "0.3", features = ["runtime-check-and-panic"] }
+            "#;
+
+        assert_eq!(
+            runtime_warning(manifest).unwrap().unwrap(),
+            "the crate checks specs with `no-check`, so a violated precondition lifted from an \
+             assertion no longer panics"
+        );
+    }
+
+    #[test]
+    fn test_runtime_from_features() {
+        let manifest = |features: &str| {
+            format!(
+                r#"
+                [package]
+                name = "buffer"
+
+                [dependencies.anodized]
+                version = "0.3"
+                features = [{features}]
+                "#
+            )
+        };
+
+        assert_eq!(
+            runtime_warning(&manifest(r#""runtime-check-and-log""#))
+                .unwrap()
+                .unwrap(),
+            "the crate checks specs with `check-and-log`, so a violated precondition lifted \
+             from an assertion no longer panics"
+        );
+        assert_eq!(
+            runtime_warning(&manifest(
+                r#""runtime-check-and-print", "runtime-check-and-panic""#
+            ))
+            .unwrap(),
+            None
+        );
+        assert_eq!(runtime_warning(&manifest("")).unwrap(), None);
+    }
+}
@@ -0,0 +1,102 @@
+//! Positions and text in a source file, and edits of it.
+
+use std::ops::Range;
+
+use proc_macro2::{LineColumn, Span, TokenStream};
+use quote::ToTokens;
+use syn::spanned::Spanned;
+
+/// The source of a file, for copying the text of expressions and patterns.
+pub(crate) struct SourceText<'a> {
+    source: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> SourceText<'a> {
+    pub(crate) fn new(source: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+        Self {
+            source,
+            line_starts,
+        }
+    }
+
+    /// The whole source.
+    pub(crate) fn source(&self) -> &'a str {
+        self.source
+    }
+
+    /// The byte offset of a line and column, where the column counts characters.
+    pub(crate) fn offset(&self, position: LineColumn) -> usize {
+        let Some(&line_start) = self.line_starts.get(position.line.wrapping_sub(1)) else {
+            return self.source.len();
+        };
+        self.source[line_start..]
+            .char_indices()
+            .nth(position.column)
+            .map_or(self.source.len(), |(index, _)| line_start + index)
+    }
+
+    /// The whitespace at the start of a line.
+    pub(crate) fn indent(&self, position: LineColumn) -> &'a str {
+        let line_start = self.offset(LineColumn {
+            line: position.line,
+            column: 0,
+        });
+        let line = &self.source[line_start..];
+        &line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+    }
+
+    /// The source text of a syntax node, or its tokens if it doesn't come from the source,
+    /// e.g. the default `output` pattern.
+    pub(crate) fn of(&self, node: &(impl ToTokens + Spanned)) -> String {
+        let tokens = node.to_token_stream().to_string();
+        let span = node.span();
+        let (start, end) = (self.offset(span.start()), self.offset(span.end()));
+        match self.source.get(start..end) {
+            Some(text)
+                if text
+                    .parse::<TokenStream>()
+                    .is_ok_and(|parsed| parsed.to_string() == tokens) =>
+            {
+                text.to_string()
+            }
+            _ => tokens,
+        }
+    }
+}
+
+impl SourceText<'_> {
+    /// The byte range of a span.
+    pub(crate) fn range(&self, span: Span) -> Range<usize> {
+        self.offset(span.start())..self.offset(span.end())
+    }
+
+    /// The byte range of a span, extended to whole lines if nothing else is on them.
+    pub(crate) fn line_range(&self, span: Span) -> Range<usize> {
+        let Range { mut start, mut end } = self.range(span);
+        let before = &self.source[..start];
+        let after = &self.source[end..];
+        let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+        let line_end = after
+            .find('\n')
+            .map_or(self.source.len(), |index| end + index + 1);
+        if before[line_start..].trim().is_empty() && after[..line_end - end].trim().is_empty() {
+            (start, end) = (line_start, line_end);
+        }
+        start..end
+    }
+}
+
+/// Replaces ranges of a text; ranges must not overlap, except that insertions (empty
+/// ranges) may be at the start or end of another range.
+pub(crate) fn apply_edits(text: &str, mut edits: Vec<(Range<usize>, String)>) -> String {
+    edits.sort_by_key(|(range, _)| (range.start, range.end));
+    let mut text = text.to_string();
+    for (range, replacement) in edits.into_iter().rev() {
+        text.replace_range(range, &replacement);
+    }
+    text
+}
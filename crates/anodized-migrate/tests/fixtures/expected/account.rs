@@ -0,0 +1,59 @@
+use anodized::spec;
+
+pub struct Account {
+    balance: u64,
+    limit: u64,
+}
+
+/// Splits `total` into a part of at most `limit` and the rest.
+#[core::contracts::requires(limit > 0)]
+#[core::contracts::ensures(
+    move |(part, rest)| (*part <= limit || *rest == 0)
+        && *part + *rest == total
+)]
+pub fn split(total: u64, limit: u64) -> (u64, u64) {
+    let part = total.min(limit);
+    (part, total - part)
+}
+
+impl Account {
+    /// Withdraws `amount`, returning the new balance.
+    #[core::contracts::requires(
+        amount <= self.balance
+            && self.balance <= self.limit
+    )]
+    #[core::contracts::ensures({
+        let initial = self.balance;
+        move |new_balance| *new_balance == initial - amount
+    })]
+    pub fn withdraw(&mut self, amount: u64) -> u64 {
+        self.balance -= amount;
+        self.balance
+    }
+
+    #[core::contracts::requires(!cfg!(debug_assertions) || amount > 0)]
+    #[core::contracts::ensures(move |output| *output)]
+    pub fn deposit(&mut self, amount: u64) -> bool {
+        self.balance += amount;
+        self.balance <= self.limit
+    }
+
+    #[spec(
+        maintains: self.balance <= self.limit,
+    )]
+    pub fn reset(&mut self) {
+        self.balance = 0;
+    }
+
+    #[spec(
+        #[label = "not empty"]
+        requires: self.balance > 0,
+    )]
+    pub fn close(self) {}
+}
+
+#[spec]
+pub trait Ledger {
+    #[spec(requires: amount > 0)]
+    fn record(&mut self, amount: u64);
+}
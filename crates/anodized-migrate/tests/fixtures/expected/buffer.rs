@@ -0,0 +1,42 @@
+pub const CAPACITY: usize = 64;
+
+pub struct Buffer {
+    bytes: Vec<u8>,
+}
+
+impl Buffer {
+    /// Creates a buffer of the given length.
+    #[spec(
+        #[label = "buffer too large"]
+        requires: len <= CAPACITY,
+    )]
+    pub fn new(len: usize) -> Self {
+        Self {
+            bytes: vec![0; len],
+        }
+    }
+
+    #[inline]
+    #[spec(
+        #[cfg(debug_assertions)]
+        requires: !data.is_empty(),
+        requires: offset + data.len() <= self.bytes.len(),
+    )]
+    pub fn write(&mut self, offset: usize, data: &[u8]) {
+        self.bytes[offset..offset + data.len()].copy_from_slice(data);
+    }
+
+    pub fn resize(&mut self, len: usize) {
+        assert!(len <= CAPACITY, "cannot grow past {CAPACITY}");
+        self.bytes.resize(len, 0);
+    }
+}
+
+#[spec(
+    requires: a.bytes.len() == b.bytes.len(),
+)]
+pub fn checksum(a: &Buffer, b: &Buffer) -> u8 {
+    let len = a.bytes.len();
+    assert!(len > 0);
+    a.bytes.iter().zip(&b.bytes).fold(0, |sum, (x, y)| sum ^ x ^ y)
+}
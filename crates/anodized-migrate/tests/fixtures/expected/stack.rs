@@ -0,0 +1,57 @@
+use contracts::*;
+
+pub struct Stack {
+    items: Vec<u32>,
+    capacity: usize,
+}
+
+impl Stack {
+    /// Creates an empty stack.
+    #[spec(
+        #[label = "a stack must hold at least one item"]
+        requires: capacity > 0,
+        ensures: output.capacity == capacity,
+    )]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            items: Vec::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    #[spec(
+        requires: self.items.len() < self.capacity,
+        #[cfg(debug_assertions)]
+        maintains: self.items.len() <= self.capacity,
+        captures: self.items.len() as old_items_len,
+        ensures: self.items.len() == old_items_len + 1,
+    )]
+    pub fn push(&mut self, item: u32) {
+        self.items.push(item);
+    }
+
+    #[inline]
+    #[spec(
+        captures: self.items.len() as old_items_len,
+        ensures: !(output.is_some()) || (self.items.len() == old_items_len - 1),
+    )]
+    pub fn pop(&mut self) -> Option<u32> {
+        self.items.pop()
+    }
+}
+
+#[contract_trait]
+pub trait Sized {
+    #[ensures(ret <= self.capacity())]
+    fn len(&self) -> usize;
+
+    fn capacity(&self) -> usize;
+}
+
+#[spec(
+    #[cfg(test)]
+    ensures: *output >= a && *output >= b,
+)]
+pub fn max(a: u32, b: u32) -> u32 {
+    a.max(b)
+}
@@ -0,0 +1,66 @@
+use anodized::spec;
+
+pub struct Account {
+    balance: u64,
+    limit: u64,
+}
+
+/// Splits `total` into a part of at most `limit` and the rest.
+#[spec(
+    requires: limit > 0,
+    binds: (part, rest),
+    ensures: [
+        *part <= limit || *rest == 0,
+        *part + *rest == total,
+    ],
+)]
+pub fn split(total: u64, limit: u64) -> (u64, u64) {
+    let part = total.min(limit);
+    (part, total - part)
+}
+
+impl Account {
+    /// Withdraws `amount`, returning the new balance.
+    #[spec(
+        requires: [
+            amount <= self.balance,
+            self.balance <= self.limit,
+        ],
+        captures: self.balance as initial,
+        binds: new_balance,
+        ensures: *new_balance == initial - amount,
+    )]
+    pub fn withdraw(&mut self, amount: u64) -> u64 {
+        self.balance -= amount;
+        self.balance
+    }
+
+    #[spec(
+        #[cfg(debug_assertions)]
+        requires: amount > 0,
+        ensures: *output,
+    )]
+    pub fn deposit(&mut self, amount: u64) -> bool {
+        self.balance += amount;
+        self.balance <= self.limit
+    }
+
+    #[spec(
+        maintains: self.balance <= self.limit,
+    )]
+    pub fn reset(&mut self) {
+        self.balance = 0;
+    }
+
+    #[spec(
+        #[label = "not empty"]
+        requires: self.balance > 0,
+    )]
+    pub fn close(self) {}
+}
+
+#[spec]
+pub trait Ledger {
+    #[spec(requires: amount > 0)]
+    fn record(&mut self, amount: u64);
+}
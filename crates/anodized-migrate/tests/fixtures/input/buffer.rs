@@ -0,0 +1,35 @@
+pub const CAPACITY: usize = 64;
+
+pub struct Buffer {
+    bytes: Vec<u8>,
+}
+
+impl Buffer {
+    /// Creates a buffer of the given length.
+    pub fn new(len: usize) -> Self {
+        assert!(len <= CAPACITY, "buffer too large");
+
+        Self {
+            bytes: vec![0; len],
+        }
+    }
+
+    #[inline]
+    pub fn write(&mut self, offset: usize, data: &[u8]) {
+        debug_assert!(!data.is_empty());
+        assert!(offset + data.len() <= self.bytes.len());
+        self.bytes[offset..offset + data.len()].copy_from_slice(data);
+    }
+
+    pub fn resize(&mut self, len: usize) {
+        assert!(len <= CAPACITY, "cannot grow past {CAPACITY}");
+        self.bytes.resize(len, 0);
+    }
+}
+
+pub fn checksum(a: &Buffer, b: &Buffer) -> u8 {
+    assert_eq!(a.bytes.len(), b.bytes.len());
+    let len = a.bytes.len();
+    assert!(len > 0);
+    a.bytes.iter().zip(&b.bytes).fold(0, |sum, (x, y)| sum ^ x ^ y)
+}
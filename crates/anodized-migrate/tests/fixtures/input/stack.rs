@@ -0,0 +1,44 @@
+use contracts::*;
+
+pub struct Stack {
+    items: Vec<u32>,
+    capacity: usize,
+}
+
+impl Stack {
+    /// Creates an empty stack.
+    #[requires(capacity > 0, "a stack must hold at least one item")]
+    #[ensures(ret.capacity == capacity)]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            items: Vec::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    #[debug_invariant(self.items.len() <= self.capacity)]
+    #[requires(self.items.len() < self.capacity)]
+    #[ensures(self.items.len() == old(self.items.len()) + 1)]
+    pub fn push(&mut self, item: u32) {
+        self.items.push(item);
+    }
+
+    #[inline]
+    #[contracts::ensures(ret.is_some() -> self.items.len() == old(self.items.len()) - 1)]
+    pub fn pop(&mut self) -> Option<u32> {
+        self.items.pop()
+    }
+}
+
+#[contract_trait]
+pub trait Sized {
+    #[ensures(ret <= self.capacity())]
+    fn len(&self) -> usize;
+
+    fn capacity(&self) -> usize;
+}
+
+#[test_ensures(ret >= a && ret >= b)]
+pub fn max(a: u32, b: u32) -> u32 {
+    a.max(b)
+}
@@ -0,0 +1,142 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// Copy an input fixture to a fresh directory, so that the tool can rewrite it.
+fn copy_fixture(test: &str, fixture: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(test);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(fixture);
+    fs::copy(
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/input")
+            .join(fixture),
+        &path,
+    )
+    .unwrap();
+    path
+}
+
+fn migrate(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_anodized-migrate"))
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .expect("failed to run anodized-migrate")
+}
+
+const REPORT: &str = "\
+warning: ./account.rs:48:5: native postconditions can't read the `&mut` argument `self`, but `self.balance <= self.limit` does
+warning: ./account.rs:55:5: native contracts have no runtime settings, but `self.balance > 0` has a label
+warning: ./account.rs:62:1: native contracts can't express the specs of a trait or its `impl`s
+";
+
+#[test]
+fn migrate_directory() {
+    let path = copy_fixture("migrate_directory", "account.rs");
+    let output = migrate(path.parent().unwrap(), &[]);
+
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "./account.rs: 3 spec(s)\n"
+    );
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        format!("{REPORT}Migrated 3 spec(s), left 3 spec(s) unchanged\n")
+    );
+
+    let expected = include_str!("fixtures/expected/account.rs");
+    assert_eq!(fs::read_to_string(path).unwrap(), expected);
+}
+
+#[test]
+fn dry_run() {
+    let path = copy_fixture("dry_run", "account.rs");
+    let output = migrate(path.parent().unwrap(), &["--dry-run"]);
+
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        format!("{REPORT}Would migrate 3 spec(s), left 3 spec(s) unchanged\n")
+    );
+
+    let input = include_str!("fixtures/input/account.rs");
+    assert_eq!(fs::read_to_string(path).unwrap(), input);
+}
+
+#[test]
+fn from_contracts() {
+    let path = copy_fixture("from_contracts", "stack.rs");
+    let output = migrate(path.parent().unwrap(), &["--from-contracts"]);
+
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "./stack.rs: 4 function(s)\n"
+    );
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "\
+warning: ./stack.rs:33:1: contracts of traits need a `#[spec]` trait; convert them by hand
+warning: ./stack.rs:35:5: contracts of trait methods need a `#[spec]` trait; convert them by hand
+Converted 4 function(s), left 2 contract(s) unchanged
+"
+    );
+
+    let expected = include_str!("fixtures/expected/stack.rs");
+    assert_eq!(fs::read_to_string(path).unwrap(), expected);
+}
+
+#[test]
+fn from_asserts() {
+    let path = copy_fixture("from_asserts", "buffer.rs");
+    let output = migrate(path.parent().unwrap(), &["--from-asserts"]);
+
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "./buffer.rs: 3 function(s)\n"
+    );
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "\
+warning: ./buffer.rs:25:9: the message of `len <= CAPACITY` has format arguments, which a label can't have
+Lifted 3 function(s), left 1 assertion(s) unchanged
+"
+    );
+
+    let expected = include_str!("fixtures/expected/buffer.rs");
+    assert_eq!(fs::read_to_string(path).unwrap(), expected);
+}
+
+#[test]
+fn from_asserts_without_panic_runtime() {
+    let path = copy_fixture("from_asserts_without_panic_runtime", "buffer.rs");
+    let dir = path.parent().unwrap();
+    fs::write(
+        dir.join("Cargo.toml"),
+        "\
+[package]
+name = \"buffer\"
+
+[package.metadata.anodized]
+runtime = \"check-and-print\"
+",
+    )
+    .unwrap();
+    let output = migrate(dir, &["--from-asserts"]);
+
+    let manifest = fs::canonicalize(dir).unwrap().join("Cargo.toml");
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        format!(
+            "\
+warning: ./buffer.rs:25:9: the message of `len <= CAPACITY` has format arguments, which a label can't have
+warning: {}: the crate checks specs with `check-and-print`, so a violated precondition lifted from an assertion no longer panics
+Lifted 3 function(s), left 1 assertion(s) unchanged
+",
+            manifest.display()
+        )
+    );
+}
@@ -0,0 +1,92 @@
+//! Control of the runtime checks while the program runs.
+//!
+//! Checks are enabled unless turned off by one of the following:
+//! - The `ANODIZED_CHECKS` environment variable, read on the first check:
+//!   `0`, `off`, `false`, or `no` turns checks off, any other value keeps them on.
+//! - [`set_enabled`], which overrides the environment variable, for all threads.
+//! - [`without_checks`], for the duration of a closure, on the current thread.
+//!
+//! Only crates that turn on `runtime-control` in `[package.metadata.anodized]` consult
+//! these switches, and conditions marked with `#[deny]` are always checked.
+//!
+//! Without the `std` feature, only [`set_enabled`] is available, and checks are on
+//! until it turns them off.
+
+use core::sync::atomic::{AtomicU8, Ordering};
+#[cfg(feature = "std")]
+use std::{cell::Cell, thread_local};
+
+const UNSET: u8 = 0;
+const ENABLED: u8 = 1;
+const DISABLED: u8 = 2;
+
+/// Whether checks are enabled for all threads, or `UNSET` before it's first needed.
+static STATE: AtomicU8 = AtomicU8::new(UNSET);
+
+#[cfg(feature = "std")]
+thread_local! {
+    /// The number of [`without_checks`] calls in progress on this thread.
+    static SUPPRESSED: Cell<u32> = const { Cell::new(0) };
+}
+
+/// Turns checks on or off for all threads.
+pub fn set_enabled(enabled: bool) {
+    STATE.store(if enabled { ENABLED } else { DISABLED }, Ordering::Relaxed);
+}
+
+/// Returns whether checks are on for all threads, regardless of [`without_checks`].
+pub fn is_enabled() -> bool {
+    match STATE.load(Ordering::Relaxed) {
+        ENABLED => true,
+        DISABLED => false,
+        #[cfg(not(feature = "std"))]
+        _ => true,
+        #[cfg(feature = "std")]
+        _ => {
+            let state = if enabled_by_env() { ENABLED } else { DISABLED };
+            // Don't override a concurrent `set_enabled`.
+            match STATE.compare_exchange(UNSET, state, Ordering::Relaxed, Ordering::Relaxed) {
+                Ok(_) => state == ENABLED,
+                Err(current) => current == ENABLED,
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+fn enabled_by_env() -> bool {
+    match std::env::var("ANODIZED_CHECKS") {
+        Ok(value) => !["0", "off", "false", "no"]
+            .iter()
+            .any(|off| value.trim().eq_ignore_ascii_case(off)),
+        Err(_) => true,
+    }
+}
+
+/// Runs `f` with checks turned off on the current thread.
+///
+/// Checks in other threads, including ones spawned by `f`, are not affected.
+#[cfg(feature = "std")]
+pub fn without_checks<R>(f: impl FnOnce() -> R) -> R {
+    /// Ends the suppression even if `f` panics.
+    struct Suppression;
+
+    impl Drop for Suppression {
+        fn drop(&mut self) {
+            SUPPRESSED.with(|suppressed| suppressed.set(suppressed.get() - 1));
+        }
+    }
+
+    SUPPRESSED.with(|suppressed| suppressed.set(suppressed.get() + 1));
+    let _suppression = Suppression;
+    f()
+}
+
+/// Returns whether checks are on for the current thread.
+pub fn checks_enabled() -> bool {
+    #[cfg(feature = "std")]
+    let suppressed = SUPPRESSED.with(|suppressed| suppressed.get() > 0);
+    #[cfg(not(feature = "std"))]
+    let suppressed = false;
+    is_enabled() && !suppressed
+}
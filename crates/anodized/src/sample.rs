@@ -0,0 +1,94 @@
+//! Runtime support for sampled checks.
+
+/// Returns whether a call that reaches a sampled check runs it, i.e. whether the call is
+/// among the first `count` of every `period` calls.
+///
+/// Each condition gets its own counter. With `std`, the counter is a thread-local, so
+/// sampling needs no synchronization. Without it, the counter is shared by all threads,
+/// which makes concurrent calls skew the share of checked calls.
+#[doc(hidden)]
+#[macro_export]
+#[cfg(feature = "std")]
+macro_rules! __sample {
+    ($count:expr, $period:expr) => {{
+        $crate::__private::thread_local! {
+            static SAMPLER: $crate::__private::Sampler =
+                const { $crate::__private::Sampler::new() };
+        }
+        SAMPLER.with(|sampler| sampler.sample($count, $period))
+    }};
+}
+
+#[doc(hidden)]
+#[macro_export]
+#[cfg(not(feature = "std"))]
+macro_rules! __sample {
+    ($count:expr, $period:expr) => {{
+        static SAMPLER: $crate::__private::Sampler = $crate::__private::Sampler::new();
+        SAMPLER.sample($count, $period)
+    }};
+}
+
+#[cfg(feature = "std")]
+pub use thread_local::Sampler;
+
+#[cfg(not(feature = "std"))]
+pub use shared::Sampler;
+
+#[cfg(feature = "std")]
+mod thread_local {
+    use core::cell::Cell;
+
+    /// Counts the calls that reach a sampled check, to select the ones that run it.
+    pub struct Sampler {
+        calls: Cell<u32>,
+    }
+
+    impl Sampler {
+        #[allow(clippy::new_without_default)]
+        pub const fn new() -> Self {
+            Self {
+                calls: Cell::new(0),
+            }
+        }
+
+        /// Counts a call, and returns `true` if it's among the first `count` of every `period` calls.
+        pub fn sample(&self, count: u32, period: u32) -> bool {
+            let call = self.calls.get();
+            self.calls
+                .set(if call + 1 >= period { 0 } else { call + 1 });
+            call < count
+        }
+    }
+}
+
+#[cfg(not(feature = "std"))]
+mod shared {
+    use core::sync::atomic::{AtomicU32, Ordering};
+
+    /// Counts the calls that reach a sampled check, to select the ones that run it.
+    ///
+    /// Uses only atomic loads and stores, which all targets support.
+    pub struct Sampler {
+        calls: AtomicU32,
+    }
+
+    impl Sampler {
+        #[allow(clippy::new_without_default)]
+        pub const fn new() -> Self {
+            Self {
+                calls: AtomicU32::new(0),
+            }
+        }
+
+        /// Counts a call, and returns `true` if it's among the first `count` of every `period` calls.
+        pub fn sample(&self, count: u32, period: u32) -> bool {
+            let call = self.calls.load(Ordering::Relaxed);
+            self.calls.store(
+                if call + 1 >= period { 0 } else { call + 1 },
+                Ordering::Relaxed,
+            );
+            call < count
+        }
+    }
+}
@@ -0,0 +1,274 @@
+//! Counters of the evaluations and failures of each condition.
+//!
+//! Counting is off by default. Turn it on for a crate with `stats = true` in its
+//! `[package.metadata.anodized]`, or with the `stats` feature; add `stats-timing = true`,
+//! or the `stats-timing` feature, to also measure the time spent evaluating each condition.
+//!
+//! A condition appears in the [`report`] once it has been evaluated.
+
+use std::{
+    fmt::{self, Write as _},
+    io::Write as _,
+    string::{String, ToString},
+    sync::{
+        Mutex, MutexGuard,
+        atomic::{AtomicBool, AtomicU8, AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+    vec::Vec,
+};
+
+/// Where a condition comes from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Condition {
+    /// The module of the instrumented function.
+    pub module: &'static str,
+    /// The name of the instrumented function.
+    pub function: &'static str,
+    /// The part of the spec, e.g. `precondition`.
+    pub kind: &'static str,
+    /// The condition as it appears in the spec.
+    pub condition: &'static str,
+    /// The condition's label, if it has one.
+    pub label: Option<&'static str>,
+    /// The file of the spec.
+    pub file: &'static str,
+    /// The line of the spec.
+    pub line: u32,
+}
+
+/// The counters of a condition, kept in a static by the generated code.
+#[doc(hidden)]
+pub struct Counter {
+    condition: Condition,
+    registered: AtomicBool,
+    evaluations: AtomicU64,
+    failures: AtomicU64,
+    nanos: AtomicU64,
+    timed: AtomicBool,
+}
+
+/// All counters that have been used, in the order of their first use.
+static REGISTRY: Mutex<Vec<&'static Counter>> = Mutex::new(Vec::new());
+
+impl Counter {
+    pub const fn new(condition: Condition) -> Self {
+        Self {
+            condition,
+            registered: AtomicBool::new(false),
+            evaluations: AtomicU64::new(0),
+            failures: AtomicU64::new(0),
+            nanos: AtomicU64::new(0),
+            timed: AtomicBool::new(false),
+        }
+    }
+
+    /// Evaluates a condition and counts the result.
+    pub fn record(&'static self, condition: impl FnOnce() -> bool) -> bool {
+        let holds = condition();
+        self.count(holds);
+        holds
+    }
+
+    /// Evaluates a condition, counts the result, and adds up the time it took.
+    pub fn record_timed(&'static self, condition: impl FnOnce() -> bool) -> bool {
+        let start = Instant::now();
+        let holds = condition();
+        let nanos = u64::try_from(start.elapsed().as_nanos()).unwrap_or(u64::MAX);
+        self.timed.store(true, Ordering::Relaxed);
+        self.nanos.fetch_add(nanos, Ordering::Relaxed);
+        self.count(holds);
+        holds
+    }
+
+    fn count(&'static self, holds: bool) {
+        if !self.registered.swap(true, Ordering::Relaxed) {
+            lock_registry().push(self);
+        }
+        self.evaluations.fetch_add(1, Ordering::Relaxed);
+        if !holds {
+            self.failures.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+fn lock_registry() -> MutexGuard<'static, Vec<&'static Counter>> {
+    // The registry is only ever pushed to, so it's consistent even if a thread panicked.
+    REGISTRY
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// The counters of a condition at the time of a [`report`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry {
+    pub condition: Condition,
+    /// How many times the condition was evaluated.
+    pub evaluations: u64,
+    /// How many times the condition didn't hold.
+    pub failures: u64,
+    /// The time spent evaluating the condition, if measured.
+    pub time: Option<Duration>,
+}
+
+/// A snapshot of the counters of all conditions evaluated so far.
+///
+/// [`Display`](fmt::Display) formats the report as text, one condition per line.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Report {
+    /// The entries, in order of the source location of the conditions.
+    pub entries: Vec<Entry>,
+}
+
+/// Takes a snapshot of the counters.
+pub fn report() -> Report {
+    let mut entries: Vec<Entry> = lock_registry()
+        .iter()
+        .map(|counter| Entry {
+            condition: counter.condition,
+            evaluations: counter.evaluations.load(Ordering::Relaxed),
+            failures: counter.failures.load(Ordering::Relaxed),
+            time: counter
+                .timed
+                .load(Ordering::Relaxed)
+                .then(|| Duration::from_nanos(counter.nanos.load(Ordering::Relaxed))),
+        })
+        .collect();
+    entries.sort_by_key(|entry| (entry.condition.file, entry.condition.line));
+    Report { entries }
+}
+
+impl Report {
+    /// Formats the report as a JSON array with an object per condition.
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("[");
+        for (i, entry) in self.entries.iter().enumerate() {
+            let Condition {
+                module,
+                function,
+                kind,
+                condition,
+                label,
+                file,
+                line,
+            } = entry.condition;
+            if i > 0 {
+                json.push(',');
+            }
+            let label = label.map_or("null".to_string(), json_string);
+            let time_ns = entry
+                .time
+                .map_or("null".to_string(), |time| time.as_nanos().to_string());
+            let _ = write!(
+                json,
+                r#"{{"module":{},"function":{},"kind":{},"condition":{},"label":{label},"file":{},"line":{line},"evaluations":{},"failures":{},"time_ns":{time_ns}}}"#,
+                json_string(module),
+                json_string(function),
+                json_string(kind),
+                json_string(condition),
+                json_string(file),
+                entry.evaluations,
+                entry.failures,
+            );
+        }
+        json.push(']');
+        json
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for entry in &self.entries {
+            let Condition {
+                module,
+                function,
+                kind,
+                condition,
+                label,
+                file,
+                line,
+            } = entry.condition;
+            write!(
+                f,
+                "{file}:{line}: {module}::{function}: {kind} `{condition}`"
+            )?;
+            if let Some(label) = label {
+                write!(f, " ({label})")?;
+            }
+            write!(
+                f,
+                ": {} evaluations, {} failures",
+                entry.evaluations, entry.failures
+            )?;
+            if let Some(time) = entry.time {
+                write!(f, ", {time:?}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut json = String::with_capacity(value.len() + 2);
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c < ' ' => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// The format of a report.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// See [`Report`]'s `Display` implementation.
+    Text,
+    /// See [`Report::to_json`].
+    Json,
+}
+
+const NO_DUMP: u8 = 0;
+const DUMP_TEXT: u8 = 1;
+const DUMP_JSON: u8 = 2;
+
+static DUMP_FORMAT: AtomicU8 = AtomicU8::new(NO_DUMP);
+
+/// Prints the report to stderr when the process exits normally, in the given format.
+///
+/// The dump happens when `main` returns or `std::process::exit` is called,
+/// but not on an abort, e.g. by a panic with `panic = "abort"`.
+/// Calling this again changes the format, but only one report is printed.
+pub fn dump_at_exit(format: Format) {
+    let format = match format {
+        Format::Text => DUMP_TEXT,
+        Format::Json => DUMP_JSON,
+    };
+    if DUMP_FORMAT.swap(format, Ordering::Relaxed) == NO_DUMP {
+        unsafe extern "C" {
+            fn atexit(callback: extern "C" fn()) -> std::ffi::c_int;
+        }
+        // SAFETY: `dump` is a valid callback for the whole run of the process.
+        unsafe { atexit(dump) };
+    }
+}
+
+extern "C" fn dump() {
+    let report = report();
+    // Printing must not panic, as there's no unwinding out of the callback.
+    let _ = match DUMP_FORMAT.load(Ordering::Relaxed) {
+        DUMP_TEXT => write!(std::io::stderr(), "{report}"),
+        DUMP_JSON => writeln!(std::io::stderr(), "{}", report.to_json()),
+        _ => Ok(()),
+    };
+}
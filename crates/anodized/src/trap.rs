@@ -0,0 +1,75 @@
+//! Runtime support for the `runtime-check-and-trap` behavior.
+
+use std::{eprintln, fmt::Arguments};
+
+/// Reports a violation, then breaks into the debugger if one is attached, otherwise panics.
+///
+/// The report names the location of the call if the check blames the caller, and a panic
+/// reports it by itself.
+#[track_caller]
+pub fn trap_or_panic(violation: Arguments) {
+    if is_debugger_attached() {
+        eprintln!("{violation}");
+        breakpoint();
+    } else {
+        panic!("{violation}");
+    }
+}
+
+/// Reports a violation, then breaks into the debugger if one is attached.
+pub fn trap_or_print(violation: Arguments) {
+    eprintln!("{violation}");
+    if is_debugger_attached() {
+        breakpoint();
+    }
+}
+
+/// Returns `true` if a debugger is attached to this process and can be trapped into.
+///
+/// Supported on Linux, Android, and Windows running on x86, x86-64, or AArch64.
+/// Returns `false` everywhere else.
+pub fn is_debugger_attached() -> bool {
+    cfg!(any(
+        target_arch = "x86",
+        target_arch = "x86_64",
+        target_arch = "aarch64"
+    )) && has_tracer()
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn has_tracer() -> bool {
+    // A debugger attaches via `ptrace`, which shows up as a non-zero `TracerPid`.
+    std::fs::read_to_string("/proc/self/status").is_ok_and(|status| {
+        status.lines().any(|line| {
+            line.strip_prefix("TracerPid:")
+                .is_some_and(|pid| pid.trim() != "0")
+        })
+    })
+}
+
+#[cfg(windows)]
+fn has_tracer() -> bool {
+    unsafe extern "system" {
+        fn IsDebuggerPresent() -> i32;
+    }
+    // SAFETY: `IsDebuggerPresent` takes no arguments and has no preconditions.
+    unsafe { IsDebuggerPresent() != 0 }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", windows)))]
+fn has_tracer() -> bool {
+    false
+}
+
+fn breakpoint() {
+    // SAFETY: A breakpoint instruction only raises a trap, which the attached debugger handles.
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    unsafe {
+        std::arch::asm!("int3");
+    }
+    // SAFETY: As above; this is the immediate that debuggers expect on AArch64.
+    #[cfg(target_arch = "aarch64")]
+    unsafe {
+        std::arch::asm!("brk #0xf000");
+    }
+}
@@ -0,0 +1,15 @@
+use anodized::{spec, spec_proptest};
+
+#[spec(requires: x > 0)]
+#[spec_proptest]
+fn below_spec(x: u32) {}
+
+#[spec_proptest(y in 0..10u32)]
+#[spec(requires: x > 0)]
+fn unknown_argument(x: u32) {}
+
+#[spec_proptest]
+#[spec(requires: items.len() > 0)]
+fn generic<T>(items: Vec<T>) {}
+
+fn main() {}
@@ -73,7 +73,11 @@ fn func_all_conditions_fail(log: &mut Vec<&'static str>) {
     return;
 }
 
-#[cfg(feature = "runtime-check-and-print")]
+#[cfg(any(
+    feature = "runtime-check-and-print",
+    feature = "runtime-check-and-log",
+    feature = "runtime-check-and-trace"
+))]
 #[test]
 fn execution_order_print_only() {
     let mut log = Vec::new();
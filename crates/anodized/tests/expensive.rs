@@ -0,0 +1,24 @@
+use anodized::spec;
+
+#[spec(
+    #[deny]
+    requires: !values.is_empty(),
+    #[deny]
+    #[expensive]
+    requires: values.is_sorted(),
+)]
+fn median(values: &[i32]) -> i32 {
+    values[values.len() / 2]
+}
+
+#[test]
+#[should_panic(expected = "Precondition failed: ! values.is_empty()")]
+fn cheap_check_runs() {
+    median(&[]);
+}
+
+#[test]
+fn expensive_check_depends_on_build() {
+    let result = std::panic::catch_unwind(|| median(&[3, 1, 2]));
+    assert_eq!(result.is_err(), !cfg!(feature = "skip-expensive"));
+}
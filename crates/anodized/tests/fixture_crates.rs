@@ -0,0 +1,121 @@
+//! Builds crates with settings in `[package.metadata.anodized]`, which only apply to a crate
+//! of their own. Each crate is a single file in `tests/fixture_crates`.
+
+use std::{env, fs, path::Path, process::Command};
+
+/// Runs `cargo` with the given arguments on a crate made of the fixture with the given name
+/// and the given metadata, and returns its stderr if it succeeds.
+fn cargo(lib: &str, metadata: &str, args: &[&str], rustflags: &str) -> String {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(lib);
+    fs::create_dir_all(dir.join("src")).unwrap();
+    let source = fs::read_to_string(
+        manifest_dir
+            .join("tests/fixture_crates")
+            .join(format!("{lib}.rs")),
+    )
+    .unwrap();
+    // Leave an unchanged source alone, so that Cargo rebuilds the crate only for other reasons.
+    if fs::read_to_string(dir.join("src/lib.rs")).ok() != Some(source.clone()) {
+        fs::write(dir.join("src/lib.rs"), source).unwrap();
+    }
+    let manifest = format!(
+        r#"[package]
+name = "fixture"
+version = "0.0.0"
+edition = "2024"
+publish = false
+
+[dependencies]
+anodized = {{ path = {:?}, default-features = false }}
+
+[package.metadata.anodized]
+{metadata}
+
+# Keep the crate out of the workspace that its directory is in.
+[workspace]
+"#,
+        manifest_dir.display().to_string()
+    );
+    fs::write(dir.join("Cargo.toml"), manifest).unwrap();
+    // Use the versions of the workspace's dependencies, which are already downloaded.
+    let lock = manifest_dir.join("../../Cargo.lock");
+    if lock.is_file() {
+        fs::copy(lock, dir.join("Cargo.lock")).unwrap();
+    }
+
+    let output = Command::new(env!("CARGO"))
+        .args(args)
+        .current_dir(&dir)
+        .env("CARGO_TARGET_DIR", dir.join("target"))
+        .env("RUSTFLAGS", rustflags)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+    assert!(
+        output.status.success(),
+        "{}{stderr}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    stderr
+}
+
+/// A change of the runtime in the manifest takes effect without touching the source.
+#[test]
+fn manifest_change_rebuilds() {
+    let args = ["test", "--", "--nocapture"];
+    let stderr = cargo(
+        "manifest_change",
+        r#"runtime = "check-and-print""#,
+        &args,
+        "",
+    );
+    assert!(stderr.contains("Precondition failed: x > 0"), "{stderr}");
+    let stderr = cargo("manifest_change", r#"runtime = "no-check""#, &args, "");
+    assert!(!stderr.contains("Precondition failed"), "{stderr}");
+}
+
+/// Fns in `impl` blocks get no harness, with a warning, so that the crate builds for Kani.
+#[test]
+fn kani_harnesses_in_impls() {
+    let stderr = cargo(
+        "kani_harness",
+        r#"runtime = "check-and-panic"
+kani-harnesses = true"#,
+        &["build"],
+        "--cfg kani",
+    );
+    let warning =
+        "no Kani proof harness for `make`: `#[spec]` can't add one for a fn in an `impl` block";
+    assert!(
+        stderr.contains(warning),
+        "missing `{warning}` in `{stderr}`"
+    );
+    let warning = "no Kani proof harness for `scaled`: `#[spec]` can't tell from the source \
+                   file whether the fn is in an `impl` block";
+    assert!(
+        stderr.contains(warning),
+        "missing `{warning}` in `{stderr}`"
+    );
+    // Methods get no harness anyway.
+    assert!(!stderr.contains("harness for `get`"), "{stderr}");
+}
+
+/// Preconditions left out of a Flux signature are reported in the body of the fn, so that
+/// the crate builds for Flux.
+#[test]
+fn flux_notes_in_impls() {
+    let stderr = cargo(
+        "flux_notes",
+        r#"runtime = "check-and-panic"
+flux-signatures = true"#,
+        &["build"],
+        "--cfg flux",
+    );
+    let warning = "Flux signature leaves out a condition that isn't linear arithmetic over \
+                   integer arguments: `! items.is_empty()`";
+    assert!(
+        stderr.contains(warning),
+        "missing `{warning}` in `{stderr}`"
+    );
+}
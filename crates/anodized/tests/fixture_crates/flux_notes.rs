@@ -0,0 +1,16 @@
+//! Preconditions of fns in `impl` blocks that `flux-signatures` leaves out of the signature.
+
+use anodized::spec;
+
+pub struct Buffer {
+    pub items: Vec<u8>,
+}
+
+impl Buffer {
+    #[spec(
+        requires: !items.is_empty(),
+    )]
+    pub fn new(items: Vec<u8>) -> Buffer {
+        Buffer { items }
+    }
+}
@@ -0,0 +1,38 @@
+//! Specs of fns in `impl` blocks, or maybe in one, for which `kani-harnesses` adds no harness.
+
+use anodized::spec;
+
+pub struct Point {
+    pub x: i32,
+}
+
+impl Point {
+    #[spec(
+        requires: x > 0,
+        ensures: output.x == x,
+    )]
+    pub fn make(x: i32) -> Point {
+        Point { x }
+    }
+
+    #[anodized::spec(
+        ensures: *output == self.x,
+    )]
+    pub fn get(&self) -> i32 {
+        self.x
+    }
+}
+
+/// Defines an associated fn that `#[spec]` can't find in this file.
+macro_rules! define_scaled {
+    () => {
+        impl Point {
+            #[spec(requires: factor > 0)]
+            pub fn scaled(factor: i32) -> Point {
+                Point { x: factor }
+            }
+        }
+    };
+}
+
+define_scaled!();
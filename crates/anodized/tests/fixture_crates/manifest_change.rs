@@ -0,0 +1,11 @@
+use anodized::spec;
+
+#[spec(requires: x > 0)]
+pub fn positive(x: u32) -> u32 {
+    x
+}
+
+#[test]
+fn violate() {
+    positive(0);
+}
@@ -0,0 +1,75 @@
+#![cfg(feature = "runtime-check-and-log")]
+
+use std::sync::Mutex;
+
+use anodized::spec;
+use log::{Level, Log, Metadata, Record};
+
+/// Collects every record so the test can inspect what the checks reported.
+struct CapturingLogger {
+    records: Mutex<Vec<(Level, String, String)>>,
+}
+
+impl Log for CapturingLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        self.records.lock().unwrap().push((
+            record.level(),
+            record.module_path().unwrap_or_default().to_string(),
+            record.args().to_string(),
+        ));
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: CapturingLogger = CapturingLogger {
+    records: Mutex::new(Vec::new()),
+};
+
+#[spec(
+    requires: divisor != 0,
+    ensures: *output <= dividend,
+)]
+fn checked_divide(dividend: i32, divisor: i32) -> i32 {
+    if divisor == 0 {
+        dividend + 1
+    } else {
+        dividend / divisor
+    }
+}
+
+#[test]
+fn violations_are_logged_without_panicking() {
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(log::LevelFilter::Trace);
+
+    assert_eq!(checked_divide(10, 2), 5);
+    assert!(LOGGER.records.lock().unwrap().is_empty());
+
+    let line = line!() + 1;
+    let result = checked_divide(10, 0);
+    assert_eq!(result, 11);
+
+    // A violated precondition names the location of the call.
+    let caller = format!("{}:{line}:18", file!());
+    let records = LOGGER.records.lock().unwrap();
+    assert_eq!(
+        *records,
+        [
+            (
+                Level::Error,
+                "log_backend".to_string(),
+                format!("Precondition failed: divisor != 0, called at {caller}"),
+            ),
+            (
+                Level::Error,
+                "log_backend".to_string(),
+                "Postcondition failed: | output | * output <= dividend".to_string(),
+            ),
+        ]
+    );
+}
@@ -0,0 +1,40 @@
+//! Specs in a `no_std` crate: the generated code only needs `core`, except that
+//! `check-and-print` reports with `eprintln!` unless `print-fn` is set, see `print_fn.rs`.
+#![no_std]
+#![cfg(not(feature = "runtime-check-and-print"))]
+
+extern crate std;
+
+use anodized::spec;
+
+#[spec(
+    requires: divisor != 0,
+    captures: dividend as original,
+    #[sample(1 in 2)]
+    ensures: *output * divisor <= original,
+)]
+fn divide(dividend: u32, divisor: u32) -> u32 {
+    dividend / divisor
+}
+
+#[spec]
+trait Counter {
+    #[spec(
+        ensures: *output > 0,
+    )]
+    fn next(&mut self) -> u32;
+}
+
+#[spec]
+impl Counter for u32 {
+    fn next(&mut self) -> u32 {
+        *self += 1;
+        *self
+    }
+}
+
+#[test]
+fn specs_without_std() {
+    assert_eq!(divide(7, 2), 3);
+    assert_eq!(0u32.next(), 1);
+}
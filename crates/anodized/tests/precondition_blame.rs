@@ -0,0 +1,103 @@
+use std::{
+    env,
+    panic::{UnwindSafe, catch_unwind},
+    process::Command,
+    sync::Mutex,
+};
+
+use anodized::spec;
+
+#[spec(
+    #[deny]
+    requires: x > 0,
+    #[deny]
+    ensures: *output > 2,
+)]
+fn increment(x: i32) -> i32 {
+    x + 1
+}
+
+#[spec]
+trait Counter {
+    #[spec(
+        #[deny]
+        requires: by > 0,
+    )]
+    fn add(&mut self, by: u32);
+}
+
+#[spec]
+impl Counter for u32 {
+    fn add(&mut self, by: u32) {
+        *self += by;
+    }
+}
+
+#[spec(
+    mode: print,
+    requires: x > 0,
+)]
+fn printed(x: i32) -> i32 {
+    x
+}
+
+/// Violates the precondition of `printed`, and returns the line of the call.
+fn violate_printed() -> u32 {
+    printed(0);
+    line!() - 1
+}
+
+static PANIC_LINE: Mutex<Option<u32>> = Mutex::new(None);
+
+/// Runs `f` and returns the line of the panic it raised, if in this file.
+fn panic_line<T>(f: impl FnOnce() -> T + UnwindSafe) -> Option<u32> {
+    *PANIC_LINE.lock().unwrap() = None;
+    assert!(catch_unwind(f).is_err());
+    PANIC_LINE.lock().unwrap().take()
+}
+
+// The panic hook is global, so the cases are in a single test.
+#[test]
+fn blame() {
+    std::panic::set_hook(Box::new(|info| {
+        let location = info.location().unwrap();
+        if location.file() == file!() {
+            *PANIC_LINE.lock().unwrap() = Some(location.line());
+        }
+    }));
+
+    // A violated precondition blames the caller.
+    assert_eq!(panic_line(|| increment(0)), Some(line!()));
+    assert_eq!(panic_line(|| 0u32.add(0)), Some(line!()));
+
+    // A violated postcondition blames the callee, i.e. its spec.
+    let line = panic_line(|| increment(1)).unwrap();
+    assert!((8..=12).contains(&line), "{line}");
+
+    let _ = std::panic::take_hook();
+}
+
+/// Set in the process that runs [`report_names_the_caller`] to print to the real stderr.
+const CHILD: &str = "ANODIZED_BLAME_CHILD";
+
+// Reports are printed to stderr, so the test runs itself in a child process to read them.
+#[test]
+fn report_names_the_caller() {
+    let line = violate_printed();
+    if env::var_os(CHILD).is_some() {
+        return;
+    }
+
+    let output = Command::new(env::current_exe().unwrap())
+        .args(["--exact", "report_names_the_caller", "--nocapture"])
+        .env(CHILD, "1")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let expected = format!("Precondition failed: x > 0, called at {}:{line}:", file!());
+    assert!(
+        stderr.contains(&expected),
+        "missing `{expected}` in `{stderr}`"
+    );
+}
@@ -0,0 +1,54 @@
+//! `print-fn` end to end: builds and tests a `no_std` crate that selects it in its manifest,
+//! which only applies to a crate of its own.
+
+use std::{env, fs, path::Path, process::Command};
+
+#[test]
+fn no_std_crate_with_print_fn() {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("print_fn");
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::copy(
+        manifest_dir.join("tests/print_fn/lib.rs"),
+        dir.join("src/lib.rs"),
+    )
+    .unwrap();
+    let manifest = format!(
+        r#"[package]
+name = "print-fn"
+version = "0.0.0"
+edition = "2024"
+publish = false
+
+[dependencies]
+anodized = {{ path = {:?}, default-features = false }}
+
+[package.metadata.anodized]
+runtime = "check-and-print"
+print-fn = "crate::report"
+
+# Keep the crate out of the workspace that its directory is in.
+[workspace]
+"#,
+        manifest_dir.display().to_string()
+    );
+    fs::write(dir.join("Cargo.toml"), manifest).unwrap();
+    // Use the versions of the workspace's dependencies, which are already downloaded.
+    let lock = manifest_dir.join("../../Cargo.lock");
+    if lock.is_file() {
+        fs::copy(lock, dir.join("Cargo.lock")).unwrap();
+    }
+
+    let output = Command::new(env!("CARGO"))
+        .arg("test")
+        .current_dir(&dir)
+        .env("CARGO_TARGET_DIR", dir.join("target"))
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
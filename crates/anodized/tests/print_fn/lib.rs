@@ -0,0 +1,43 @@
+//! A `no_std` crate that reports violations with its own function, selected with `print-fn`.
+//! Without `#[macro_use]`, `eprintln!` isn't in scope, so only `print-fn` compiles.
+#![no_std]
+
+extern crate std;
+
+use core::fmt::Arguments;
+use std::{string::String, sync::Mutex, vec::Vec};
+
+use anodized::spec;
+
+/// The reported violations, in order.
+static REPORTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+pub fn report(violation: Arguments) {
+    REPORTS.lock().unwrap().push(std::format!("{violation}"));
+}
+
+#[spec(
+    requires: divisor != 0,
+    ensures: *output <= dividend,
+)]
+pub fn divide(dividend: u32, divisor: u32) -> u32 {
+    dividend.checked_div(divisor).unwrap_or(dividend + 1)
+}
+
+#[test]
+fn violations_are_reported_with_print_fn() {
+    assert_eq!(divide(7, 2), 3);
+    assert!(REPORTS.lock().unwrap().is_empty());
+
+    assert_eq!(divide(7, 0), 8);
+    let reports = REPORTS.lock().unwrap();
+    assert_eq!(reports.len(), 2, "{reports:?}");
+    assert!(
+        reports[0].starts_with("Precondition failed: divisor != 0, called at src/lib.rs:"),
+        "{reports:?}"
+    );
+    assert_eq!(
+        reports[1],
+        "Postcondition failed: | output | * output <= dividend"
+    );
+}
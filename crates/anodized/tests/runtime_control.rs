@@ -0,0 +1,42 @@
+use std::panic::catch_unwind;
+
+use anodized::{runtime, spec, without_checks};
+
+#[spec(
+    mode: panic,
+    requires: x > 0,
+)]
+fn positive(x: i32) -> i32 {
+    x
+}
+
+#[spec(
+    #[deny]
+    requires: x > 0,
+)]
+fn always_positive(x: i32) -> i32 {
+    x
+}
+
+// The switches are global, so they're exercised by a single test.
+#[test]
+fn switches() {
+    // Read on the first check.
+    unsafe { std::env::set_var("ANODIZED_CHECKS", "off") };
+    assert_eq!(positive(0), 0);
+    assert!(!runtime::is_enabled());
+
+    runtime::set_enabled(true);
+    assert!(catch_unwind(|| positive(0)).is_err());
+
+    assert_eq!(without_checks(|| positive(0)), 0);
+    assert!(catch_unwind(|| without_checks(|| always_positive(0))).is_err());
+
+    // A panic ends the suppression.
+    assert!(catch_unwind(|| without_checks(|| panic!())).is_err());
+    assert!(catch_unwind(|| positive(0)).is_err());
+
+    runtime::set_enabled(false);
+    assert_eq!(positive(0), 0);
+    assert!(catch_unwind(|| always_positive(0)).is_err());
+}
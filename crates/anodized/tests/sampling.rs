@@ -0,0 +1,21 @@
+use std::panic::catch_unwind;
+
+use anodized::spec;
+
+#[spec(
+    #[deny]
+    #[sample(1 in 3)]
+    requires: x > 0,
+)]
+fn sampled(x: i32) -> i32 {
+    x
+}
+
+#[test]
+fn checks_a_sample_of_calls() {
+    // The first call of every three is checked, on each thread separately.
+    let checked: Vec<bool> = (0..7)
+        .map(|_| catch_unwind(|| sampled(0)).is_err())
+        .collect();
+    assert_eq!(checked, [true, false, false, true, false, false, true]);
+}
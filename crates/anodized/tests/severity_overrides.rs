@@ -0,0 +1,50 @@
+use anodized::spec;
+
+#[spec(
+    #[deny]
+    requires: divisor != 0,
+    #[warn]
+    requires: dividend >= 0,
+)]
+fn divide(dividend: i32, divisor: i32) -> i32 {
+    dividend / divisor
+}
+
+#[spec(
+    mode: print,
+    requires: x > 0,
+    ensures: *output > x,
+)]
+fn double(x: i32) -> i32 {
+    x * 2
+}
+
+#[spec(
+    mode: panic,
+    requires: x > 0,
+)]
+fn strict(x: i32) -> i32 {
+    x
+}
+
+#[test]
+fn warn_does_not_panic() {
+    assert_eq!(divide(-6, 3), -2);
+}
+
+#[test]
+#[should_panic(expected = "Precondition failed: divisor != 0")]
+fn deny_panics() {
+    divide(6, 0);
+}
+
+#[test]
+fn mode_print_does_not_panic() {
+    assert_eq!(double(-1), -2);
+}
+
+#[test]
+#[should_panic(expected = "Precondition failed: x > 0")]
+fn mode_panic_panics() {
+    strict(0);
+}
@@ -0,0 +1,49 @@
+use anodized::{spec, spec_proptest};
+
+#[spec_proptest]
+#[spec(
+    requires: divisor != 0,
+    ensures: *output <= dividend,
+)]
+fn divide(dividend: u32, divisor: u32) -> u32 {
+    dividend / divisor
+}
+
+#[spec_proptest(amount in 0..=1_000u64)]
+#[spec(
+    requires: *balance >= amount,
+    captures: *balance as initial,
+    ensures: *balance == initial - amount,
+)]
+fn withdraw(balance: &mut u64, amount: u64) {
+    *balance -= amount;
+}
+
+#[spec_proptest(text in "[a-z]{0,8}")]
+#[spec(
+    ensures: output.len() == text.len(),
+)]
+fn shout(text: &str) -> String {
+    text.to_uppercase()
+}
+
+#[spec_proptest(
+    #[should_panic(expected = "minimal failing input: byte = 10")]
+)]
+#[spec(
+    #[label = "single digit"]
+    ensures: *output < 10,
+)]
+fn identity(byte: u8) -> u8 {
+    byte
+}
+
+#[spec_proptest(
+    #[should_panic(expected = "few generated inputs satisfy the preconditions of `unlikely`")]
+)]
+#[spec(
+    requires: x == 123_456_789,
+)]
+fn unlikely(x: u64) -> u64 {
+    x
+}
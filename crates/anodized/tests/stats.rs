@@ -0,0 +1,60 @@
+#![cfg(all(feature = "stats", not(feature = "runtime-no-check")))]
+
+use anodized::{spec, stats};
+
+#[spec(
+    #[warn]
+    #[label = "positive"]
+    requires: x > 0,
+    ensures: *output == x,
+)]
+fn identity(x: i32) -> i32 {
+    x
+}
+
+#[test]
+fn counts_evaluations_and_failures() {
+    for x in [-1, 1, 2] {
+        identity(x);
+    }
+
+    let report = stats::report();
+    let entries: Vec<_> = report
+        .entries
+        .iter()
+        .filter(|entry| entry.condition.function == "identity")
+        .collect();
+    assert_eq!(entries.len(), 2);
+
+    let precondition = entries
+        .iter()
+        .find(|entry| entry.condition.kind == "precondition")
+        .unwrap();
+    assert_eq!(precondition.condition.condition, "x > 0");
+    assert_eq!(precondition.condition.label, Some("positive"));
+    assert_eq!(precondition.condition.module, "stats");
+    assert_eq!(precondition.evaluations, 3);
+    assert_eq!(precondition.failures, 1);
+    assert_eq!(precondition.time.is_some(), cfg!(feature = "stats-timing"));
+
+    let postcondition = entries
+        .iter()
+        .find(|entry| entry.condition.kind == "postcondition")
+        .unwrap();
+    assert_eq!(postcondition.evaluations, 3);
+    assert_eq!(postcondition.failures, 0);
+
+    let text = report.to_string();
+    assert!(
+        text.contains(
+            "stats::identity: precondition `x > 0` (positive): 3 evaluations, 1 failures"
+        )
+    );
+
+    let json = report.to_json();
+    assert!(json.starts_with('[') && json.ends_with(']'));
+    assert!(json.contains(
+        r#""function":"identity","kind":"precondition","condition":"x > 0","label":"positive""#
+    ));
+    assert!(json.contains(r#""evaluations":3,"failures":1"#));
+}
@@ -0,0 +1,196 @@
+#![cfg(feature = "runtime-check-and-trace")]
+
+use std::{
+    collections::BTreeMap,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use anodized::spec;
+use tracing::{
+    Event, Metadata, Subscriber,
+    field::{Field, Visit},
+    span::{Attributes, Id, Record},
+};
+
+type Fields = BTreeMap<String, String>;
+
+/// Collects the fields of every event and span so the test can inspect them.
+#[derive(Default)]
+struct Captured {
+    next_span_id: AtomicU64,
+    spans: Mutex<BTreeMap<u64, Fields>>,
+    /// The spans entered and not yet exited, innermost last.
+    entered: Mutex<Vec<u64>>,
+    /// Each event's fields, paired with the fields of its parent span, if any.
+    events: Mutex<Vec<(Fields, Option<Fields>)>>,
+}
+
+#[derive(Clone, Default)]
+struct CapturingSubscriber(Arc<Captured>);
+
+struct FieldVisitor<'a>(&'a mut Fields);
+
+impl Visit for FieldVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{value:?}"));
+    }
+}
+
+impl Subscriber for CapturingSubscriber {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let id = self.0.next_span_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let mut fields = Fields::new();
+        span.record(&mut FieldVisitor(&mut fields));
+        self.0.spans.lock().unwrap().insert(id, fields);
+        Id::from_u64(id)
+    }
+
+    fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = Fields::new();
+        event.record(&mut FieldVisitor(&mut fields));
+        let parent = if event.is_contextual() {
+            self.0.entered.lock().unwrap().last().copied()
+        } else {
+            event.parent().map(Id::into_u64)
+        };
+        let parent = parent.and_then(|id| self.0.spans.lock().unwrap().get(&id).cloned());
+        self.0.events.lock().unwrap().push((fields, parent));
+    }
+
+    fn enter(&self, span: &Id) {
+        self.0.entered.lock().unwrap().push(span.into_u64());
+    }
+
+    fn exit(&self, span: &Id) {
+        let mut entered = self.0.entered.lock().unwrap();
+        if let Some(index) = entered.iter().rposition(|id| *id == span.into_u64()) {
+            entered.remove(index);
+        }
+    }
+}
+
+#[spec(
+    #[label = "cannot divide by zero"]
+    requires: divisor != 0,
+    ensures: *output <= dividend,
+)]
+fn checked_divide(dividend: i32, divisor: i32) -> i32 {
+    if divisor == 0 {
+        dividend + 1
+    } else {
+        dividend / divisor
+    }
+}
+
+#[spec(requires: value > 0)]
+fn log_in_body(value: i32) -> i32 {
+    tracing::info!("in body");
+    value
+}
+
+#[spec(requires: value > 0)]
+async fn log_in_async_body(value: i32) -> i32 {
+    tracing::info!("in async body");
+    value
+}
+
+fn fields(pairs: &[(&str, &str)]) -> Fields {
+    pairs
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+#[test]
+fn violations_are_traced_without_panicking() {
+    let subscriber = CapturingSubscriber::default();
+
+    let line = tracing::subscriber::with_default(subscriber.clone(), || {
+        assert_eq!(checked_divide(10, 2), 5);
+        let line = line!() + 1;
+        let result = checked_divide(10, 0);
+        assert_eq!(result, 11);
+        line
+    });
+    // A violated precondition names the location of the call.
+    let message = format!(
+        "Precondition failed: cannot divide by zero: divisor != 0, called at {}:{line}:22",
+        file!()
+    );
+
+    let parent = if cfg!(feature = "trace-spans") {
+        Some(fields(&[("function", "checked_divide")]))
+    } else {
+        None
+    };
+
+    let events = subscriber.0.events.lock().unwrap();
+    assert_eq!(
+        *events,
+        [
+            (
+                fields(&[
+                    ("function", "checked_divide"),
+                    ("kind", "precondition"),
+                    ("condition", "divisor != 0"),
+                    ("label", "cannot divide by zero"),
+                    ("message", &message),
+                ]),
+                parent.clone(),
+            ),
+            (
+                fields(&[
+                    ("function", "checked_divide"),
+                    ("kind", "postcondition"),
+                    ("condition", "| output | * output <= dividend"),
+                    (
+                        "message",
+                        "Postcondition failed: | output | * output <= dividend"
+                    ),
+                ]),
+                parent,
+            ),
+        ]
+    );
+}
+
+#[test]
+fn body_events_are_in_the_span_of_the_call() {
+    let subscriber = CapturingSubscriber::default();
+
+    tracing::subscriber::with_default(subscriber.clone(), || {
+        assert_eq!(log_in_body(1), 1);
+        assert_eq!(pollster::block_on(log_in_async_body(2)), 2);
+    });
+
+    let parent =
+        |function| cfg!(feature = "trace-spans").then(|| fields(&[("function", function)]));
+
+    let events = subscriber.0.events.lock().unwrap();
+    assert_eq!(
+        *events,
+        [
+            (fields(&[("message", "in body")]), parent("log_in_body")),
+            (
+                fields(&[("message", "in async body")]),
+                parent("log_in_async_body")
+            ),
+        ]
+    );
+}
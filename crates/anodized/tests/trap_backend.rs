@@ -0,0 +1,26 @@
+#![cfg(feature = "runtime-check-and-trap")]
+
+use anodized::spec;
+
+#[spec(
+    requires: divisor != 0,
+)]
+fn checked_divide(dividend: i32, divisor: i32) -> i32 {
+    dividend / divisor
+}
+
+#[test]
+fn no_debugger_under_test() {
+    assert!(!anodized::is_debugger_attached());
+}
+
+#[test]
+fn divide_success() {
+    assert_eq!(checked_divide(10, 2), 5);
+}
+
+#[test]
+#[should_panic(expected = "Precondition failed: divisor != 0")]
+fn falls_back_to_panic_without_debugger() {
+    checked_divide(10, 0);
+}